@@ -0,0 +1,244 @@
+//! Memory of the arena tree against the boxed layout it replaced, where children were owned
+//! through `Box<Node>` and `Vec<Node>`. Run with `cargo bench --bench memory`.
+//!
+//! Both trees are copies of the parsed one, so only the allocations held by a tree are counted.
+//! Parsing makes more of them, they are shown for the arena, where the boxed parser is gone
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use esl::{
+    fuzzing::generate::Generator,
+    parser::{Parser, nodes},
+    source::FileId,
+    tokenizer::Tokenizer,
+};
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        LIVE_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        LIVE_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Allocations made by `f`, and the bytes still held by its result
+fn measure<T>(f: impl FnOnce() -> T) -> (T, usize, usize) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let live = LIVE_BYTES.load(Ordering::Relaxed);
+    let result = f();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let bytes = LIVE_BYTES.load(Ordering::Relaxed) - live;
+    (result, allocations, bytes)
+}
+
+/// The tree before the arena, every kind owns its children
+mod boxed {
+    // the tree is only built to be measured, nothing reads it
+    #![allow(dead_code)]
+
+    use esl::{
+        parser::{
+            ast::{Ast, NodeId},
+            bigint::BigUint,
+            errors::ParsingError,
+            expressions::Operation as ArenaOperation,
+            nodes::NodeKind as ArenaKind,
+            primitives::{BooleanLiteral, DontCare, FloatingLiteral, Identifier, StringLiteral},
+            types::TypeExpr as ArenaType,
+        },
+        source::Span,
+    };
+
+    pub struct Node {
+        pub kind: NodeKind,
+        pub range: Span,
+    }
+
+    pub enum NodeKind {
+        IntegerLiteral { value: BigUint, error: Option<Box<Node>> },
+        FloatingLiteral(FloatingLiteral),
+        StringLiteral(StringLiteral),
+        BooleanLiteral(BooleanLiteral),
+        Identifier(Identifier),
+        DontCare(DontCare),
+        Error(ParsingError),
+        Operation(Operation),
+        Expression { rpn: Vec<Node> },
+        ValueDeclaration {
+            declaration: Box<Node>,
+            type_hint: Option<Box<Node>>,
+            assigned_value: Box<Node>,
+            mutable: bool,
+            keyword: Span,
+            constant: bool,
+            errors: Vec<Node>,
+        },
+        Block { items: Vec<Node>, returns_last: bool, docs: Vec<Span> },
+        Assignment { target: Box<Node>, value: Box<Node>, operation: Option<Operation> },
+        Lambda { args: Vec<LambdaArg>, body: Box<Node> },
+        Type(TypeExpr),
+    }
+
+    pub enum Operation {
+        /// one of the operators without arguments, only the size matters
+        Operator(u8),
+        FuncCall { args: Vec<Node> },
+        PartialCall { args: Vec<Node> },
+        ValueCtor { args: Vec<Node> },
+        TypeCtor { args: Vec<Node> },
+    }
+
+    pub struct LambdaArg {
+        name: Box<Node>,
+        type_hint: Option<Box<Node>>,
+        default_value: Option<Box<Node>>,
+    }
+
+    pub enum TypeExpr {
+        Named { name: Box<Node>, args: Vec<Node> },
+        Function { params: Vec<Node>, ret: Box<Node> },
+        Tuple { items: Vec<Node> },
+        Optional { inner: Box<Node> },
+        Ref { inner: Box<Node> },
+    }
+
+    pub fn file(ast: &Ast) -> Vec<Node> {
+        ast.roots.iter().map(|root| node(ast, *root)).collect()
+    }
+
+    fn node(ast: &Ast, id: NodeId) -> Node {
+        let one = |id: NodeId| Box::new(node(ast, id));
+        let many = |ids: &[NodeId]| ids.iter().map(|id| node(ast, *id)).collect::<Vec<_>>();
+        let kind = match &ast[id].kind {
+            ArenaKind::IntegerLiteral(lit) => NodeKind::IntegerLiteral {
+                value: lit.value.clone(),
+                error: lit.error.map(one),
+            },
+            ArenaKind::FloatingLiteral(lit) => NodeKind::FloatingLiteral(lit.clone()),
+            ArenaKind::StringLiteral(lit) => NodeKind::StringLiteral(lit.clone()),
+            ArenaKind::BooleanLiteral(lit) => NodeKind::BooleanLiteral(lit.clone()),
+            ArenaKind::Identifier(ident) => NodeKind::Identifier(ident.clone()),
+            ArenaKind::DontCare(_) => NodeKind::DontCare(DontCare {}),
+            ArenaKind::Error(err) => NodeKind::Error(err.clone()),
+            ArenaKind::Operation(op) => NodeKind::Operation(operation(op, many)),
+            ArenaKind::Expression(expr) => NodeKind::Expression { rpn: many(&expr.rpn) },
+            ArenaKind::ValueDeclaration(decl) => NodeKind::ValueDeclaration {
+                declaration: one(decl.declaration),
+                type_hint: decl.type_hint.map(one),
+                assigned_value: one(decl.assigned_value),
+                mutable: decl.mutable,
+                keyword: decl.keyword,
+                constant: decl.constant,
+                errors: many(&decl.errors),
+            },
+            ArenaKind::Block(block) => NodeKind::Block {
+                items: many(&block.items),
+                returns_last: block.returns_last,
+                docs: block.docs.clone(),
+            },
+            ArenaKind::Assignment(assignment) => NodeKind::Assignment {
+                target: one(assignment.target),
+                value: one(assignment.value),
+                operation: assignment.operation.as_ref().map(|op| operation(op, many)),
+            },
+            ArenaKind::Lambda(lambda) => NodeKind::Lambda {
+                args: lambda
+                    .args
+                    .iter()
+                    .map(|arg| LambdaArg {
+                        name: one(arg.name),
+                        type_hint: arg.type_hint.map(one),
+                        default_value: arg.default_value.map(one),
+                    })
+                    .collect(),
+                body: one(lambda.body),
+            },
+            ArenaKind::Type(ty) => NodeKind::Type(match ty {
+                ArenaType::Named { name, args } => TypeExpr::Named {
+                    name: one(*name),
+                    args: many(args),
+                },
+                ArenaType::Function { params, ret } => TypeExpr::Function {
+                    params: many(params),
+                    ret: one(*ret),
+                },
+                ArenaType::Tuple { items } => TypeExpr::Tuple { items: many(items) },
+                ArenaType::Optional { inner } => TypeExpr::Optional { inner: one(*inner) },
+                ArenaType::Ref { inner } => TypeExpr::Ref { inner: one(*inner) },
+            }),
+        };
+        Node {
+            kind,
+            range: ast[id].range,
+        }
+    }
+
+    fn operation(op: &ArenaOperation, many: impl Fn(&[NodeId]) -> Vec<Node>) -> Operation {
+        match op {
+            ArenaOperation::FuncCall { args } => Operation::FuncCall { args: many(args) },
+            ArenaOperation::PartialCall { args } => Operation::PartialCall { args: many(args) },
+            ArenaOperation::ValueCtor { args } => Operation::ValueCtor { args: many(args) },
+            ArenaOperation::TypeCtor { args } => Operation::TypeCtor { args: many(args) },
+            _ => Operation::Operator(0),
+        }
+    }
+}
+
+fn generated(programs: u64) -> String {
+    (0..programs)
+        .map(|seed| Generator::new(seed).program())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn main() {
+    println!(
+        "node size: arena {} B, boxed {} B",
+        size_of::<nodes::Node>(),
+        size_of::<boxed::Node>()
+    );
+    for programs in [100, 1_000, 10_000] {
+        let src = generated(programs);
+        let mut tokenizer = Tokenizer::new(FileId(0), src.as_bytes());
+        tokenizer.tokenize();
+
+        let (ast, parse_allocations, parse_bytes) = measure(|| {
+            let mut parser = Parser::new(FileId(0), src.as_bytes(), &tokenizer.tokens);
+            parser.p_file();
+            parser.ast
+        });
+        let (arena, arena_allocations, arena_bytes) = measure(|| ast.clone());
+        let (boxed, boxed_allocations, boxed_bytes) = measure(|| boxed::file(&ast));
+        drop((arena, boxed));
+
+        println!("{programs:>6} programs {:>6} KB {:>8} nodes", src.len() / 1024, ast.len());
+        for (name, allocations, bytes) in [
+            ("arena tree", arena_allocations, arena_bytes),
+            ("boxed tree", boxed_allocations, boxed_bytes),
+            ("arena parse", parse_allocations, parse_bytes),
+        ] {
+            println!("    {name:<12} {allocations:>8} allocations {:>8} KB", bytes / 1024);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Tokenizer and parser throughput over large generated inputs.
+//! Run with `cargo bench`, pass a name filter to run only some inputs.
+//!
+//! Only the current tree is measured. To compare with an older layout, copy this file into a
+//! checkout of the older revision and run it there too, the inputs are the same
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use esl::{parser::Parser, source::FileId, tokenizer::Tokenizer};
+
+/// Each input is measured for at least that long, the best run is reported
+const MEASURE_TIME: Duration = Duration::from_secs(2);
+
+type Input = (&'static str, fn() -> Vec<u8>);
+
+fn statements(count: usize) -> Vec<u8> {
+    let mut src = String::from("{\n");
+    for i in 0..count {
+        src.push_str(&format!(
+            "    let a{i}: int = f(x, y + {i}) * (b - c) / 3;\n\
+             \x20   var s{i} = (p, q) -> {{ p + q * 2 }};\n\
+             \x20   s{i} += g(a{i}, [1, 2], {{ k }})?.field;\n"
+        ));
+    }
+    src.push_str("}\n");
+    src.into_bytes()
+}
+
+fn nested_braces(depth: usize, repeat: usize) -> Vec<u8> {
+    let mut src = String::from("{\n");
+    for _ in 0..repeat {
+        src.push_str("    x = ");
+        src.push_str(&"(1 + ".repeat(depth));
+        src.push('2');
+        src.push_str(&")".repeat(depth));
+        src.push_str(";\n");
+    }
+    src.push_str("}\n");
+    src.into_bytes()
+}
+
+fn long_expressions(operands: usize, repeat: usize) -> Vec<u8> {
+    let mut src = String::from("{\n");
+    for i in 0..repeat {
+        src.push_str(&format!("    let e{i} = a"));
+        for j in 0..operands {
+            src.push_str(&format!(" + b{j} * 0x{j:X} << c.d"));
+        }
+        src.push_str(";\n");
+    }
+    src.push_str("}\n");
+    src.into_bytes()
+}
+
+fn best_time(mut f: impl FnMut()) -> Duration {
+    let started = Instant::now();
+    let mut best = Duration::MAX;
+    while started.elapsed() < MEASURE_TIME {
+        let run = Instant::now();
+        f();
+        best = best.min(run.elapsed());
+    }
+    best
+}
+
+fn throughput(bytes: usize, time: Duration) -> String {
+    format!("{:>8.1} MB/s", bytes as f64 / time.as_secs_f64() / 1e6)
+}
+
+fn bench(name: &str, src: &[u8]) {
+    let tokenize = best_time(|| {
+        let mut tokenizer = Tokenizer::new(FileId(0), black_box(src));
+        tokenizer.tokenize();
+        black_box(tokenizer.tokens);
+    });
+
+    let mut tokenizer = Tokenizer::new(FileId(0), src);
+    tokenizer.tokenize();
+    let parse = best_time(|| {
+        let mut parser = Parser::new(FileId(0), black_box(src), &tokenizer.tokens);
+        parser.p_file();
+        black_box(parser.ast);
+    });
+
+    println!(
+        "{name:<18} {:>7} KB   tokenize {}   parse {}",
+        src.len() / 1024,
+        throughput(src.len(), tokenize),
+        throughput(src.len(), parse),
+    );
+}
+
+fn main() {
+    // `cargo bench` passes `--bench`, everything else is a filter
+    let filters = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect::<Vec<_>>();
+    let inputs: [Input; 3] = [
+        ("statements", || statements(20_000)),
+        ("nested_braces", || nested_braces(200, 500)),
+        ("long_expressions", || long_expressions(2_000, 50)),
+    ];
+    for (name, input) in inputs {
+        if filters.is_empty() || filters.iter().any(|f| name.contains(f.as_str())) {
+            bench(name, &input());
+        }
+    }
+}
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Tokenizer + parser over arbitrary bytes: no panics, spans are valid, in bounds and don't overlap
+fuzz_target!(|data: &[u8]| {
+    esl::fuzzing::check(data);
+});
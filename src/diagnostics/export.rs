@@ -0,0 +1,299 @@
+use super::{Diagnostic, Label, Severity, Suggestion};
+use crate::source::{SourceMap, Span};
+
+/// Version of the `--format json` schema, bump on any breaking change
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+const SARIF_VERSION: &str = "2.1.0";
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Minimal JSON value, objects keep insertion order so output is stable
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(u64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    pub fn str(s: impl Into<String>) -> Self {
+        Json::String(s.into())
+    }
+    pub fn num(n: usize) -> Self {
+        Json::Number(n as u64)
+    }
+}
+
+impl std::fmt::Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{b}"),
+            Json::Number(n) => write!(f, "{n}"),
+            Json::String(s) => write_json_string(f, s),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_json_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_json_string(f: &mut std::fmt::Formatter<'_>, s: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/*************************************************
+ *                     JSON                      *
+ *************************************************/
+
+/// `esl check --format json`, lines and columns are 1-based
+pub fn to_json(sources: &SourceMap, diags: &[Diagnostic]) -> Json {
+    Json::Object(vec![
+        ("version", Json::num(JSON_SCHEMA_VERSION as usize)),
+        (
+            "diagnostics",
+            Json::Array(diags.iter().map(|d| json_diagnostic(sources, d)).collect()),
+        ),
+    ])
+}
+
+fn json_diagnostic(sources: &SourceMap, diag: &Diagnostic) -> Json {
+    Json::Object(vec![
+        ("severity", Json::str(diag.severity.as_str())),
+        ("code", Json::str(diag.code)),
+        ("message", Json::str(&diag.message)),
+        ("file", Json::str(&sources.get(diag.primary.span.file).name)),
+        ("span", json_span(sources, diag.primary.span)),
+        (
+            "labels",
+            Json::Array(
+                std::iter::once((&diag.primary, true))
+                    .chain(diag.secondary.iter().map(|l| (l, false)))
+                    .map(|(label, primary)| json_label(sources, label, primary))
+                    .collect(),
+            ),
+        ),
+        (
+            "notes",
+            Json::Array(diag.notes.iter().map(Json::str).collect()),
+        ),
+        ("help", diag.help.as_ref().map_or(Json::Null, Json::str)),
+        (
+            "suggestions",
+            Json::Array(
+                diag.suggestions
+                    .iter()
+                    .map(|s| json_suggestion(sources, s))
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+fn json_suggestion(sources: &SourceMap, suggestion: &Suggestion) -> Json {
+    Json::Object(vec![
+        ("message", Json::str(&suggestion.message)),
+        ("file", Json::str(&sources.get(suggestion.span.file).name)),
+        ("span", json_span(sources, suggestion.span)),
+        ("replacement", Json::str(&suggestion.replacement)),
+    ])
+}
+
+fn json_label(sources: &SourceMap, label: &Label, primary: bool) -> Json {
+    Json::Object(vec![
+        ("file", Json::str(&sources.get(label.span.file).name)),
+        ("span", json_span(sources, label.span)),
+        ("message", Json::str(&label.message)),
+        ("primary", Json::Bool(primary)),
+    ])
+}
+
+fn json_span(sources: &SourceMap, span: Span) -> Json {
+    let (start, end) = sources.lookup(span);
+    let position = |offset: usize, pos: crate::source::LineCol| {
+        Json::Object(vec![
+            ("offset", Json::num(offset)),
+            ("line", Json::num(pos.line + 1)),
+            ("column", Json::num(pos.col_utf8 + 1)),
+            ("column_utf16", Json::num(pos.col_utf16 + 1)),
+        ])
+    };
+    Json::Object(vec![
+        ("start", position(span.start, start)),
+        ("end", position(span.end, end)),
+    ])
+}
+
+/*************************************************
+ *                     SARIF                     *
+ *************************************************/
+
+/// `esl check --format sarif`, SARIF 2.1.0 log with a single run
+pub fn to_sarif(sources: &SourceMap, diags: &[Diagnostic]) -> Json {
+    let mut rules = diags.iter().map(|d| d.code).collect::<Vec<_>>();
+    rules.sort();
+    rules.dedup();
+
+    let driver = Json::Object(vec![
+        ("name", Json::str("esl")),
+        ("version", Json::str(env!("CARGO_PKG_VERSION"))),
+        (
+            "rules",
+            Json::Array(
+                rules
+                    .iter()
+                    .map(|id| Json::Object(vec![("id", Json::str(*id))]))
+                    .collect(),
+            ),
+        ),
+    ]);
+
+    let run = Json::Object(vec![
+        ("tool", Json::Object(vec![("driver", driver)])),
+        ("columnKind", Json::str("utf16CodeUnits")),
+        (
+            "results",
+            Json::Array(diags.iter().map(|d| sarif_result(sources, d)).collect()),
+        ),
+    ]);
+
+    Json::Object(vec![
+        ("$schema", Json::str(SARIF_SCHEMA)),
+        ("version", Json::str(SARIF_VERSION)),
+        ("runs", Json::Array(vec![run])),
+    ])
+}
+
+fn sarif_result(sources: &SourceMap, diag: &Diagnostic) -> Json {
+    let level = match diag.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "note",
+    };
+    let mut text = diag.message.clone();
+    for note in &diag.notes {
+        text.push_str("\nnote: ");
+        text.push_str(note);
+    }
+    if let Some(help) = &diag.help {
+        text.push_str("\nhelp: ");
+        text.push_str(help);
+    }
+
+    Json::Object(vec![
+        ("ruleId", Json::str(diag.code)),
+        ("level", Json::str(level)),
+        ("message", Json::Object(vec![("text", Json::String(text))])),
+        (
+            "locations",
+            Json::Array(vec![sarif_location(sources, &diag.primary, None)]),
+        ),
+        (
+            "relatedLocations",
+            Json::Array(
+                diag.secondary
+                    .iter()
+                    .enumerate()
+                    .map(|(i, label)| sarif_location(sources, label, Some(i)))
+                    .collect(),
+            ),
+        ),
+        (
+            "fixes",
+            Json::Array(
+                diag.suggestions
+                    .iter()
+                    .map(|s| sarif_fix(sources, s))
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+fn sarif_fix(sources: &SourceMap, suggestion: &Suggestion) -> Json {
+    let replacement = Json::Object(vec![
+        ("deletedRegion", sarif_region(sources, suggestion.span)),
+        (
+            "insertedContent",
+            Json::Object(vec![("text", Json::str(&suggestion.replacement))]),
+        ),
+    ]);
+    let change = Json::Object(vec![
+        ("artifactLocation", sarif_artifact(sources, suggestion.span)),
+        ("replacements", Json::Array(vec![replacement])),
+    ]);
+    Json::Object(vec![
+        (
+            "description",
+            Json::Object(vec![("text", Json::str(&suggestion.message))]),
+        ),
+        ("artifactChanges", Json::Array(vec![change])),
+    ])
+}
+
+fn sarif_artifact(sources: &SourceMap, span: Span) -> Json {
+    Json::Object(vec![(
+        "uri",
+        Json::str(sources.get(span.file).name.replace('\\', "/")),
+    )])
+}
+
+fn sarif_region(sources: &SourceMap, span: Span) -> Json {
+    let (start, end) = sources.lookup(span);
+    Json::Object(vec![
+        ("startLine", Json::num(start.line + 1)),
+        ("startColumn", Json::num(start.col_utf16 + 1)),
+        ("endLine", Json::num(end.line + 1)),
+        ("endColumn", Json::num(end.col_utf16 + 1)),
+    ])
+}
+
+fn sarif_location(sources: &SourceMap, label: &Label, id: Option<usize>) -> Json {
+    let physical = Json::Object(vec![
+        ("artifactLocation", sarif_artifact(sources, label.span)),
+        ("region", sarif_region(sources, label.span)),
+    ]);
+
+    let mut fields = Vec::new();
+    if let Some(id) = id {
+        fields.push(("id", Json::num(id)));
+    }
+    fields.push(("physicalLocation", physical));
+    if !label.message.is_empty() {
+        fields.push(("message", Json::Object(vec![("text", Json::str(&label.message))])));
+    }
+    Json::Object(fields)
+}
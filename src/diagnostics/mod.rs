@@ -0,0 +1,373 @@
+pub mod export;
+pub mod render;
+pub mod suggest;
+
+use crate::{
+    parser::{
+        MAX_DEPTH,
+        bigint::MAX_DIGITS,
+        errors::ParsingError,
+        ast::{Ast, NodeId},
+        visit::Visitor,
+    },
+    semantic::{
+        consts::Constants,
+        errors::{ConstError, MutabilityError, ResolveError, TypeError},
+        resolve::Resolution,
+        typeck::Typing,
+    },
+    source::Span,
+    tokenizer::{
+        MAX_NESTING,
+        error::TokenizeError,
+        token::{Token, TokenKind},
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// Edit fixing the problem, `replacement` replaces the text under the span
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub span: Span,
+    pub replacement: String,
+    /// what the edit does, like "insert `=`"
+    pub message: String,
+}
+
+/// Error or warning from any compilation stage, ready to be shown to the user
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// stable identifier, like E0101
+    pub code: &'static str,
+    pub message: String,
+    /// where the problem is
+    pub primary: Label,
+    /// related places, like the declaration of a misused variable
+    pub secondary: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+    pub suggestions: Vec<Suggestion>,
+}
+
+impl Diagnostic {
+    pub fn new(
+        severity: Severity,
+        code: &'static str,
+        message: impl Into<String>,
+        span: Span,
+    ) -> Self {
+        Self {
+            severity,
+            code,
+            message: message.into(),
+            primary: Label {
+                span,
+                message: String::new(),
+            },
+            secondary: Vec::new(),
+            notes: Vec::new(),
+            help: None,
+            suggestions: Vec::new(),
+        }
+    }
+    pub fn error(code: &'static str, message: impl Into<String>, span: Span) -> Self {
+        Self::new(Severity::Error, code, message, span)
+    }
+    pub fn warning(code: &'static str, message: impl Into<String>, span: Span) -> Self {
+        Self::new(Severity::Warning, code, message, span)
+    }
+
+    /// Text under the primary span
+    pub fn with_label(mut self, message: impl Into<String>) -> Self {
+        self.primary.message = message.into();
+        self
+    }
+    pub fn with_secondary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.secondary.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+    pub fn with_suggestion(
+        mut self,
+        span: Span,
+        message: impl Into<String>,
+        replacement: impl Into<String>,
+    ) -> Self {
+        self.suggestions.push(Suggestion {
+            span,
+            replacement: replacement.into(),
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn from_tokenize_error(err: &TokenizeError, span: Span) -> Self {
+        let diag = Self::error(err.code(), err.to_string(), span);
+        match err {
+            TokenizeError::UnterminatedString => diag
+                .with_label("string starts here")
+                .with_help("add closing `\"`"),
+            TokenizeError::InvalidAttributeName => diag.with_label("expected name after `@`"),
+            TokenizeError::UnexpectedChar(_) => diag.with_label("not a part of the language"),
+            TokenizeError::UnexpectedClosingDelimiter(_) => diag.with_label("unexpected brace"),
+            TokenizeError::UnclosedDelimiter { opened_at } => diag
+                .with_label("file ends here")
+                .with_secondary(
+                    Span::new(span.file, *opened_at..*opened_at + 1),
+                    "brace is opened here",
+                ),
+            TokenizeError::UnterminatedBlockComment => diag
+                .with_label("comment starts here")
+                .with_note("block comments can be nested, each /* needs its own */"),
+            TokenizeError::NestingTooDeep => diag
+                .with_label("the rest of the file is skipped")
+                .with_note(format!("braces can be nested at most {MAX_NESTING} levels deep")),
+        }
+    }
+
+    pub fn from_parsing_error(err: &ParsingError, span: Span) -> Self {
+        let mut diag = Self::error(err.code(), err.to_string(), span);
+        if let Some(fix) = err.fix() {
+            diag = diag.with_suggestion(fix.span(), fix.message(), fix.replacement());
+        }
+        match err {
+            ParsingError::NoOperandAfterPrefixOperator
+            | ParsingError::NoOperandAfterInfixOperation => {
+                diag.with_label("expected operand here")
+            }
+            ParsingError::NoOperandBeforePostfixOperation
+            | ParsingError::NoOperandBeforeInfixOperation => {
+                diag.with_label("expected operand before")
+            }
+            ParsingError::UnexpectedCallArgument => diag
+                .with_label("unexpected tokens")
+                .with_help("arguments should be separated with `,`"),
+            ParsingError::UnexpectedToken(m) if m.expected.is_empty() => {
+                diag.with_label("unexpected tokens")
+            }
+            ParsingError::UnexpectedToken(m) | ParsingError::ExpectedDifferentToken(m) => {
+                diag.with_label(format!("expected {}", m.expected_str()))
+            }
+            ParsingError::InvalidNumber => diag.with_label("expected digits after the prefix"),
+            ParsingError::NumberTooLong => diag
+                .with_label("too long to be read")
+                .with_note(format!("integer literals can have at most {MAX_DIGITS} digits")),
+            ParsingError::InvalidUtf8 => diag.with_label("contains invalid UTF-8 bytes"),
+            ParsingError::InvalidEscape => diag
+                .with_label("contains an unknown escape")
+                .with_help("known escapes are \\n, \\t, \\r, \\0, \\\\, \\\" and \\'"),
+            ParsingError::NestingTooDeep => diag
+                .with_label("the rest of the group is skipped")
+                .with_note(format!("expressions can be nested at most {MAX_DEPTH} levels deep")),
+            ParsingError::ExpressionAsType => diag
+                .with_label("this is a value")
+                .with_help("types are written like `int`, `List[int]`, `(int) -> bool` or `int?`"),
+            ParsingError::FunctionTypeWithoutParens => {
+                diag.with_label("parameters should be in parentheses")
+            }
+            ParsingError::EmptyTypeArguments => diag
+                .with_label("expected a type here")
+                .with_help("remove the brackets if the type takes no arguments"),
+            ParsingError::TypeArgumentsInParens => diag.with_label("use `[` and `]` here"),
+            ParsingError::MisplacedInnerDoc => diag
+                .with_label("documents nothing here")
+                .with_help("move it to the start, or use `//` for a regular comment"),
+            ParsingError::Internal(_) => diag
+                .with_label("parser failed here")
+                .with_note("this is a bug in the compiler, please report it"),
+        }
+    }
+
+    pub fn from_resolve_error(err: &ResolveError, span: Span) -> Self {
+        let diag = Self::new(err.severity(), err.code(), err.to_string(), span);
+        match err {
+            ResolveError::Unresolved { similar, .. } => {
+                let diag = diag.with_label("not found in this scope");
+                match similar {
+                    Some(name) => {
+                        diag.with_suggestion(span, format!("did you mean `{name}`?"), &**name)
+                    }
+                    None => diag,
+                }
+            }
+            ResolveError::UsedBeforeDeclaration { declared_at, .. } => diag
+                .with_label("used here")
+                .with_secondary(*declared_at, "declared here")
+                .with_help("move the declaration above the first use"),
+            ResolveError::Shadowed { previous, .. } => diag
+                .with_label("declared again here")
+                .with_secondary(*previous, "previous declaration")
+                .with_help("rename one of them if the shadowing is unintended"),
+        }
+    }
+
+    pub fn from_mutability_error(err: &MutabilityError, span: Span) -> Self {
+        let diag = Self::error(err.code(), err.to_string(), span).with_label("assigned here");
+        match err {
+            MutabilityError::AssignToLet {
+                name,
+                declared_at,
+                keyword,
+            } => diag
+                .with_secondary(*declared_at, format!("`{name}` is declared here"))
+                .with_suggestion(*keyword, format!("make `{name}` mutable"), "var"),
+            MutabilityError::AssignToArgument { name, declared_at } => diag
+                .with_secondary(*declared_at, "arguments can not be changed")
+                .with_help(format!("copy it into a variable: `var {name} = {name};`")),
+            MutabilityError::AssignToConst { name, declared_at } => diag
+                .with_secondary(*declared_at, format!("`{name}` is declared `@const` here"))
+                .with_help("`@const` values are computed once, at compile time"),
+        }
+    }
+
+    pub fn from_type_error(err: &TypeError, span: Span) -> Self {
+        let diag = Self::error(err.code(), err.to_string(), span);
+        match err {
+            TypeError::Mismatch {
+                expected,
+                found,
+                expected_at,
+            } => {
+                let diag = diag.with_label(format!("expected `{expected}`, found `{found}`"));
+                match expected_at {
+                    Some(at) => diag.with_secondary(*at, "expected because of this"),
+                    None => diag,
+                }
+            }
+            TypeError::ArgumentCount { expected, .. } => {
+                diag.with_label(format!("expected {expected} arguments"))
+            }
+            TypeError::NotCallable(_) => diag.with_label("called here"),
+            TypeError::UnsupportedOperand { found, .. } => {
+                diag.with_label(format!("this is `{found}`"))
+            }
+            TypeError::Recursive(_) => diag
+                .with_label("infinite type")
+                .with_note("a value can not be used as a part of its own type, like `x(x)`"),
+        }
+    }
+
+    pub fn from_const_error(err: &ConstError, span: Span) -> Self {
+        let diag = Self::error(err.code(), err.to_string(), span);
+        match err {
+            ConstError::Overflow { ty, .. } | ConstError::LiteralOutOfRange { ty } => {
+                let diag = diag.with_label(format!("does not fit into `{ty}`"));
+                match ty.int_type() {
+                    Some(range) => diag.with_note(format!(
+                        "`{ty}` values are between {} and {}",
+                        range.min(),
+                        range.max()
+                    )),
+                    None => diag,
+                }
+            }
+            ConstError::DivisionByZero => diag.with_label("the divisor is zero"),
+            ConstError::ShiftOutOfRange { bits, .. } => {
+                diag.with_label(format!("shift amount should be between 0 and {}", bits - 1))
+            }
+            ConstError::NegativeExponent => diag
+                .with_label("the result is not an integer")
+                .with_help("use a float base, like `2.0 ** -1`"),
+            ConstError::NotConstant { unknown } => diag
+                .with_label("computed at compile time")
+                .with_secondary(*unknown, "not known at compile time")
+                .with_help("use `let` if the value is computed at runtime"),
+        }
+    }
+}
+
+/// All tokenizer errors, including the ones nested in braces
+pub fn collect_tokenizer(tokens: &[Token], out: &mut Vec<Diagnostic>) {
+    for token in tokens {
+        if let TokenKind::Error(err) = &token.kind {
+            out.push(Diagnostic::from_tokenize_error(err, token.range));
+        }
+    }
+}
+
+struct ParserErrors<'a> {
+    out: &'a mut Vec<Diagnostic>,
+}
+
+impl Visitor<'_> for ParserErrors<'_> {
+    fn visit_error(&mut self, ast: &Ast, id: NodeId, err: &ParsingError) {
+        self.out.push(Diagnostic::from_parsing_error(err, ast[id].range));
+    }
+}
+
+/// All parser errors of the subtree
+pub fn collect_parser(ast: &Ast, id: NodeId, out: &mut Vec<Diagnostic>) {
+    ParserErrors { out }.visit_node(ast, id);
+}
+
+/// Unresolved names and shadowing warnings
+pub fn collect_resolver(resolution: &Resolution, out: &mut Vec<Diagnostic>) {
+    for (err, span) in &resolution.errors {
+        out.push(Diagnostic::from_resolve_error(err, *span));
+    }
+}
+
+/// Assignments to values which are not `var`
+pub fn collect_mutability(errors: &[(MutabilityError, Span)], out: &mut Vec<Diagnostic>) {
+    for (err, span) in errors {
+        out.push(Diagnostic::from_mutability_error(err, *span));
+    }
+}
+
+/// Type mismatches and operators applied to wrong types
+pub fn collect_types(typing: &Typing, out: &mut Vec<Diagnostic>) {
+    for (err, span) in &typing.errors {
+        out.push(Diagnostic::from_type_error(err, *span));
+    }
+}
+
+/// Overflow, division by zero and `@const` values not known at compile time
+pub fn collect_consts(constants: &Constants, out: &mut Vec<Diagnostic>) {
+    for (err, span) in &constants.errors {
+        out.push(Diagnostic::from_const_error(err, *span));
+    }
+}
+
+/// Diagnostics of both stages, ordered by position
+pub fn collect(tokens: &[Token], ast: &Ast) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+    collect_tokenizer(tokens, &mut out);
+    for root in &ast.roots {
+        collect_parser(ast, *root, &mut out);
+    }
+    out.sort_by_key(|d| (d.primary.span.file, d.primary.span.start));
+    out
+}
@@ -0,0 +1,350 @@
+use std::io::Write;
+
+use super::{Diagnostic, Label, Severity, Suggestion};
+use crate::source::{SourceFile, SourceMap};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const CYAN: &str = "\x1b[1;36m";
+const BLUE: &str = "\x1b[1;34m";
+const GREEN: &str = "\x1b[1;32m";
+
+/// Spans longer than that are shown only with their first and last lines
+const MAX_SPAN_LINES: usize = 4;
+const TAB_WIDTH: usize = 4;
+/// Lines wider than that are cropped around the first underline
+const MAX_LINE_WIDTH: usize = 120;
+const CROP_CONTEXT: usize = 20;
+
+/// Renders diagnostics as source snippets with underlined spans:
+///
+/// ```text
+/// error[E0108]: Expected `:` or `=`, found integer
+///  --> main.esl:2:10
+///   |
+/// 2 |     let x 5;
+///   |          ^ expected `:` or `=`
+/// help: insert `=`
+///   |
+/// 2 |     let x = 5;
+///   |           +
+/// ```
+pub struct Renderer {
+    pub colored: bool,
+}
+
+// Underline of a single line
+struct Underline<'a> {
+    from: usize,
+    to: usize,
+    primary: bool,
+    // only shown under the last line of the label
+    message: Option<&'a str>,
+}
+
+impl Renderer {
+    pub fn new(colored: bool) -> Self {
+        Self { colored }
+    }
+
+    pub fn render(&self, sources: &SourceMap, diag: &Diagnostic) -> String {
+        let mut out = Vec::new();
+        self.render_to(&mut out, sources, diag)
+            .expect("writing into a vec can not fail");
+        String::from_utf8_lossy(&out).into_owned()
+    }
+
+    pub fn render_all(
+        &self,
+        out: &mut impl Write,
+        sources: &SourceMap,
+        diags: &[Diagnostic],
+    ) -> std::io::Result<()> {
+        for diag in diags {
+            self.render_to(out, sources, diag)?;
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    pub fn render_to(
+        &self,
+        out: &mut impl Write,
+        sources: &SourceMap,
+        diag: &Diagnostic,
+    ) -> std::io::Result<()> {
+        let severity_color = match diag.severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+            Severity::Info => CYAN,
+        };
+        writeln!(
+            out,
+            "{}{}[{}]{}{}: {}{}",
+            self.color(severity_color),
+            diag.severity.as_str(),
+            diag.code,
+            self.color(RESET),
+            self.color(BOLD),
+            diag.message,
+            self.color(RESET),
+        )?;
+
+        // Labels are grouped by file, primary file goes first
+        let labels = std::iter::once((&diag.primary, true))
+            .chain(diag.secondary.iter().map(|l| (l, false)))
+            .collect::<Vec<_>>();
+        let mut files = Vec::new();
+        for (label, _) in &labels {
+            if !files.contains(&label.span.file) {
+                files.push(label.span.file);
+            }
+        }
+
+        let max_line = labels
+            .iter()
+            .map(|(l, _)| sources.get(l.span.file).line_index(l.span.end) + 1)
+            .max()
+            .unwrap_or(1);
+        let gutter = max_line.to_string().len();
+
+        for (i, file) in files.iter().enumerate() {
+            let file = sources.get(*file);
+            let file_labels = labels
+                .iter()
+                .filter(|(l, _)| l.span.file == file.id)
+                .copied()
+                .collect::<Vec<_>>();
+            let anchor = file_labels[0].0.span.start;
+
+            writeln!(
+                out,
+                "{}{}{}{} {}:{}",
+                " ".repeat(gutter),
+                self.color(BLUE),
+                if i == 0 { "-->" } else { ":::" },
+                self.color(RESET),
+                file.name,
+                file.line_col(anchor),
+            )?;
+            self.snippet(out, file, &file_labels, gutter)?;
+        }
+
+        for note in &diag.notes {
+            self.footer(out, gutter, "note", note)?;
+        }
+        if let Some(help) = &diag.help {
+            self.footer(out, gutter, "help", help)?;
+        }
+        for suggestion in &diag.suggestions {
+            self.suggestion(out, sources, suggestion, gutter)?;
+        }
+        Ok(())
+    }
+
+    /// Patched line with the edit marked, `+` for insertions and `~` for replacements
+    fn suggestion(
+        &self,
+        out: &mut impl Write,
+        sources: &SourceMap,
+        suggestion: &Suggestion,
+        gutter: usize,
+    ) -> std::io::Result<()> {
+        let file = sources.get(suggestion.span.file);
+        let line = file.line_index(suggestion.span.start);
+        let range = file.line_range(line);
+        let fits = file.line_index(suggestion.span.end) == line
+            && suggestion.span.end <= range.end
+            && !suggestion.replacement.contains('\n')
+            && range.len() + suggestion.replacement.len() <= MAX_LINE_WIDTH;
+        if !fits {
+            return self.footer(out, gutter, "help", &suggestion.message);
+        }
+
+        let before = String::from_utf8_lossy(&file.src[range.start..suggestion.span.start]);
+        let after = String::from_utf8_lossy(&file.src[suggestion.span.end..range.end]);
+        let patched = format!("{before}{}{after}", suggestion.replacement)
+            .replace('\t', &" ".repeat(TAB_WIDTH));
+
+        // leading spaces of the inserted text are not marked
+        let inserted = suggestion.replacement.trim_start();
+        let from = self.display_col(file, range.start, suggestion.span.start)
+            + (suggestion.replacement.len() - inserted.len());
+        let mark = if suggestion.span.is_empty() { "+" } else { "~" };
+        let gutter = gutter.max((line + 1).to_string().len());
+
+        writeln!(
+            out,
+            "{}help{}: {}",
+            self.color(BOLD),
+            self.color(RESET),
+            suggestion.message,
+        )?;
+        self.gutter_line(out, gutter, "")?;
+        writeln!(
+            out,
+            "{}{:>gutter$} |{} {patched}",
+            self.color(BLUE),
+            line + 1,
+            self.color(RESET),
+        )?;
+        self.gutter_line(
+            out,
+            gutter,
+            &format!(
+                "{}{}{}{}",
+                " ".repeat(from),
+                self.color(GREEN),
+                mark.repeat(inserted.chars().count().max(1)),
+                self.color(RESET),
+            ),
+        )
+    }
+
+    fn snippet(
+        &self,
+        out: &mut impl Write,
+        file: &SourceFile,
+        labels: &[(&Label, bool)],
+        gutter: usize,
+    ) -> std::io::Result<()> {
+        // line index -> underlines
+        let mut lines = Vec::<(usize, Vec<Underline>)>::new();
+        for (label, primary) in labels {
+            let first = file.line_index(label.span.start);
+            let last = file
+                .line_index(label.span.end.max(label.span.start + 1) - 1)
+                .max(first);
+
+            for line in first..=last {
+                if last - first >= MAX_SPAN_LINES && line > first && line < last {
+                    continue;
+                }
+                let range = file.line_range(line);
+                let from = label.span.start.max(range.start);
+                let to = label.span.end.min(range.end).max(from);
+                let underline = Underline {
+                    from: self.display_col(file, range.start, from),
+                    to: self.display_col(file, range.start, to),
+                    primary: *primary,
+                    message: (line == last).then_some(label.message.as_str()),
+                };
+                match lines.iter_mut().find(|(l, _)| *l == line) {
+                    Some((_, underlines)) => underlines.push(underline),
+                    None => lines.push((line, vec![underline])),
+                }
+            }
+        }
+        lines.sort_by_key(|(line, _)| *line);
+
+        self.gutter_line(out, gutter, "")?;
+        let mut prev_line = None;
+        for (line, underlines) in &lines {
+            if prev_line.is_some_and(|prev| prev + 1 < *line) {
+                writeln!(out, "{}...{}", self.color(BLUE), self.color(RESET))?;
+            }
+            prev_line = Some(*line);
+
+            let text = String::from_utf8_lossy(&file.src[file.line_range(*line)])
+                .replace('\t', &" ".repeat(TAB_WIDTH))
+                .chars()
+                .collect::<Vec<_>>();
+
+            // long lines are cropped around the underlines
+            let first_col = underlines.iter().map(|u| u.from).min().unwrap_or(0);
+            let crop = if text.len() > MAX_LINE_WIDTH && first_col > MAX_LINE_WIDTH / 2 {
+                first_col - CROP_CONTEXT
+            } else {
+                0
+            };
+            let shown = text.iter().skip(crop).take(MAX_LINE_WIDTH).collect::<String>();
+            let (before, after) = (
+                if crop > 0 { "..." } else { "" },
+                if crop + MAX_LINE_WIDTH < text.len() { "..." } else { "" },
+            );
+            writeln!(
+                out,
+                "{}{:>gutter$} |{} {before}{shown}{after}",
+                self.color(BLUE),
+                line + 1,
+                self.color(RESET),
+            )?;
+
+            for underline in underlines {
+                let from = (underline.from - crop.min(underline.from)) + before.len();
+                let to = (underline.to.saturating_sub(crop) + before.len())
+                    .min(before.len() + MAX_LINE_WIDTH + after.len())
+                    .max(from);
+                let (mark, color) = if underline.primary {
+                    ('^', RED)
+                } else {
+                    ('-', BLUE)
+                };
+                let marks = mark.to_string().repeat((to - from).max(1));
+                let message = underline.message.unwrap_or("");
+                self.gutter_line(
+                    out,
+                    gutter,
+                    &format!(
+                        "{}{}{} {}{}",
+                        " ".repeat(from),
+                        self.color(color),
+                        marks,
+                        message,
+                        self.color(RESET),
+                    ),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn gutter_line(&self, out: &mut impl Write, gutter: usize, text: &str) -> std::io::Result<()> {
+        let line = format!(
+            "{}{} |{} {}",
+            self.color(BLUE),
+            " ".repeat(gutter),
+            self.color(RESET),
+            text
+        );
+        writeln!(out, "{}", line.trim_end())
+    }
+
+    fn footer(
+        &self,
+        out: &mut impl Write,
+        gutter: usize,
+        kind: &str,
+        text: &str,
+    ) -> std::io::Result<()> {
+        writeln!(
+            out,
+            "{}{} ={} {}{}:{} {}",
+            self.color(BLUE),
+            " ".repeat(gutter),
+            self.color(RESET),
+            self.color(BOLD),
+            kind,
+            self.color(RESET),
+            text,
+        )
+    }
+
+    // Column on the screen, chars are counted instead of bytes and tabs are expanded
+    fn display_col(&self, file: &SourceFile, line_start: usize, offset: usize) -> usize {
+        file.src[line_start..offset]
+            .iter()
+            .map(|b| match b {
+                b'\t' => TAB_WIDTH,
+                0x80..=0xBF => 0,
+                _ => 1,
+            })
+            .sum()
+    }
+
+    fn color(&self, code: &'static str) -> &'static str {
+        if self.colored { code } else { "" }
+    }
+}
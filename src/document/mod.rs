@@ -0,0 +1,285 @@
+//! Source text of an open file with its tokens and tree, kept up to date on edits.
+//! An edit re-tokenizes and reparses only the smallest `{}` block around it,
+//! everything else is reused and only moved by the length difference
+
+use std::ops::Range;
+
+use crate::{
+    parser::{
+        Parser,
+        ast::{Ast, NodeId},
+        errors::{Fix, ParsingError},
+        nodes::NodeKind,
+        primitives::{DontCare, StringValue},
+    },
+    source::{FileId, Span, interner::Interner},
+    tokenizer::{
+        Tokenizer,
+        error::TokenizeError,
+        token::{Token, TokenKind},
+    },
+};
+
+/// What was tokenized and parsed again after an edit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reparsed {
+    /// only the block with this span (in the edited text)
+    Block(Span),
+    Full,
+}
+
+pub struct Document {
+    pub file: FileId,
+    pub src: Vec<u8>,
+    pub interner: Interner,
+    pub tokens: Vec<Token>,
+    /// Nodes of replaced blocks are reused for the reparsed ones, so the arena does not grow
+    pub ast: Ast,
+    /// unreachable slots of the arena
+    free: Vec<NodeId>,
+}
+
+impl Document {
+    pub fn new(file: FileId, src: Vec<u8>) -> Self {
+        let mut document = Self {
+            file,
+            src,
+            interner: Interner::new(),
+            tokens: Vec::new(),
+            ast: Ast::new(),
+            free: Vec::new(),
+        };
+        document.parse_all();
+        document
+    }
+
+    /// Replaces the `range` of the text with `replacement`, panics if it is out of bounds
+    pub fn edit(&mut self, range: Range<usize>, replacement: &[u8]) -> Reparsed {
+        // blocks are looked up before the text changes
+        let blocks = self.enclosing_blocks(&range);
+        self.src.splice(range.clone(), replacement.iter().copied());
+
+        let edit = Edit {
+            old_end: range.end,
+            delta: replacement.len() as isize - range.len() as isize,
+        };
+        for (open, block) in blocks {
+            if let Some(reparsed) = self.reparse_block(open, block, &edit) {
+                return reparsed;
+            }
+        }
+        self.parse_all();
+        Reparsed::Full
+    }
+
+    fn parse_all(&mut self) {
+        let interner = std::mem::take(&mut self.interner);
+        let mut tokenizer = Tokenizer::with_interner(self.file, &self.src, interner);
+        tokenizer.tokenize();
+
+        let mut parser = Parser::new(self.file, &self.src, &tokenizer.tokens);
+        parser.p_file();
+        self.ast = parser.ast;
+        self.free.clear();
+        self.tokens = tokenizer.tokens;
+        self.interner = tokenizer.interner;
+    }
+
+    /// Closed `{}` groups parsed as blocks, which contain the range, the innermost first
+    fn enclosing_blocks(&self, range: &Range<usize>) -> Vec<(usize, NodeId)> {
+        let mut blocks = Vec::new();
+        for (open, token) in self.tokens.iter().enumerate() {
+            let TokenKind::CurlyBraces { close } = token.kind else {
+                continue;
+            };
+            let close = &self.tokens[close];
+            if token.range.end <= range.start
+                && range.end <= close.range.start
+                && !close.range.is_empty()
+                && let Some(block) = find_block(&self.ast, token.range.join(close.range))
+            {
+                blocks.push((open, block));
+            }
+        }
+        blocks.reverse();
+        blocks
+    }
+
+    /// Re-tokenizes the group starting at the `open` token of the old tokens. Returns None
+    /// when it ends at a different place, so the rest of the file could change too
+    fn reparse_block(&mut self, open: usize, block: NodeId, edit: &Edit) -> Option<Reparsed> {
+        let close = self.tokens[open].kind.close()?;
+        let outer = self.tokens[..open]
+            .iter()
+            .filter(|t| t.kind.close().is_some_and(|c| c > close))
+            .filter_map(|t| closing_byte(&t.kind))
+            .collect::<Vec<_>>();
+
+        let interner = std::mem::take(&mut self.interner);
+        let mut tokenizer = Tokenizer::with_interner(self.file, &self.src, interner);
+        tokenizer.pos = self.tokens[open].range.start;
+        tokenizer.tokenize_group(&outer);
+        self.interner = tokenizer.interner;
+        let mut group = tokenizer.tokens;
+
+        let new_end = edit.shift(self.tokens[close].range.end);
+        match group.last() {
+            Some(last)
+                if matches!(last.kind, TokenKind::ClosingBrace { .. })
+                    && last.range.end == new_end
+                    && !last.range.is_empty() => {}
+            _ => return None,
+        }
+
+        // indices of the tokens after the group move by the difference of the lengths
+        let moved = group.len() as isize - (close + 1 - open) as isize;
+        let shift_index = |idx: usize| {
+            if idx > close {
+                idx.saturating_add_signed(moved)
+            } else {
+                idx
+            }
+        };
+        let (before, after) = self.tokens.split_at_mut(close + 1);
+        for token in before[..open].iter_mut().chain(after) {
+            token.range = edit.shift_span(token.range);
+            shift_token(&mut token.kind, shift_index, |offset| edit.shift(offset));
+        }
+        for token in &mut group {
+            shift_token(&mut token.kind, |idx| idx + open, |offset| offset);
+        }
+        self.tokens.splice(open..=close, group);
+
+        let mut ast = std::mem::take(&mut self.ast);
+        for doc in &mut ast.docs {
+            *doc = edit.shift_span(*doc);
+        }
+        let mut reachable = ast.roots.clone();
+        while let Some(id) = reachable.pop() {
+            reachable.extend(ast.children(id));
+            let node = &mut ast[id];
+            node.range = edit.shift_span(node.range);
+            node_spans(&mut node.kind, |span| *span = edit.shift_span(*span));
+        }
+        // chained lambdas are parsed one level deeper, like nested braces
+        let lambdas = ast
+            .ancestors(block)
+            .zip(ast.ancestors(block).skip(1))
+            .filter(|(child, parent)| {
+                matches!(&ast[*parent].kind, NodeKind::Lambda(lambda) if lambda.body == *child)
+            })
+            .count();
+
+        let len = ast.len();
+        let mut parser = Parser::with_ast(self.file, &self.src, &self.tokens, ast);
+        parser.pos = open;
+        parser.depth = outer.len() + lambdas;
+        let parsed = parser.p_block();
+        self.ast = parser.ast;
+        let Some(parsed) = parsed else {
+            self.parse_all();
+            return Some(Reparsed::Full);
+        };
+
+        // the old id is kept, so the parent and side tables still point to the block.
+        // The parsed block is the last node, only its content is moved into the old one
+        let range = self.ast[parsed].range;
+        let placeholder = NodeKind::DontCare(DontCare {});
+        let mut kind = std::mem::replace(&mut self.ast[parsed].kind, placeholder);
+        self.ast.truncate(parsed.index());
+        self.free.extend(self.ast.free_descendants(block));
+        let moved = self.ast.move_tail(len, &mut self.free);
+        kind.visit_children_mut(|child| *child = moved[child.index() - len]);
+        self.ast.set_kind(block, kind);
+        self.ast[block].range = range;
+        Some(Reparsed::Block(range))
+    }
+}
+
+// Text after the end of the edit is moved by `delta`
+struct Edit {
+    old_end: usize,
+    delta: isize,
+}
+
+impl Edit {
+    fn shift(&self, offset: usize) -> usize {
+        if offset >= self.old_end {
+            offset.saturating_add_signed(self.delta)
+        } else {
+            offset
+        }
+    }
+    fn shift_span(&self, span: Span) -> Span {
+        Span::new(span.file, self.shift(span.start)..self.shift(span.end))
+    }
+}
+
+fn closing_byte(kind: &TokenKind) -> Option<u8> {
+    match kind {
+        TokenKind::RoundBraces { .. } => Some(b')'),
+        TokenKind::SquareBraces { .. } => Some(b']'),
+        TokenKind::CurlyBraces { .. } => Some(b'}'),
+        _ => None,
+    }
+}
+
+fn shift_token(
+    kind: &mut TokenKind,
+    index: impl Fn(usize) -> usize,
+    offset: impl Fn(usize) -> usize,
+) {
+    match kind {
+        TokenKind::RoundBraces { close }
+        | TokenKind::SquareBraces { close }
+        | TokenKind::CurlyBraces { close } => *close = index(*close),
+        TokenKind::ClosingBrace { open } => *open = index(*open),
+        TokenKind::Error(TokenizeError::UnclosedDelimiter { opened_at }) => {
+            *opened_at = offset(*opened_at)
+        }
+        _ => (),
+    }
+}
+
+// Spans stored in the node itself, besides its range
+fn node_spans(kind: &mut NodeKind, mut f: impl FnMut(&mut Span)) {
+    match kind {
+        NodeKind::StringLiteral(lit) => {
+            if let StringValue::Source(span) = &mut lit.value {
+                f(span);
+            }
+        }
+        NodeKind::Block(block) => block.docs.iter_mut().for_each(f),
+        NodeKind::ValueDeclaration(decl) => f(&mut decl.keyword),
+        NodeKind::Error(
+            ParsingError::UnexpectedToken(mismatch)
+            | ParsingError::ExpectedDifferentToken(mismatch),
+        ) => match &mut mismatch.fix {
+            Some(Fix::Insert { at, .. }) => f(at),
+            Some(Fix::Keyword { span, .. }) => f(span),
+            None => (),
+        },
+        _ => (),
+    }
+}
+
+/// Block node parsed from the braces with exactly this span
+fn find_block(ast: &Ast, span: Span) -> Option<NodeId> {
+    let mut found = ast
+        .roots
+        .iter()
+        .copied()
+        .find(|id| ast[*id].range.contains(span))?;
+    loop {
+        if ast[found].range == span && matches!(ast[found].kind, NodeKind::Block(_)) {
+            return Some(found);
+        }
+        let mut next = None;
+        ast[found].visit_children(|child| {
+            if next.is_none() && ast[child].range.contains(span) {
+                next = Some(child);
+            }
+        });
+        found = next?;
+    }
+}
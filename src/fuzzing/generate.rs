@@ -0,0 +1,313 @@
+//! Random syntactically valid programs for property tests.
+//! Programs are built from the grammar, so every node kind and operation shows up,
+//! and the same seed always gives the same program
+
+/// Names which are not keywords
+const NAMES: &[&str] = &[
+    "a", "b", "c", "x", "y", "value", "items", "count", "f", "g", "make", "Point", "List", "int",
+    "u8", "i128",
+];
+const INTEGERS: &[&str] = &[
+    "0",
+    "1",
+    "42",
+    "1000000",
+    "0xFF",
+    "0x1f",
+    "0b1010",
+    "9223372036854775808",
+    "0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+];
+const FLOATS: &[&str] = &["0.5", "1.25", "3.0", "100.001"];
+const STRINGS: &[&str] = &[
+    r#""""#,
+    r#""text""#,
+    r#""line\nbreak""#,
+    r#""tab\tquote\"""#,
+    r#""héllo ✓""#,
+];
+const INFIX: &[&str] = &[
+    "+", "-", "*", "/", "%", "**", ">", ">=", "<", "<=", "!=", "==", "|", "&", "^", "<<", ">>",
+    "||", "&&", ".", "::", "..", "..=", "|>", "=>",
+];
+const PREFIX: &[&str] = &["-", "!", "ref "];
+const ASSIGN: &[&str] = &[
+    "=", "+=", "-=", "*=", "/=", "%=", "**=", "&=", "|=", "^=", "<<=", ">>=",
+];
+
+/// Nesting of expressions, lambdas and blocks is limited by that
+const MAX_DEPTH: usize = 4;
+
+pub struct Generator {
+    state: u64,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    // splitmix64, any seed works, including 0
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+    fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+
+    /// A whole file, a single top level block
+    pub fn program(&mut self) -> String {
+        let mut out = String::new();
+        self.block(&mut out, 0, "");
+        out.push('\n');
+        out
+    }
+
+    fn block(&mut self, out: &mut String, depth: usize, indent: &str) {
+        let inner = format!("{indent}    ");
+        out.push_str("{\n");
+        if self.chance(20) {
+            out.push_str(&format!("{inner}//! documents the block\n"));
+        }
+
+        let count = if depth >= MAX_DEPTH {
+            1
+        } else {
+            1 + self.below(5)
+        };
+        for i in 0..count {
+            if self.chance(10) {
+                out.push_str(&format!("{inner}// comment\n"));
+            }
+            out.push_str(&inner);
+            self.statement(out, depth + 1, &inner);
+            // the last one may be the value of the block
+            if i + 1 < count || self.chance(50) {
+                out.push(';');
+            }
+            out.push('\n');
+        }
+        out.push_str(indent);
+        out.push('}');
+    }
+
+    fn statement(&mut self, out: &mut String, depth: usize, indent: &str) {
+        match self.below(3) {
+            0 => {
+                out.push_str(match self.below(10) {
+                    0 => "@const let ",
+                    1..5 => "let ",
+                    _ => "var ",
+                });
+                out.push_str(if self.chance(10) {
+                    "_"
+                } else {
+                    self.pick(NAMES)
+                });
+                if self.chance(30) {
+                    out.push_str(": ");
+                    self.type_hint(out, depth);
+                }
+                out.push_str(" = ");
+                self.expression(out, depth, indent);
+            }
+            1 => {
+                self.expression(out, depth, indent);
+                out.push(' ');
+                out.push_str(self.pick(ASSIGN));
+                out.push(' ');
+                self.expression(out, depth, indent);
+            }
+            _ => self.expression(out, depth, indent),
+        }
+    }
+
+    fn type_hint(&mut self, out: &mut String, depth: usize) {
+        if depth < MAX_DEPTH && self.chance(10) {
+            out.push_str("ref ");
+            return self.type_hint(out, depth + 1);
+        }
+        let kinds = if depth >= MAX_DEPTH { 2 } else { 5 };
+        match self.below(kinds) {
+            0 => out.push_str(self.pick(NAMES)),
+            1 => out.push('_'),
+            2 => {
+                out.push_str(self.pick(NAMES));
+                self.type_args(out, depth);
+            }
+            // `(A)` is just `A`, so a tuple has none, two or more items, or a trailing comma
+            3 => {
+                let count = self.below(4);
+                self.type_list(out, depth, count, count == 1);
+            }
+            _ => {
+                let count = self.below(4);
+                self.type_list(out, depth, count, false);
+                out.push_str(" -> ");
+                self.type_hint(out, depth + 1);
+                return;
+            }
+        }
+        while self.chance(15) {
+            out.push('?');
+        }
+    }
+
+    fn type_args(&mut self, out: &mut String, depth: usize) {
+        out.push('[');
+        let count = self.below(3) + 1;
+        for i in 0..count {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            self.type_hint(out, depth + 1);
+        }
+        out.push(']');
+    }
+
+    fn type_list(&mut self, out: &mut String, depth: usize, count: usize, trailing_comma: bool) {
+        out.push('(');
+        for i in 0..count {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            self.type_hint(out, depth + 1);
+        }
+        if trailing_comma {
+            out.push(',');
+        }
+        out.push(')');
+    }
+
+    fn expression(&mut self, out: &mut String, depth: usize, indent: &str) {
+        // a lambda body takes everything after the arrow, so a bare lambda is a whole expression
+        if depth < MAX_DEPTH && self.chance(10) {
+            return self.lambda(out, depth, indent);
+        }
+        self.atom(out, depth, indent);
+        let infix = if depth >= MAX_DEPTH { 0 } else { self.below(4) };
+        for _ in 0..infix {
+            out.push(' ');
+            out.push_str(self.pick(INFIX));
+            out.push(' ');
+            self.atom(out, depth, indent);
+        }
+    }
+
+    fn atom(&mut self, out: &mut String, depth: usize, indent: &str) {
+        if self.chance(15) {
+            out.push_str(self.pick(PREFIX));
+        }
+        self.operand(out, depth, indent);
+        if depth >= MAX_DEPTH {
+            return;
+        }
+        while self.chance(20) {
+            match self.below(5) {
+                0 => out.push('?'),
+                1 => self.args(out, depth, indent, "(", ")", false),
+                // `_` makes a partial call
+                2 => self.args(out, depth, indent, "(", ")", true),
+                3 => self.args(out, depth, indent, "{ ", " }", false),
+                _ => self.type_args(out, depth),
+            }
+        }
+    }
+
+    fn args(
+        &mut self,
+        out: &mut String,
+        depth: usize,
+        indent: &str,
+        open: &str,
+        close: &str,
+        partial: bool,
+    ) {
+        out.push_str(open);
+        let count = self.below(4) + usize::from(partial);
+        let placeholder = self.below(count.max(1));
+        for i in 0..count {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            if partial && i == placeholder {
+                out.push('_');
+            } else {
+                self.expression(out, depth + 1, indent);
+            }
+        }
+        if count > 0 && self.chance(10) {
+            out.push(',');
+        }
+        out.push_str(close);
+    }
+
+    fn operand(&mut self, out: &mut String, depth: usize, indent: &str) {
+        let kinds = if depth >= MAX_DEPTH { 5 } else { 7 };
+        match self.below(kinds) {
+            0 => out.push_str(self.pick(NAMES)),
+            1 => out.push_str(self.pick(INTEGERS)),
+            2 => out.push_str(self.pick(FLOATS)),
+            3 => out.push_str(self.pick(STRINGS)),
+            4 => out.push_str(if self.chance(50) { "true" } else { "false" }),
+            5 => {
+                out.push('(');
+                self.expression(out, depth + 1, indent);
+                out.push(')');
+            }
+            _ => {
+                out.push('(');
+                self.lambda(out, depth + 1, indent);
+                out.push(')');
+            }
+        }
+    }
+
+    fn lambda(&mut self, out: &mut String, depth: usize, indent: &str) {
+        let count = self.below(4);
+        if count == 1 && self.chance(50) {
+            out.push_str(if self.chance(20) {
+                "_"
+            } else {
+                self.pick(NAMES)
+            });
+        } else {
+            out.push('(');
+            for i in 0..count {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                out.push_str(if self.chance(10) {
+                    "_"
+                } else {
+                    self.pick(NAMES)
+                });
+                if self.chance(30) {
+                    out.push_str(": ");
+                    self.type_hint(out, depth);
+                }
+                if self.chance(20) {
+                    out.push_str(" = ");
+                    self.expression(out, depth + 1, indent);
+                }
+            }
+            out.push(')');
+        }
+        out.push_str(" -> ");
+        if self.chance(50) {
+            self.block(out, depth + 1, indent);
+        } else {
+            self.expression(out, depth + 1, indent);
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Invariants checked by the fuzz target (`fuzz/fuzz_targets/parse.rs`) and the smoke test.
+//! Every check panics, so the fuzzer reports it as a crash
+
+pub mod generate;
+
+use crate::{
+    diagnostics::{export, render::Renderer},
+    parser::{
+        Parser,
+        ast::{Ast, NodeId},
+        export::AstFile,
+    },
+    semantic::{self, consts},
+    source::{SourceMap, Span},
+    tokenizer::{
+        Tokenizer,
+        token::{Token, TokenKind},
+    },
+};
+
+/// Runs the whole front end over arbitrary bytes
+pub fn check(data: &[u8]) {
+    let mut sources = SourceMap::new();
+    let file = sources.add_file("fuzz.esl", data.to_vec());
+    let src = &sources.get(file).src;
+
+    let mut tokenizer = Tokenizer::new(file, src);
+    tokenizer.tokenize();
+    check_tokens(&tokenizer.tokens, src.len());
+
+    let mut parser = Parser::new(file, src, &tokenizer.tokens);
+    parser.p_file();
+    assert_eq!(parser.pos, tokenizer.tokens.len(), "parser left tokens behind");
+    let ast = &parser.ast;
+    check_siblings(ast.roots.iter().map(|n| ast[*n].range));
+    for root in &ast.roots {
+        assert_eq!(ast.parent(*root), None, "root {root:?} has a parent");
+        check_node(ast, *root, src.len());
+    }
+
+    let mut tree = AstFile {
+        names: tokenizer.interner,
+        ast: parser.ast,
+    };
+    let analysis = semantic::analyze(&tokenizer.tokens, &tree, src);
+    let diags = &analysis.diagnostics;
+    let renderer = Renderer::new(true);
+    for diag in diags {
+        check_span(diag.primary.span, src.len());
+        for suggestion in &diag.suggestions {
+            check_span(suggestion.span, src.len());
+        }
+        renderer.render(&sources, diag);
+    }
+    export::to_json(&sources, diags).to_string();
+    export::to_sarif(&sources, diags).to_string();
+
+    // folding keeps the tree well formed
+    consts::fold(&mut tree, &analysis.constants);
+    for root in &tree.ast.roots {
+        check_node(&tree.ast, *root, src.len());
+    }
+}
+
+fn check_span(span: Span, len: usize) {
+    assert!(span.start <= span.end, "inverted span {span:?}");
+    assert!(span.end <= len, "span {span:?} is out of bounds ({len})");
+}
+
+// Non-empty spans should not overlap, empty ones are positions between tokens
+fn check_siblings(spans: impl Iterator<Item = Span>) {
+    let mut spans = spans.filter(|s| !s.is_empty()).collect::<Vec<_>>();
+    spans.sort_by_key(|s| s.start);
+    for pair in spans.windows(2) {
+        assert!(
+            pair[0].end <= pair[1].start,
+            "spans {:?} and {:?} overlap",
+            pair[0],
+            pair[1]
+        );
+    }
+}
+
+// Tokens are ordered and every brace points to its pair
+fn check_tokens(tokens: &[Token], len: usize) {
+    for pair in tokens.windows(2) {
+        assert!(
+            pair[0].range.end <= pair[1].range.start,
+            "tokens {:?} and {:?} are out of order",
+            pair[0].range,
+            pair[1].range
+        );
+    }
+    for (idx, token) in tokens.iter().enumerate() {
+        check_span(token.range, len);
+        if let Some(close) = token.kind.close() {
+            assert!(
+                matches!(tokens.get(close).map(|t| &t.kind), Some(TokenKind::ClosingBrace { open }) if *open == idx),
+                "brace {idx} has no pair"
+            );
+        }
+        if let TokenKind::ClosingBrace { open } = token.kind {
+            assert_eq!(tokens.get(open).and_then(|t| t.kind.close()), Some(idx), "brace {idx} has no pair");
+        }
+    }
+}
+
+fn check_node(ast: &Ast, id: NodeId, len: usize) {
+    check_span(ast[id].range, len);
+
+    let children = ast.children(id);
+    check_siblings(children.iter().map(|c| ast[*c].range));
+    for child in children {
+        assert_eq!(ast.parent(child), Some(id), "wrong parent of {child:?}");
+        check_node(ast, child, len);
+    }
+}
@@ -0,0 +1,9 @@
+pub mod diagnostics;
+pub mod document;
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub mod fuzzing;
+pub mod parser;
+pub mod semantic;
+pub mod source;
+pub mod tokenizer;
@@ -1,48 +1,297 @@
-pub mod parser;
-pub mod tokenizer;
+use std::{
+    io::{IsTerminal, Write},
+    process::ExitCode,
+};
 
-use tokenizer::token::{Token, TokenKind};
+use esl::{
+    diagnostics::{self, Diagnostic, Severity, export, render::Renderer},
+    parser::{self, ast::Ast, debugger::Debugger, export::AstFile},
+    semantic,
+    source,
+    tokenizer::{
+        self,
+        token::{Token, TokenKind},
+    },
+};
 
-fn debug_file(path: &str) {
-    let src = std::fs::read(path).unwrap();
-    let mut tokenizer = tokenizer::Tokenizer::new(&src);
+fn check_file(sources: &source::SourceMap, file: source::FileId) -> Vec<Diagnostic> {
+    let src = &sources.get(file).src;
+    let mut tokenizer = tokenizer::Tokenizer::new(file, src);
     tokenizer.tokenize();
 
-    print_token_tree(&tokenizer.tokens, &src, 0);
+    let mut parser = parser::Parser::new(file, src, &tokenizer.tokens);
+    parser.p_file();
 
-    let mut parser = parser::Parser::new(&src, &tokenizer.tokens);
-    let expr = parser.p_block();
+    let ast = AstFile {
+        names: tokenizer.interner,
+        ast: parser.ast,
+    };
+    semantic::check(&tokenizer.tokens, &ast, src)
+}
+
+#[derive(Clone, Copy)]
+enum DebugFormat {
+    Tree,
+    Dot,
+    Html,
+}
+
+fn debug_file(path: &str, format: DebugFormat, debugger: &Debugger) -> ExitCode {
+    let mut sources = source::SourceMap::new();
+    let file = match sources.load_file(path) {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("error: can not read {path}: {err}");
+            return ExitCode::from(2);
+        }
+    };
+    let src = &sources.get(file).src;
+    let mut tokenizer = tokenizer::Tokenizer::new(file, src);
+    tokenizer.tokenize();
+
+    let mut parser = parser::Parser::new(file, src, &tokenizer.tokens);
+    parser.p_file();
+
+    let written = print_debug(format, debugger, &sources, file, &tokenizer.tokens, &parser.ast);
+    if let Err(err) = written {
+        eprintln!("error: can not write output: {err}");
+        return ExitCode::from(2);
+    }
+    ExitCode::SUCCESS
+}
+
+fn print_debug(
+    format: DebugFormat,
+    debugger: &Debugger,
+    sources: &source::SourceMap,
+    file: source::FileId,
+    tokens: &[Token],
+    ast: &Ast,
+) -> std::io::Result<()> {
+    let src = &sources.get(file).src;
+    let mut stdout = std::io::stdout().lock();
+    match format {
+        DebugFormat::Dot => return debugger.write_dot(&mut stdout, ast, src),
+        DebugFormat::Html => return debugger.write_html(&mut stdout, ast, src),
+        DebugFormat::Tree => (),
+    }
+
+    print_token_tree(&mut stdout, tokens, src)?;
+    if ast.roots.is_empty() {
+        writeln!(stdout, "No expression")?;
+    }
+    debugger.write_tree(&mut stdout, ast, src)?;
+
+    let diags = diagnostics::collect(tokens, ast);
+    let renderer = Renderer::new(std::io::stderr().is_terminal());
+    renderer.render_all(&mut std::io::stderr(), sources, &diags)
+}
+
+#[derive(Clone, Copy)]
+enum AstFormat {
+    Json,
+    Sexpr,
+}
+
+fn print_ast(format: AstFormat, path: &str) -> ExitCode {
+    let mut sources = source::SourceMap::new();
+    let file = match sources.load_file(path) {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("error: can not read {path}: {err}");
+            return ExitCode::from(2);
+        }
+    };
+    let src = &sources.get(file).src;
+    let mut tokenizer = tokenizer::Tokenizer::new(file, src);
+    tokenizer.tokenize();
+    let mut parser = parser::Parser::new(file, src, &tokenizer.tokens);
+    parser.p_file();
+
+    let ast = AstFile {
+        names: tokenizer.interner,
+        ast: parser.ast,
+    };
+    match format {
+        AstFormat::Sexpr => print!("{}", parser::export::to_sexpr(&ast, src)),
+        #[cfg(feature = "serde")]
+        AstFormat::Json => println!("{}", parser::export::to_json(&ast)),
+        #[cfg(not(feature = "serde"))]
+        AstFormat::Json => {
+            eprintln!("error: json output needs esl built with the `serde` feature");
+            return ExitCode::from(2);
+        }
+    }
+    ExitCode::SUCCESS
+}
 
-    match expr {
-        Some(e) => parser::debugger::Debugger::print_nodes_tree(&e, &parser),
-        _ => println!("No expression"),
+#[derive(Clone, Copy)]
+enum OutputFormat {
+    Human,
+    Json,
+    Sarif,
+}
+
+fn check(format: OutputFormat, paths: &[String]) -> ExitCode {
+    let mut sources = source::SourceMap::new();
+    let mut diags = Vec::new();
+
+    for path in paths {
+        match sources.load_file(path) {
+            Ok(file) => diags.extend(check_file(&sources, file)),
+            Err(err) => {
+                eprintln!("error: can not read {path}: {err}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    let mut stdout = std::io::stdout();
+    let written = match format {
+        OutputFormat::Human => {
+            Renderer::new(stdout.is_terminal()).render_all(&mut stdout, &sources, &diags)
+        }
+        OutputFormat::Json => writeln!(stdout, "{}", export::to_json(&sources, &diags)),
+        OutputFormat::Sarif => writeln!(stdout, "{}", export::to_sarif(&sources, &diags)),
     };
+    if let Err(err) = written {
+        eprintln!("error: can not write output: {err}");
+        return ExitCode::from(2);
+    }
+
+    if diags.iter().any(|d| d.severity == Severity::Error) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
 }
 
-fn main() {
-    debug_file("test.txt");
+const USAGE: &str = "\
+usage:
+    esl check [--format human|json|sarif] <files...>
+    esl ast [--format sexpr|json] <file>
+    esl debug [--format tree|dot|html] [--max-depth <n>] [--spans] [--errors-only] <file>";
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    match args.first().map(String::as_str) {
+        None => debug_file("test.txt", DebugFormat::Tree, &Debugger::new()),
+        Some("debug") => {
+            let mut format = DebugFormat::Tree;
+            let mut debugger = Debugger::new();
+            let mut path = None;
+            let mut rest = args[1..].iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "--format" => {
+                        format = match rest.next().map(String::as_str) {
+                            Some("tree") => DebugFormat::Tree,
+                            Some("dot") => DebugFormat::Dot,
+                            Some("html") => DebugFormat::Html,
+                            _ => {
+                                eprintln!("{USAGE}");
+                                return ExitCode::from(2);
+                            }
+                        }
+                    }
+                    "--max-depth" => match rest.next().and_then(|n| n.parse().ok()) {
+                        Some(depth) => debugger = debugger.with_max_depth(depth),
+                        None => {
+                            eprintln!("{USAGE}");
+                            return ExitCode::from(2);
+                        }
+                    },
+                    "--spans" => debugger = debugger.with_spans(true),
+                    "--errors-only" => debugger = debugger.hide_error_free(true),
+                    _ if path.is_none() => path = Some(arg),
+                    _ => {
+                        eprintln!("{USAGE}");
+                        return ExitCode::from(2);
+                    }
+                }
+            }
+            let Some(path) = path else {
+                eprintln!("{USAGE}");
+                return ExitCode::from(2);
+            };
+            debug_file(path, format, &debugger)
+        }
+        Some("ast") => {
+            let (format, path) = match &args[1..] {
+                [path] => (AstFormat::Sexpr, path),
+                [flag, format, path] if flag == "--format" => match format.as_str() {
+                    "sexpr" => (AstFormat::Sexpr, path),
+                    "json" => (AstFormat::Json, path),
+                    _ => {
+                        eprintln!("{USAGE}");
+                        return ExitCode::from(2);
+                    }
+                },
+                _ => {
+                    eprintln!("{USAGE}");
+                    return ExitCode::from(2);
+                }
+            };
+            print_ast(format, path)
+        }
+        Some("check") => {
+            let mut format = OutputFormat::Human;
+            let mut paths = Vec::new();
+            let mut rest = args[1..].iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "--format" => {
+                        format = match rest.next().map(String::as_str) {
+                            Some("human") => OutputFormat::Human,
+                            Some("json") => OutputFormat::Json,
+                            Some("sarif") => OutputFormat::Sarif,
+                            _ => {
+                                eprintln!("{USAGE}");
+                                return ExitCode::from(2);
+                            }
+                        }
+                    }
+                    path => paths.push(path.to_string()),
+                }
+            }
+            if paths.is_empty() {
+                eprintln!("{USAGE}");
+                return ExitCode::from(2);
+            }
+            check(format, &paths)
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            ExitCode::from(2)
+        }
+    }
 }
 
-fn print_token_tree(tokens: &[Token], src: &[u8], indent: usize) {
+fn print_token_tree(out: &mut impl Write, tokens: &[Token], src: &[u8]) -> std::io::Result<()> {
+    let mut indent = 0;
     for token in tokens {
-        let content = std::str::from_utf8(&src[token.range.clone()]).unwrap_or("<invalid utf8>");
+        // braces are shown with all the children
+        let range = match token.kind.close() {
+            Some(close) => token.range.join(tokens[close].range),
+            None => token.range,
+        };
+        let content = std::str::from_utf8(&src[range.as_range()]).unwrap_or("<invalid utf8>");
         let pad = " ".repeat(indent);
         match &token.kind {
-            TokenKind::RoundBraces { children } => {
-                println!("{pad}RoundBraces -> {content}");
-                print_token_tree(children, src, indent + 2);
-            }
-            TokenKind::SquareBraces { children } => {
-                println!("{pad}SquareBraces -> {content}");
-                print_token_tree(children, src, indent + 2);
-            }
-            TokenKind::CurlyBraces { children } => {
-                println!("{pad}CurlyBraces -> {content}");
-                print_token_tree(children, src, indent + 2);
+            TokenKind::RoundBraces { .. } => writeln!(out, "{pad}RoundBraces -> {content}")?,
+            TokenKind::SquareBraces { .. } => writeln!(out, "{pad}SquareBraces -> {content}")?,
+            TokenKind::CurlyBraces { .. } => writeln!(out, "{pad}CurlyBraces -> {content}")?,
+            TokenKind::ClosingBrace { .. } => {
+                indent -= 2;
+                continue;
             }
             _ => {
-                println!("{pad}{:?} -> {content}", token.kind);
+                writeln!(out, "{pad}{:?} -> {content}", token.kind)?;
+                continue;
             }
         }
+        indent += 2;
     }
+    Ok(())
 }
@@ -0,0 +1,298 @@
+use crate::{
+    parser::{
+        nodes::{Node, NodeKind},
+        primitives::DontCare,
+    },
+    source::Span,
+};
+
+/// Index of a node in the [`Ast`], stable for the lifetime of the tree.
+/// Side tables (types, resolved names) are keyed by it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+pub struct NodeId(u32);
+
+impl NodeId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// All nodes of a parsed file in a single allocation, children are referenced by [`NodeId`].
+/// Children are always allocated before their parents
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "AstParts")
+)]
+pub struct Ast {
+    nodes: Vec<Node>,
+    /// top level nodes of the file
+    pub roots: Vec<NodeId>,
+    /// `//!` comments at the start of the file, documenting it
+    pub docs: Vec<Span>,
+}
+
+impl Ast {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(nodes: usize) -> Self {
+        Self {
+            nodes: Vec::with_capacity(nodes),
+            roots: Vec::new(),
+            docs: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Adds a node and links its children to it
+    pub fn push(&mut self, node: Node) -> NodeId {
+        let id = NodeId(self.nodes.len() as u32);
+        self.nodes.push(node);
+        self.adopt_children(id);
+        id
+    }
+
+    /// Frees the capacity reserved up front, when the parser is done
+    pub(crate) fn shrink_to_fit(&mut self) {
+        self.nodes.shrink_to_fit();
+    }
+
+    /// Drops the nodes after the first `len`, when the parser backtracks.
+    /// Nothing before them may refer to them
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.nodes.truncate(len);
+    }
+
+    /// Turns the descendants of the node into unlinked placeholders and returns their ids,
+    /// so [`Ast::move_tail`] can reuse them. The node itself is kept
+    pub(crate) fn free_descendants(&mut self, id: NodeId) -> Vec<NodeId> {
+        let mut freed = Vec::new();
+        let mut stack = self.children(id);
+        while let Some(child) = stack.pop() {
+            self[child].visit_children(|c| stack.push(c));
+            let range = self[child].range;
+            self[child] = Node::new(NodeKind::DontCare(DontCare {}), range);
+            freed.push(child);
+        }
+        freed
+    }
+
+    /// Moves the nodes after the first `len` into the `free` slots, the rest of them right
+    /// after `len`, keeping their order. Used slots are taken out of `free`.
+    /// Only the moved nodes may refer to each other, the old id of each maps to
+    /// `moved[old.index() - len]`
+    pub(crate) fn move_tail(&mut self, len: usize, free: &mut Vec<NodeId>) -> Vec<NodeId> {
+        free.sort_unstable_by(|a, b| b.cmp(a));
+        let tail = self.nodes.split_off(len);
+        let mut next = len as u32;
+        let moved = tail
+            .iter()
+            .map(|_| {
+                free.pop().unwrap_or_else(|| {
+                    next += 1;
+                    NodeId(next - 1)
+                })
+            })
+            .collect::<Vec<_>>();
+        for (mut node, id) in tail.into_iter().zip(&moved) {
+            node.visit_children_mut(|child| {
+                if let Some(i) = child.index().checked_sub(len) {
+                    *child = moved[i];
+                }
+            });
+            if id.index() < len {
+                self.nodes[id.index()] = node;
+            } else {
+                self.nodes.push(node);
+            }
+        }
+        for id in &moved {
+            self.adopt_children(*id);
+        }
+        moved
+    }
+
+    /// Replaces the kind of the node, new children are linked to it
+    pub fn set_kind(&mut self, id: NodeId, kind: NodeKind) {
+        self.nodes[id.index()].kind = kind;
+        self.adopt_children(id);
+    }
+
+    fn adopt_children(&mut self, id: NodeId) {
+        // children are usually allocated before the parent, but hooks of passes may add new ones
+        let (before, rest) = self.nodes.split_at_mut(id.index());
+        let Some((node, after)) = rest.split_first_mut() else {
+            return;
+        };
+        node.visit_children(|child| {
+            let child = match child.index().checked_sub(id.index() + 1) {
+                Some(i) => &mut after[i],
+                None => &mut before[child.index()],
+            };
+            child.parent = Some(id);
+        });
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = NodeId> + use<> {
+        (0..self.nodes.len() as u32).map(NodeId)
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self[id].parent
+    }
+
+    /// The node itself, its parent, the parent of the parent, etc.
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(Some(id), |id| self.parent(*id))
+    }
+
+    pub fn children(&self, id: NodeId) -> Vec<NodeId> {
+        let mut children = Vec::new();
+        self[id].visit_children(|child| children.push(child));
+        children
+    }
+
+    /// Children of the parent, or roots for the top level nodes
+    pub fn siblings(&self, id: NodeId) -> Vec<NodeId> {
+        match self.parent(id) {
+            Some(parent) => self.children(parent),
+            None => self.roots.clone(),
+        }
+    }
+    pub fn next_sibling(&self, id: NodeId) -> Option<NodeId> {
+        let siblings = self.siblings(id);
+        let pos = siblings.iter().position(|s| *s == id)?;
+        siblings.get(pos + 1).copied()
+    }
+    pub fn prev_sibling(&self, id: NodeId) -> Option<NodeId> {
+        let siblings = self.siblings(id);
+        let pos = siblings.iter().position(|s| *s == id)?;
+        siblings.get(pos.checked_sub(1)?).copied()
+    }
+
+    /// The deepest node containing the byte offset. The end of a span counts as its part,
+    /// so a cursor right after an identifier still finds the identifier
+    pub fn node_at(&self, offset: usize) -> Option<NodeId> {
+        let contains = |id: &NodeId| {
+            let range = self[*id].range;
+            range.start <= offset && offset <= range.end
+        };
+        let mut found = self.roots.iter().copied().find(contains)?;
+        loop {
+            let mut next = None;
+            self[found].visit_children(|child| {
+                // the first one wins, when spans touch
+                if next.is_none() && contains(&child) {
+                    next = Some(child);
+                }
+            });
+            match next {
+                Some(child) => found = child,
+                None => return Some(found),
+            }
+        }
+    }
+
+    /// `_` placeholder, possibly wrapped into an expression
+    pub fn is_dont_care(&self, id: NodeId) -> bool {
+        match &self[id].kind {
+            NodeKind::DontCare(_) => true,
+            NodeKind::Expression(e) => e.rpn.len() == 1 && self.is_dont_care(e.rpn[0]),
+            _ => false,
+        }
+    }
+
+    /// `(a) -> a`, possibly wrapped into an expression
+    pub fn is_lambda(&self, id: NodeId) -> bool {
+        match &self[id].kind {
+            NodeKind::Lambda(_) => true,
+            NodeKind::Expression(e) => e.rpn.len() == 1 && self.is_lambda(e.rpn[0]),
+            _ => false,
+        }
+    }
+}
+
+impl std::ops::Index<NodeId> for Ast {
+    type Output = Node;
+
+    fn index(&self, id: NodeId) -> &Node {
+        &self.nodes[id.index()]
+    }
+}
+
+impl std::ops::IndexMut<NodeId> for Ast {
+    fn index_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id.index()]
+    }
+}
+
+// Deserialized nodes, before parents are linked and child ids are checked
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct AstParts {
+    nodes: Vec<Node>,
+    roots: Vec<NodeId>,
+    docs: Vec<Span>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<AstParts> for Ast {
+    type Error = String;
+
+    /// Checks the invariants of the parser's arena, so a bad file can't make the tree cyclic:
+    /// children come before their parent, every node but the roots has exactly one parent
+    fn try_from(parts: AstParts) -> Result<Self, String> {
+        let mut ast = Ast {
+            nodes: parts.nodes,
+            roots: parts.roots,
+            docs: parts.docs,
+        };
+        let len = ast.nodes.len();
+        let mut linked = vec![false; len];
+        for id in ast.ids() {
+            let mut bad = None;
+            ast[id].visit_children(|child| {
+                if bad.is_some() {
+                    return;
+                }
+                if child.index() >= len {
+                    bad = Some(format!("node {} has unknown child {}", id.index(), child.index()));
+                } else if child >= id {
+                    bad = Some(format!(
+                        "node {} has child {} which is not before it",
+                        id.index(),
+                        child.index()
+                    ));
+                } else if std::mem::replace(&mut linked[child.index()], true) {
+                    bad = Some(format!("node {} has more than one parent", child.index()));
+                }
+            });
+            if let Some(err) = bad {
+                return Err(err);
+            }
+            ast.adopt_children(id);
+        }
+        for root in &ast.roots {
+            if root.index() >= len {
+                return Err(format!("unknown root {}", root.index()));
+            }
+            if std::mem::replace(&mut linked[root.index()], true) {
+                return Err(format!("root {} has a parent or is repeated", root.index()));
+            }
+        }
+        match linked.iter().position(|linked| !linked) {
+            Some(orphan) => Err(format!("node {orphan} has no parent and is not a root")),
+            None => Ok(ast),
+        }
+    }
+}
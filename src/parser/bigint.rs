@@ -0,0 +1,132 @@
+//! Unsigned integers of any size, literals are kept exactly and checked against their type later
+
+use std::fmt;
+
+/// Little endian 32-bit limbs, the last one is never zero, so zero has none
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+/// Longest literal the parser reads. Reading takes quadratic time in the number of digits,
+/// while any sized integer type fits in 128 binary digits
+pub const MAX_DIGITS: usize = 1024;
+
+impl BigUint {
+    /// Digits without a prefix, None when empty or when a digit is not of the radix
+    pub fn from_digits(digits: &[u8], radix: u32) -> Option<Self> {
+        if digits.is_empty() {
+            return None;
+        }
+        // as many digits as fit in a limb are added at once, like 9 decimal ones
+        let chunk_len = u32::MAX.ilog(radix) as usize;
+        let mut value = Self::default();
+        for chunk in digits.chunks(chunk_len) {
+            let mut mul = 1;
+            let mut add = 0;
+            for digit in chunk {
+                add = add * radix + char::from(*digit).to_digit(radix)?;
+                mul *= radix;
+            }
+            value.mul_add(mul, add);
+        }
+        Some(value)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn to_u128(&self) -> Option<u128> {
+        if self.limbs.len() > 4 {
+            return None;
+        }
+        let value = self
+            .limbs
+            .iter()
+            .rev()
+            .fold(0, |acc, limb| acc << 32 | u128::from(*limb));
+        Some(value)
+    }
+
+    fn mul_add(&mut self, mul: u32, add: u32) {
+        let mut carry = u64::from(add);
+        for limb in &mut self.limbs {
+            let value = u64::from(*limb) * u64::from(mul) + carry;
+            *limb = value as u32;
+            carry = value >> 32;
+        }
+        if carry > 0 {
+            self.limbs.push(carry as u32);
+        }
+    }
+
+    // Divides in place and returns the remainder
+    fn div_rem(&mut self, div: u32) -> u32 {
+        let mut rem = 0;
+        for limb in self.limbs.iter_mut().rev() {
+            let value = rem << 32 | u64::from(*limb);
+            *limb = (value / u64::from(div)) as u32;
+            rem = value % u64::from(div);
+        }
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        rem as u32
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(mut value: u128) -> Self {
+        let mut limbs = Vec::new();
+        while value > 0 {
+            limbs.push(value as u32);
+            value >>= 32;
+        }
+        Self { limbs }
+    }
+}
+
+/// Decimal digits
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const CHUNK: u32 = 1_000_000_000;
+        let mut rest = self.clone();
+        let mut chunks = Vec::new();
+        while !rest.is_zero() {
+            chunks.push(rest.div_rem(CHUNK));
+        }
+        let Some((first, lower)) = chunks.split_last() else {
+            return write!(f, "0");
+        };
+        write!(f, "{first}")?;
+        for chunk in lower.iter().rev() {
+            write!(f, "{chunk:09}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Decimal digits, the limbs are not a part of the format
+impl TryFrom<String> for BigUint {
+    type Error = String;
+
+    fn try_from(digits: String) -> Result<Self, String> {
+        if digits.len() > MAX_DIGITS {
+            return Err(format!("integer has more than {MAX_DIGITS} digits"));
+        }
+        Self::from_digits(digits.as_bytes(), 10)
+            .ok_or_else(|| format!("`{digits}` is not a decimal integer"))
+    }
+}
+
+impl From<BigUint> for String {
+    fn from(value: BigUint) -> Self {
+        value.to_string()
+    }
+}
@@ -1,13 +1,145 @@
+use std::io::{self, Write};
+
 use super::*;
 
-pub struct Debugger;
+/// Renders the tree for humans: a box-drawing tree, Graphviz DOT or a standalone HTML page
+#[derive(Debug, Clone, Default)]
+pub struct Debugger {
+    max_depth: Option<usize>,
+    show_spans: bool,
+    hide_error_free: bool,
+}
 
 impl Debugger {
-    pub fn print_nodes_tree(node: &Node, parser: &Parser) {
-        Self::nodes_tree_rec(node, parser, "".to_string(), true, true)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Nodes deeper than that are replaced with `...`, the roots are at depth 0
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+    /// Byte ranges next to the nodes
+    pub fn with_spans(mut self, show: bool) -> Self {
+        self.show_spans = show;
+        self
+    }
+    /// Only the nodes with an error somewhere below them
+    pub fn hide_error_free(mut self, hide: bool) -> Self {
+        self.hide_error_free = hide;
+        self
+    }
+
+    pub fn write_tree(&self, out: &mut impl Write, ast: &Ast, src: &[u8]) -> io::Result<()> {
+        let view = View::new(self, ast, src);
+        for root in view.roots() {
+            view.tree_rec(out, root, 0, String::new(), true, true)?;
+        }
+        Ok(())
     }
 
-    fn nodes_tree_rec(node: &Node, parser: &Parser, prefix: String, is_last_sibling: bool, is_root: bool) {
+    pub fn write_dot(&self, out: &mut impl Write, ast: &Ast, src: &[u8]) -> io::Result<()> {
+        let view = View::new(self, ast, src);
+        writeln!(out, "digraph ast {{")?;
+        writeln!(out, "    node [shape=box, fontname=\"monospace\"];")?;
+        for root in view.roots() {
+            view.dot_rec(out, root, 0)?;
+        }
+        writeln!(out, "}}")
+    }
+
+    /// Collapsible nodes next to the source, hovering a node highlights its range
+    pub fn write_html(&self, out: &mut impl Write, ast: &Ast, src: &[u8]) -> io::Result<()> {
+        let mut view = View::new(self, ast, src);
+        view.utf16 = utf16_offsets(src);
+        write!(out, "{HTML_HEAD}")?;
+        writeln!(
+            out,
+            "<pre id=\"src\">{}</pre>",
+            escape_html(&String::from_utf8_lossy(src))
+        )?;
+        writeln!(out, "<div id=\"tree\">")?;
+        for root in view.roots() {
+            view.html_rec(out, root, 0)?;
+        }
+        writeln!(out, "</div>")?;
+        write!(out, "{HTML_TAIL}")
+    }
+}
+
+// The tree with the options applied
+struct View<'a> {
+    options: &'a Debugger,
+    ast: &'a Ast,
+    src: &'a [u8],
+    /// by node index, the node or one of its descendants is an error
+    has_error: Vec<bool>,
+    /// by byte offset, the offset in UTF-16 units, only for HTML
+    utf16: Vec<usize>,
+}
+
+impl<'a> View<'a> {
+    fn new(options: &'a Debugger, ast: &'a Ast, src: &'a [u8]) -> Self {
+        let mut has_error = vec![false; ast.len()];
+        if options.hide_error_free {
+            for id in ast.ids() {
+                if !matches!(ast[id].kind, NodeKind::Error(_)) {
+                    continue;
+                }
+                for ancestor in ast.ancestors(id) {
+                    if std::mem::replace(&mut has_error[ancestor.index()], true) {
+                        break;
+                    }
+                }
+            }
+        }
+        Self {
+            options,
+            ast,
+            src,
+            has_error,
+            utf16: Vec::new(),
+        }
+    }
+
+    fn shown(&self, id: &NodeId) -> bool {
+        !self.options.hide_error_free || self.has_error[id.index()]
+    }
+    fn roots(&self) -> Vec<NodeId> {
+        self.ast
+            .roots
+            .iter()
+            .copied()
+            .filter(|id| self.shown(id))
+            .collect()
+    }
+    fn children(&self, id: NodeId) -> Vec<NodeId> {
+        let mut children = self.ast.children(id);
+        children.retain(|id| self.shown(id));
+        children
+    }
+    fn too_deep(&self, depth: usize) -> bool {
+        self.options.max_depth.is_some_and(|max| depth > max)
+    }
+
+    fn text(&self, id: NodeId) -> String {
+        String::from_utf8_lossy(&self.src[self.ast[id].range.as_range()]).into_owned()
+    }
+    fn span(&self, id: NodeId) -> String {
+        let range = self.ast[id].range;
+        format!("{}..{}", range.start, range.end)
+    }
+
+    fn tree_rec(
+        &self,
+        out: &mut impl Write,
+        id: NodeId,
+        depth: usize,
+        prefix: String,
+        is_last_sibling: bool,
+        is_root: bool,
+    ) -> io::Result<()> {
         let connector = if is_root {
             ""
         } else if is_last_sibling {
@@ -15,13 +147,20 @@ impl Debugger {
         } else {
             "├── "
         };
+        let kind = &self.ast[id].kind;
+        let text = self.text(id);
+        if self.options.show_spans {
+            writeln!(
+                out,
+                "{prefix}{connector}{kind:?} @ {} -> {text}",
+                self.span(id)
+            )?;
+        } else {
+            writeln!(out, "{prefix}{connector}{kind:?} -> {text}")?;
+        }
 
-        let tokens = parser.get_src(node.range.clone());
-        let kind = &node.kind;
-        println!("{}{}{:?} -> {}", prefix, connector, kind, String::from_utf8_lossy(tokens));
-
-        let child_prefix = prefix +
-            if is_root {
+        let child_prefix = prefix
+            + if is_root {
                 ""
             } else if is_last_sibling {
                 "    "
@@ -29,14 +168,178 @@ impl Debugger {
                 "│   "
             };
 
-        let mut children_to_visit: Vec<&Node> = Vec::new();
-        node.visit_children(|child_node| {
-            children_to_visit.push(child_node);
-        });
+        let children = self.children(id);
+        if !children.is_empty() && self.too_deep(depth + 1) {
+            return writeln!(out, "{child_prefix}└── ...");
+        }
+        for (i, child) in children.iter().enumerate() {
+            let is_last = i == children.len() - 1;
+            self.tree_rec(out, *child, depth + 1, child_prefix.clone(), is_last, false)?;
+        }
+        Ok(())
+    }
 
-        let num_children = children_to_visit.len();
-        for (i, child_node) in children_to_visit.into_iter().enumerate() {
-            Self::nodes_tree_rec(child_node, parser, child_prefix.clone(), i == num_children - 1, false);
+    fn dot_rec(&self, out: &mut impl Write, id: NodeId, depth: usize) -> io::Result<()> {
+        let node = &self.ast[id];
+        let mut label = node.kind.name().to_string();
+        if let NodeKind::Error(err) = &node.kind {
+            label = format!("{label} {}\n{err}", err.code());
+        }
+        label.push('\n');
+        label.push_str(&shorten(&self.text(id), 40));
+        if self.options.show_spans {
+            label.push('\n');
+            label.push_str(&self.span(id));
+        }
+
+        let color = match node.kind {
+            NodeKind::Error(_) => ", color=red, fontcolor=red",
+            _ => "",
+        };
+        writeln!(
+            out,
+            "    n{} [label=\"{}\"{color}];",
+            id.index(),
+            escape_dot(&label)
+        )?;
+
+        let children = self.children(id);
+        if children.is_empty() {
+            return Ok(());
+        }
+        if self.too_deep(depth + 1) {
+            writeln!(
+                out,
+                "    n{}_more [label=\"...\", shape=plaintext];",
+                id.index()
+            )?;
+            return writeln!(out, "    n{} -> n{}_more;", id.index(), id.index());
         }
+        for child in children {
+            writeln!(out, "    n{} -> n{};", id.index(), child.index())?;
+            self.dot_rec(out, child, depth + 1)?;
+        }
+        Ok(())
     }
+
+    fn html_rec(&self, out: &mut impl Write, id: NodeId, depth: usize) -> io::Result<()> {
+        let node = &self.ast[id];
+        let class = match node.kind {
+            NodeKind::Error(_) => "node error",
+            _ => "node",
+        };
+        let mut label = format!("<b>{}</b>", node.kind.name());
+        if let NodeKind::Error(err) = &node.kind {
+            label.push_str(&format!(
+                " {} {}",
+                err.code(),
+                escape_html(&err.to_string())
+            ));
+        }
+        if self.options.show_spans {
+            label.push_str(&format!(" <i>{}</i>", self.span(id)));
+        }
+        label.push_str(&format!(
+            " <code>{}</code>",
+            escape_html(&shorten(&self.text(id), 60))
+        ));
+
+        // offsets in UTF-16 units, the way the page indexes the text
+        let start = self.utf16[node.range.start];
+        let end = self.utf16[node.range.end];
+        let attrs = format!("class=\"{class}\" data-start=\"{start}\" data-end=\"{end}\"");
+
+        let children = self.children(id);
+        if children.is_empty() {
+            return writeln!(out, "<div {attrs}>{label}</div>");
+        }
+        writeln!(out, "<details open><summary {attrs}>{label}</summary>")?;
+        if self.too_deep(depth + 1) {
+            writeln!(out, "<div class=\"node\">...</div>")?;
+        } else {
+            for child in children {
+                self.html_rec(out, child, depth + 1)?;
+            }
+        }
+        writeln!(out, "</details>")
+    }
+}
+
+// First line of the text, at most `max` chars
+fn shorten(text: &str, max: usize) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    match line.char_indices().nth(max) {
+        Some((end, _)) => format!("{}...", &line[..end]),
+        None if line.len() < text.len() => format!("{line}..."),
+        None => line.to_string(),
+    }
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
 }
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Offsets in the text shown on the page, invalid UTF-8 is replaced like `from_utf8_lossy` does
+fn utf16_offsets(src: &[u8]) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(src.len() + 1);
+    let mut units = 0;
+    for chunk in src.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            offsets.extend(std::iter::repeat_n(units, c.len_utf8()));
+            units += c.len_utf16();
+        }
+        offsets.extend(std::iter::repeat_n(units, chunk.invalid().len()));
+        units += usize::from(!chunk.invalid().is_empty());
+    }
+    offsets.push(units);
+    offsets
+}
+
+const HTML_HEAD: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>esl ast</title>
+<style>
+body { display: flex; gap: 1em; margin: 0; font-family: monospace; }
+#src, #tree { height: 100vh; overflow: auto; margin: 0; padding: 1em; box-sizing: border-box; }
+#src { flex: 1; white-space: pre-wrap; background: #f6f6f6; }
+#tree { flex: 1; }
+details > :not(summary) { margin-left: 1.2em; }
+.node { cursor: default; }
+.node:hover { background: #fff3b0; }
+.error { color: #c00; }
+mark { background: #ffd54f; }
+i { color: #777; }
+code { color: #555; }
+</style>
+</head>
+<body>
+"#;
+
+const HTML_TAIL: &str = r#"<script>
+const src = document.getElementById("src");
+const text = src.textContent;
+document.getElementById("tree").addEventListener("mouseover", (event) => {
+    const node = event.target.closest("[data-start]");
+    if (!node) return;
+    const start = Number(node.dataset.start);
+    const end = Number(node.dataset.end);
+    const mark = document.createElement("mark");
+    mark.textContent = text.slice(start, end);
+    src.replaceChildren(text.slice(0, start), mark, text.slice(end));
+    mark.scrollIntoView({ block: "nearest" });
+});
+</script>
+</body>
+</html>
+"#;
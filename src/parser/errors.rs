@@ -1,19 +1,193 @@
-#[derive(Debug, Clone)]
+use std::borrow::Cow;
+
+use thiserror::Error;
+
+use crate::{source::Span, tokenizer::token::TokenKind};
+
+// the parser uses literals, deserialized trees own their text
+type Text = Cow<'static, str>;
+
+/// Something that would have been valid at the error position
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Expected {
+    Token(TokenKind),
+    /// opening or closing brace, like `{` or `)`
+    Brace(char),
+    /// whole construct, like "expression" or "type"
+    Node(Text),
+}
+
+impl std::fmt::Display for Expected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expected::Token(kind) => write!(f, "{}", kind.describe()),
+            Expected::Brace(brace) => write!(f, "`{brace}`"),
+            Expected::Node(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// Edit that fixes the error, can be applied by tools
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fix {
+    /// let a 1 -> let a = 1
+    Insert { at: Span, text: Text },
+    /// whiel -> while
+    Keyword { span: Span, keyword: Text },
+}
+
+impl Fix {
+    pub fn span(&self) -> Span {
+        match self {
+            Fix::Insert { at, .. } => *at,
+            Fix::Keyword { span, .. } => *span,
+        }
+    }
+    pub fn replacement(&self) -> &str {
+        match self {
+            Fix::Insert { text, .. } => text,
+            Fix::Keyword { keyword, .. } => keyword,
+        }
+    }
+    pub fn message(&self) -> String {
+        match self {
+            Fix::Insert { text, .. } => format!("insert `{}`", text.trim()),
+            Fix::Keyword { keyword, .. } => format!("did you mean `{keyword}`?"),
+        }
+    }
+}
+
+/// What was found by the parser and what it was looking for
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mismatch {
+    /// None at the closing brace
+    pub found: Option<TokenKind>,
+    pub expected: Vec<Expected>,
+    pub fix: Option<Fix>,
+}
+
+impl Mismatch {
+    pub fn new(found: Option<&TokenKind>, expected: Vec<Expected>) -> Box<Self> {
+        Box::new(Self {
+            found: found.map(TokenKind::shallow),
+            expected,
+            fix: None,
+        })
+    }
+    pub fn with_fix(mut self: Box<Self>, fix: Fix) -> Box<Self> {
+        self.fix = Some(fix);
+        self
+    }
+
+    pub fn found_str(&self) -> String {
+        self.found
+            .as_ref()
+            .map_or("closing brace".to_string(), TokenKind::describe)
+    }
+    /// `a`, `a or b`, `one of a, b, c`
+    pub fn expected_str(&self) -> String {
+        let items = self.expected.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        match items.as_slice() {
+            [] => "something else".to_string(),
+            [one] => one.clone(),
+            [a, b] => format!("{a} or {b}"),
+            all => format!("one of {}", all.join(", ")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ParsingError {
-    // int bigger than 2^64
-    IntegerOverflow,
     // (a + !)
+    #[error("Expected operand after prefix operator (!a)")]
     NoOperandAfterPrefixOperator,
     // (a + ?)
+    #[error("Expected operand before postfix operator (a?)")]
     NoOperandBeforePostfixOperation,
     // (+ b)
+    #[error("Expected operand before infix operator (a + b)")]
     NoOperandBeforeInfixOperation,
     // (a +)
+    #[error("Expected operand after infix operator (a + b)")]
     NoOperandAfterInfixOperation,
     // f(a, b, c ####)
+    #[error("Unexpected tokens in arguments list (f(a, b))")]
     UnexpectedCallArgument,
     // (a + b ####)
-    UnexpectedToken,
+    #[error("Unexpected {}", .0.found_str())]
+    UnexpectedToken(Box<Mismatch>),
     // var a =; <- Expected expression
-    ExpectedDifferentToken { expected: &'static str }
+    #[error("Expected {}, found {}", .0.expected_str(), .0.found_str())]
+    ExpectedDifferentToken(Box<Mismatch>),
+    // 0x
+    #[error("Number literal has no digits (0x1F)")]
+    InvalidNumber,
+    // 100000...
+    #[error("Number literal has too many digits")]
+    NumberTooLong,
+    // "\xFF"
+    #[error("String literal is not valid UTF-8")]
+    InvalidUtf8,
+    // "\q"
+    #[error("Unknown escape sequence in string literal (\"\\n\")")]
+    InvalidEscape,
+    // ((((((...))))))
+    #[error("Expression is nested too deep")]
+    NestingTooDeep,
+    // let a: 1 + 2 = 3
+    #[error("Expected a type, found a value (let a: int = 1 + 2)")]
+    ExpressionAsType,
+    // let f: int -> int = ...
+    #[error("Parameters of a function type need parentheses ((int) -> int)")]
+    FunctionTypeWithoutParens,
+    // List[]
+    #[error("Expected type arguments in the brackets (List[int])")]
+    EmptyTypeArguments,
+    // List(int)
+    #[error("Type arguments go in square brackets (List[int])")]
+    TypeArgumentsInParens,
+    // { let a = 1; //! docs }
+    #[error("Inner doc comment is not at the start of a file or block (//! docs)")]
+    MisplacedInnerDoc,
+    // bug in the parser, reported instead of panicking
+    #[error("Internal parser error ({0})")]
+    Internal(Text),
+}
+
+impl ParsingError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParsingError::NoOperandAfterPrefixOperator => "E0102",
+            ParsingError::NoOperandBeforePostfixOperation => "E0103",
+            ParsingError::NoOperandBeforeInfixOperation => "E0104",
+            ParsingError::NoOperandAfterInfixOperation => "E0105",
+            ParsingError::UnexpectedCallArgument => "E0106",
+            ParsingError::UnexpectedToken(_) => "E0107",
+            ParsingError::ExpectedDifferentToken(_) => "E0108",
+            ParsingError::InvalidNumber => "E0109",
+            ParsingError::InvalidUtf8 => "E0110",
+            ParsingError::NestingTooDeep => "E0111",
+            ParsingError::InvalidEscape => "E0112",
+            ParsingError::ExpressionAsType => "E0113",
+            ParsingError::FunctionTypeWithoutParens => "E0114",
+            ParsingError::EmptyTypeArguments => "E0115",
+            ParsingError::TypeArgumentsInParens => "E0116",
+            ParsingError::MisplacedInnerDoc => "E0117",
+            ParsingError::NumberTooLong => "E0118",
+            ParsingError::Internal(_) => "E0199",
+        }
+    }
+
+    pub fn fix(&self) -> Option<&Fix> {
+        match self {
+            ParsingError::UnexpectedToken(m) | ParsingError::ExpectedDifferentToken(m) => {
+                m.fix.as_ref()
+            }
+            _ => None,
+        }
+    }
 }
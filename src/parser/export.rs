@@ -0,0 +1,164 @@
+//! The tree in formats for other tools: S-expressions, and JSON with the `serde` feature
+
+use std::fmt::Write;
+
+use crate::{
+    parser::{
+        ast::{Ast, NodeId},
+        expressions::Operation,
+        nodes::NodeKind,
+        types::TypeExpr,
+    },
+    source::interner::Interner,
+};
+
+/// Tree of a file with the names of its identifiers, symbols are indices into `names`
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "AstFileParts")
+)]
+pub struct AstFile {
+    pub names: Interner,
+    pub ast: Ast,
+}
+
+// Deserialized tree, before its symbols are checked against the names
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct AstFileParts {
+    names: Interner,
+    ast: Ast,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<AstFileParts> for AstFile {
+    type Error = String;
+
+    fn try_from(parts: AstFileParts) -> Result<Self, String> {
+        use crate::{parser::errors::ParsingError, tokenizer::token::TokenKind};
+
+        let names = parts.names.len();
+        for id in parts.ast.ids() {
+            let symbol = match &parts.ast[id].kind {
+                NodeKind::Identifier(ident) => Some(ident.name),
+                NodeKind::Error(
+                    ParsingError::UnexpectedToken(m) | ParsingError::ExpectedDifferentToken(m),
+                ) => match m.found {
+                    Some(TokenKind::Identifier(symbol)) => Some(symbol),
+                    _ => None,
+                },
+                _ => None,
+            };
+            if let Some(symbol) = symbol.filter(|s| s.index() >= names) {
+                return Err(format!(
+                    "node {} has unknown symbol {}",
+                    id.index(),
+                    symbol.index()
+                ));
+            }
+        }
+        Ok(Self {
+            names: parts.names,
+            ast: parts.ast,
+        })
+    }
+}
+
+/// One node per line, children indented under the parent:
+/// `(Kind start..end attributes... children...)`
+pub fn to_sexpr(file: &AstFile, src: &[u8]) -> String {
+    let mut out = String::new();
+    for root in &file.ast.roots {
+        write_sexpr(file, src, *root, 0, &mut out);
+        out.push('\n');
+    }
+    out
+}
+
+fn write_sexpr(file: &AstFile, src: &[u8], id: NodeId, indent: usize, out: &mut String) {
+    let node = &file.ast[id];
+    let kind = node.kind.name();
+    let _ = write!(out, "({kind} {}..{}", node.range.start, node.range.end);
+
+    match &node.kind {
+        NodeKind::IntegerLiteral(lit) => {
+            let _ = write!(out, " {}", lit.value);
+        }
+        NodeKind::FloatingLiteral(lit) => {
+            let _ = write!(out, " {:?}", lit.value);
+        }
+        NodeKind::StringLiteral(lit) => {
+            let _ = write!(out, " {:?}", lit.text(src));
+        }
+        NodeKind::BooleanLiteral(lit) => {
+            let _ = write!(out, " {}", lit.value);
+        }
+        NodeKind::Identifier(ident) => {
+            out.push(' ');
+            out.push_str(file.names.resolve(ident.name));
+        }
+        NodeKind::Error(err) => {
+            out.push(' ');
+            out.push_str(err.code());
+        }
+        NodeKind::Operation(op) => {
+            out.push(' ');
+            out.push_str(&operation_name(op));
+        }
+        NodeKind::ValueDeclaration(decl) => {
+            if decl.constant {
+                out.push_str(" const");
+            }
+            out.push_str(if decl.mutable { " var" } else { " let" });
+        }
+        NodeKind::Block(block) if block.returns_last => out.push_str(" returns"),
+        NodeKind::Assignment(assignment) => match &assignment.operation {
+            Some(op) => {
+                out.push(' ');
+                out.push_str(&operation_name(op));
+            }
+            None => out.push_str(" ="),
+        },
+        NodeKind::Type(ty) => out.push_str(match ty {
+            TypeExpr::Named { .. } => " named",
+            TypeExpr::Function { .. } => " function",
+            TypeExpr::Tuple { .. } => " tuple",
+            TypeExpr::Optional { .. } => " optional",
+            TypeExpr::Ref { .. } => " ref",
+        }),
+        _ => (),
+    }
+
+    for child in file.ast.children(id) {
+        out.push('\n');
+        out.push_str(&"  ".repeat(indent + 1));
+        write_sexpr(file, src, child, indent + 1, out);
+    }
+    out.push(')');
+}
+
+// arguments are printed as children
+fn operation_name(op: &Operation) -> String {
+    match op {
+        Operation::FuncCall { .. } => "FuncCall".to_string(),
+        Operation::PartialCall { .. } => "PartialCall".to_string(),
+        Operation::ValueCtor { .. } => "ValueCtor".to_string(),
+        Operation::TypeCtor { .. } => "TypeCtor".to_string(),
+        _ => format!("{op:?}"),
+    }
+}
+
+/// Pretty printed JSON with spans, parent links are left out
+#[cfg(feature = "serde")]
+pub fn to_json(file: &AstFile) -> String {
+    // the tree has no maps with non-string keys, so it always serializes
+    serde_json::to_string_pretty(file).expect("tree is serializable")
+}
+
+/// Reads a tree written by [`to_json`], parent links are restored
+#[cfg(feature = "serde")]
+pub fn from_json(json: &str) -> Result<AstFile, serde_json::Error> {
+    serde_json::from_str(json)
+}
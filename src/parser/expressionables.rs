@@ -1,30 +1,41 @@
 use crate::{
     parser::{
         Parser,
-        errors::ParsingError,
-        nodes::{Node, NodeKind},
+        errors::Expected,
+        ast::NodeId,
+        nodes::NodeKind,
     },
-    tokenizer::token::{Token, TokenKind},
+    tokenizer::token::TokenKind,
 };
 
 /// x: T = default
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LambdaArg {
     /// x
-    pub name: Box<Node>,
+    pub name: NodeId,
     /// T
-    pub type_hint: Option<Box<Node>>,
+    pub type_hint: Option<NodeId>,
     /// default
-    pub default_value: Option<Box<Node>>,
+    pub default_value: Option<NodeId>,
 }
 
 impl LambdaArg {
-    pub fn visit_children<'a>(&'a self, mut visit: impl FnMut(&'a Node)) {
-        visit(&self.name);
-        if let Some(hint) = &self.type_hint {
+    pub fn visit_children(&self, mut visit: impl FnMut(NodeId)) {
+        visit(self.name);
+        if let Some(hint) = self.type_hint {
             visit(hint);
         }
-        if let Some(default) = &self.default_value {
+        if let Some(default) = self.default_value {
+            visit(default);
+        }
+    }
+    pub fn visit_children_mut(&mut self, mut visit: impl FnMut(&mut NodeId)) {
+        visit(&mut self.name);
+        if let Some(hint) = &mut self.type_hint {
+            visit(hint);
+        }
+        if let Some(default) = &mut self.default_value {
             visit(default);
         }
     }
@@ -32,54 +43,61 @@ impl LambdaArg {
 
 /// (a,b,c) -> { ... }
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Lambda {
     /// a, b, c
     pub args: Vec<LambdaArg>,
     /// block or expr
-    pub body: Box<Node>,
+    pub body: NodeId,
 }
 
 impl Lambda {
-    pub fn visit_children<'a>(&'a self, mut visit: impl FnMut(&'a Node)) {
+    pub fn visit_children(&self, mut visit: impl FnMut(NodeId)) {
         for arg in &self.args {
             arg.visit_children(&mut visit);
         }
-        visit(&self.body);
+        visit(self.body);
+    }
+    pub fn visit_children_mut(&mut self, mut visit: impl FnMut(&mut NodeId)) {
+        for arg in &mut self.args {
+            arg.visit_children_mut(&mut visit);
+        }
+        visit(&mut self.body);
     }
 }
 
 impl Parser<'_> {
     fn p_lambda_arg(&mut self) -> Option<LambdaArg> {
-        let name = self.p_identifier()?;
+        let name = self.p_identifier().or_else(|| self.p_dont_care())?;
 
         let type_hint = if self.advance_on(TokenKind::OpTypedef) {
-            Some(Box::new(self.p_expression().unwrap_or_else(|| {
-                self.make_error_here(ParsingError::ExpectedDifferentToken { expected: "type" })
-            })))
+            Some(self.p_type_or_value().unwrap_or_else(|| {
+                self.make_expected_here(vec![Expected::Node("type".into())])
+            }))
         } else {
             None
         };
 
         let default_value = if self.advance_on(TokenKind::OpAsg) {
-            Some(Box::new(self.p_expression().unwrap_or_else(|| {
-                self.make_error_here(ParsingError::ExpectedDifferentToken { expected: "default value" })
-            })))
+            Some(self.p_expression().unwrap_or_else(|| {
+                self.make_expected_here(vec![Expected::Node("default value".into())])
+            }))
         } else {
             None
         };
 
         Some(LambdaArg {
-            name: Box::new(name),
+            name,
             type_hint,
             default_value,
         })
     }
 
-    pub fn p_lambda(&mut self) -> Option<Node> {
+    pub fn p_lambda(&mut self) -> Option<NodeId> {
         self.make_node(|this| {
             // Check for OpLam before consuming args
             let is_lambda = match this.next().map(|t| &t.kind) {
-                Some(TokenKind::Identifier | TokenKind::RoundBraces { .. }) => {
+                Some(TokenKind::Identifier(_) | TokenKind::Ignore | TokenKind::RoundBraces { .. }) => {
                     matches!(this.next_at(1).map(|t| &t.kind), Some(TokenKind::OpLam))
                 }
                 _ => false,
@@ -91,35 +109,37 @@ impl Parser<'_> {
 
             // Parse args
             let args = match &this.next()?.kind {
-                TokenKind::Identifier => {
-                    let name = this.p_identifier()?;
+                TokenKind::Identifier(_) | TokenKind::Ignore => {
+                    let name = this.p_identifier().or_else(|| this.p_dont_care())?;
                     vec![LambdaArg {
-                        name: Box::new(name),
+                        name,
                         type_hint: None,
                         default_value: None,
                     }]
                 }
-                TokenKind::RoundBraces { children } => {
-                    let mut inner = Parser::new(this.src, children);
-                    let mut args = Vec::new();
-
-                    while let Some(arg) = inner.p_lambda_arg() {
-                        args.push(arg);
-                        if !inner.advance_on(TokenKind::OpComma) {
-                            break;
+                TokenKind::RoundBraces { close } => {
+                    let args = this.nested(*close, |inner| {
+                        let mut args = Vec::new();
+
+                        while let Some(arg) = inner.p_lambda_arg() {
+                            args.push(arg);
+                            if !inner.advance_on(TokenKind::OpComma) {
+                                break;
+                            }
                         }
-                    }
-
-                    if inner.pos < children.len() {
-                        args.push(LambdaArg {
-                            name: Box::new(this.make_error_for_tokens(
-                                ParsingError::UnexpectedToken,
-                                &children[inner.pos..],
-                            )),
-                            type_hint: None,
-                            default_value: None,
-                        });
-                    }
+
+                        if !inner.rest().is_empty() {
+                            args.push(LambdaArg {
+                                name: inner.make_unexpected(
+                                    inner.rest(),
+                                    vec![Expected::Token(TokenKind::OpComma), Expected::Brace(')')],
+                                ),
+                                type_hint: None,
+                                default_value: None,
+                            });
+                        }
+                        args
+                    });
 
                     this.advance();
                     args
@@ -129,18 +149,16 @@ impl Parser<'_> {
 
             this.advance(); // consume OpLam
 
+            // a -> b -> c -> ... recurses without braces
+            this.depth += 1;
             let body = this.p_block()
                 .or_else(|| this.p_expression())
                 .unwrap_or_else(|| {
-                    this.make_error_here(ParsingError::ExpectedDifferentToken {
-                        expected: "lambda body",
-                    })
+                    this.make_expected_here(vec![Expected::Node("lambda body".into())])
                 });
+            this.depth -= 1;
 
-            Some(NodeKind::Lambda(Lambda {
-                args,
-                body: Box::new(body),
-            }))
+            Some(NodeKind::Lambda(Lambda { args, body }))
         })
     }
 }
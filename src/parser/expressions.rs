@@ -2,6 +2,7 @@ use super::*;
 use crate::tokenizer::token::TokenKind;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Operation {
     Add, // a + b
     Sub, // a - b
@@ -9,6 +10,7 @@ pub enum Operation {
     Mul, // a * b
     Div, // a / b
     Mod, // a % b
+    Pow, // a ** b
 
     Gt, // a > b
     Ge, // a >= b
@@ -19,27 +21,87 @@ pub enum Operation {
 
     Or,  // a | b
     And, // a & b
+    Xor, // a ^ b
+    Shl, // a << b
+    Shr, // a >> b
     Not, // !a
 
-    Dot, // a . b
-    Try, // a ?
-    Ref, // ref a
-
-    FuncCall { args: Vec<Node> },  // Callable( Args... )
-    ValueCtor { args: Vec<Node> }, // Type{ ValueArgs... }
-    TypeCtor { args: Vec<Node> },  // Type[ TypeArgs... ]
+    LogOr,  // a || b
+    LogAnd, // a && b
+
+    Dot,      // a . b
+    Path,     // a :: b
+    Range,    // a .. b
+    RangeInc, // a ..= b
+    Pipe,     // a |> f
+    Arrow,    // a => b
+    Try,      // a ?
+    Ref,      // ref a
+
+    FuncCall { args: Vec<NodeId> },    // Callable( Args... )
+    PartialCall { args: Vec<NodeId> }, // Callable( _, Args... ), makes a new function
+    ValueCtor { args: Vec<NodeId> },   // Type{ ValueArgs... }
+    TypeCtor { args: Vec<NodeId> },    // Type[ TypeArgs... ]
 }
 
 impl Operation {
-    pub fn visit_children<'a>(&'a self, visit: impl FnMut(&'a Node)) {
-        match &self {
-            Operation::FuncCall { args } => args.iter().for_each(visit),
-            Operation::TypeCtor { args } => args.iter().for_each(visit),
-            Operation::ValueCtor { args } => args.iter().for_each(visit),
+    pub fn visit_children(&self, visit: impl FnMut(NodeId)) {
+        match self {
+            Operation::FuncCall { args }
+            | Operation::PartialCall { args }
+            | Operation::TypeCtor { args }
+            | Operation::ValueCtor { args } => args.iter().copied().for_each(visit),
+            _ => (),
+        }
+    }
+    pub fn visit_children_mut(&mut self, visit: impl FnMut(&mut NodeId)) {
+        match self {
+            Operation::FuncCall { args }
+            | Operation::PartialCall { args }
+            | Operation::TypeCtor { args }
+            | Operation::ValueCtor { args } => args.iter_mut().for_each(visit),
             _ => (),
         }
     }
 
+    /// Spelling in the source, empty for calls
+    pub fn text(&self) -> &'static str {
+        match self {
+            Operation::Add => "+",
+            Operation::Sub | Operation::Neg => "-",
+            Operation::Mul => "*",
+            Operation::Div => "/",
+            Operation::Mod => "%",
+            Operation::Pow => "**",
+            Operation::Gt => ">",
+            Operation::Ge => ">=",
+            Operation::Lt => "<",
+            Operation::Le => "<=",
+            Operation::Ne => "!=",
+            Operation::Eq => "==",
+            Operation::Or => "|",
+            Operation::And => "&",
+            Operation::Xor => "^",
+            Operation::Shl => "<<",
+            Operation::Shr => ">>",
+            Operation::LogOr => "||",
+            Operation::LogAnd => "&&",
+            Operation::Dot => ".",
+            Operation::Path => "::",
+            Operation::Range => "..",
+            Operation::RangeInc => "..=",
+            Operation::Pipe => "|>",
+            Operation::Arrow => "=>",
+            Operation::Not => "!",
+            Operation::Try => "?",
+            Operation::Ref => "ref",
+            Operation::FuncCall { .. }
+            | Operation::PartialCall { .. }
+            | Operation::ValueCtor { .. }
+            | Operation::TypeCtor { .. } => "",
+        }
+    }
+
     fn from_token_prefix(token: &TokenKind) -> Option<Self> {
         match token {
             TokenKind::OpNot => Some(Self::Not),
@@ -64,6 +126,7 @@ impl Operation {
             TokenKind::OpMul => Some(Self::Mul),
             TokenKind::OpDiv => Some(Self::Div),
             TokenKind::OpMod => Some(Self::Mod),
+            TokenKind::OpPow => Some(Self::Pow),
             TokenKind::OpGt => Some(Self::Gt),
             TokenKind::OpGe => Some(Self::Ge),
             TokenKind::OpLt => Some(Self::Lt),
@@ -72,137 +135,148 @@ impl Operation {
             TokenKind::OpEq => Some(Self::Eq),
             TokenKind::OpOr => Some(Self::Or),
             TokenKind::OpAnd => Some(Self::And),
+            TokenKind::OpXor => Some(Self::Xor),
+            TokenKind::OpShl => Some(Self::Shl),
+            TokenKind::OpShr => Some(Self::Shr),
+            TokenKind::OpLogOr => Some(Self::LogOr),
+            TokenKind::OpLogAnd => Some(Self::LogAnd),
             TokenKind::OpDot => Some(Self::Dot),
+            TokenKind::OpPath => Some(Self::Path),
+            TokenKind::OpRange => Some(Self::Range),
+            TokenKind::OpRangeInc => Some(Self::RangeInc),
+            TokenKind::OpPipe => Some(Self::Pipe),
+            TokenKind::OpArrow => Some(Self::Arrow),
             _ => None,
         }
     }
-    fn get_precedence(&self) -> u32 {
+    pub(crate) fn get_precedence(&self) -> u32 {
         // TODO: not the final result, just for fun
         match &self {
+            Operation::Path => 100,
             Operation::Dot
             | Operation::Try
             | Operation::FuncCall { .. }
+            | Operation::PartialCall { .. }
             | Operation::TypeCtor { .. }
             | Operation::ValueCtor { .. } => 90,
             Operation::Neg | Operation::Not | Operation::Ref => 80,
+            Operation::Pow => 75,
             Operation::Mul | Operation::Div | Operation::Mod => 70,
             Operation::Add | Operation::Sub => 60,
+            Operation::Shl | Operation::Shr => 55,
             Operation::Gt | Operation::Ge | Operation::Lt | Operation::Le => 50,
             Operation::Eq | Operation::Ne => 40,
             Operation::And => 30,
+            Operation::Xor => 25,
             Operation::Or => 20,
+            Operation::LogAnd => 16,
+            Operation::LogOr => 14,
+            Operation::Range | Operation::RangeInc => 12,
+            Operation::Pipe => 10,
+            Operation::Arrow => 5,
         }
     }
-    fn is_prefix(&self) -> bool {
+    // a ** b ** c == a ** (b ** c), all other infix ops are left-associative
+    pub(crate) fn is_right_assoc(&self) -> bool {
+        matches!(self, Operation::Pow | Operation::Arrow)
+    }
+    pub(crate) fn is_prefix(&self) -> bool {
         matches!(self, Operation::Neg | Operation::Not | Operation::Ref)
     }
-    fn is_postfix(&self) -> bool {
+    pub(crate) fn is_postfix(&self) -> bool {
         matches!(
             self,
             Operation::Try
                 | Operation::FuncCall { .. }
+                | Operation::PartialCall { .. }
                 | Operation::ValueCtor { .. }
                 | Operation::TypeCtor { .. }
         )
     }
 }
 
-impl Parser<'_> {
-    fn p_operation_prefix(&mut self) -> Option<Node> {
+impl<'a> Parser<'a> {
+    fn p_operation_prefix(&mut self) -> Option<NodeId> {
         self.make_node(|this| {
-            let operation = match this.next() {
-                Some(next) => match Operation::from_token_prefix(&next.kind) {
-                    Some(op) => op,
-                    None => return None,
-                },
-                None => return None,
-            };
+            let operation = Operation::from_token_prefix(&this.next()?.kind)?;
             this.advance();
             Some(NodeKind::Operation(operation))
         })
     }
-    fn p_operation_postfix(&mut self) -> Option<Node> {
+    fn p_operation_postfix(&mut self) -> Option<NodeId> {
         self.make_node(|this| {
-            let mut operation = match this.next() {
-                Some(next) => match Operation::from_token_postfix(&next.kind) {
-                    Some(op) => op,
-                    None => return None,
-                },
-                None => return None,
-            };
-            // Handle call-like operations
-            match &mut operation {
-                Operation::FuncCall { args }
-                | Operation::TypeCtor { args }
-                | Operation::ValueCtor { args } => {
-                    let arg_tokens = match &this.next_unwrap().kind {
-                        TokenKind::CurlyBraces { children }
-                        | TokenKind::RoundBraces { children }
-                        | TokenKind::SquareBraces { children } => children,
-
-                        _ => {
-                            unreachable!("Call-like operation can be parsed only for braces tokens")
-                        }
-                    };
-                    args.extend(this.p_args_list(arg_tokens));
-                }
-
-                _ => (),
+            let mut operation = Operation::from_token_postfix(&this.next()?.kind)?;
+            // Handle call-like operations, they are produced only for braces tokens
+            if let Operation::FuncCall { args } | Operation::ValueCtor { args } = &mut operation
+                && let Some(close) = this.next_unwrap().kind.close()
+            {
+                args.extend(this.p_args_list(close));
+            }
+            // arguments of `T[...]` are types
+            if let Operation::TypeCtor { args } = &mut operation
+                && let Some(close) = this.next_unwrap().kind.close()
+            {
+                args.extend(this.p_type_args(close));
+            }
+            // f(_, b) is a partial application, not a call
+            if let Operation::FuncCall { args } = &mut operation
+                && args.iter().any(|arg| this.ast.is_dont_care(*arg))
+            {
+                operation = Operation::PartialCall {
+                    args: std::mem::take(args),
+                };
             }
             this.advance();
             Some(NodeKind::Operation(operation))
         })
     }
-    fn p_operation_infix(&mut self) -> Option<Node> {
+    fn p_operation_infix(&mut self) -> Option<NodeId> {
         self.make_node(|this| {
-            let operation = match this.next() {
-                Some(next) => match Operation::from_token_infix(&next.kind) {
-                    Some(op) => op,
-                    None => return None,
-                },
-                None => return None,
-            };
+            let operation = Operation::from_token_infix(&this.next()?.kind)?;
             this.advance();
             Some(NodeKind::Operation(operation))
         })
     }
     // expr, expr, ..., expr, with optional trailing comma
-    fn p_args_list(&self, tokens: &[Token]) -> Vec<Node> {
-        let mut inner_parser = Parser::new(self.src, tokens);
-        let mut nodes = Vec::with_capacity(8);
-
-        while let Some(expr) = inner_parser.p_expression() {
-            nodes.push(expr);
-            if !matches!(inner_parser.next().map(|t| &t.kind), Some(TokenKind::OpComma)) {
-                break;
+    fn p_args_list(&mut self, close: usize) -> Vec<NodeId> {
+        self.nested(close, |inner| {
+            let mut nodes = Vec::new();
+
+            while let Some(expr) = inner.p_expression() {
+                nodes.push(expr);
+                if !inner.advance_on(TokenKind::OpComma) {
+                    break;
+                }
             }
-            inner_parser.advance();
-        }
 
-        if inner_parser.pos < tokens.len() {
-            nodes.push(self.make_error_for_tokens(
-                ParsingError::UnexpectedCallArgument,
-                &tokens[inner_parser.pos..],
-            ));
-        }
+            if !inner.rest().is_empty() {
+                nodes.push(
+                    inner.make_error_for_tokens(ParsingError::UnexpectedCallArgument, inner.rest()),
+                );
+            }
 
-        nodes
+            nodes
+        })
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Expression {
-    rpn: Vec<Node>,
+    pub rpn: Vec<NodeId>,
 }
 
 impl Expression {
-    pub fn visit_children<'a>(&'a self, visit: impl FnMut(&'a Node)) {
-        self.rpn.iter().for_each(visit);
+    pub fn visit_children(&self, visit: impl FnMut(NodeId)) {
+        self.rpn.iter().copied().for_each(visit);
+    }
+    pub fn visit_children_mut(&mut self, visit: impl FnMut(&mut NodeId)) {
+        self.rpn.iter_mut().for_each(visit);
     }
 }
 
 impl Parser<'_> {
-    fn p_operand(&mut self) -> Option<Node> {
+    fn p_operand(&mut self) -> Option<NodeId> {
         self.p_lambda()
             .or_else(|| self.p_identifier())
             .or_else(|| self.p_boolean_literal())
@@ -213,37 +287,37 @@ impl Parser<'_> {
             .or_else(|| self.p_nested_expr())
     }
     // (expr)
-    fn p_nested_expr(&mut self) -> Option<Node> {
-        let Some(token) = self.next() else {
-            return None;
-        };
-        let TokenKind::RoundBraces { children } = &token.kind else {
+    fn p_nested_expr(&mut self) -> Option<NodeId> {
+        let token = self.next()?;
+        let TokenKind::RoundBraces { close } = token.kind else {
             return None;
         };
 
-        let mut inner = Parser::new(self.src, children);
-        let mut expr = inner.p_expression().unwrap_or(Node {
-            kind: NodeKind::Expression(Expression { rpn: Vec::new() }),
-            range: token.range.clone(),
-        });
+        let expr = self.nested(close, |inner| {
+            let expr = inner.p_expression().unwrap_or_else(|| {
+                let range = token.range.join(inner.tks[close].range);
+                inner.alloc(NodeKind::Expression(Expression { rpn: Vec::new() }), range)
+            });
 
-        if inner.pos < children.len() {
-            if let NodeKind::Expression(ref mut e) = expr.kind {
-                e.rpn.push(
-                    self.make_error_for_tokens(
-                        ParsingError::UnexpectedToken,
-                        &children[inner.pos..],
-                    ),
+            if !inner.rest().is_empty() {
+                let err = inner.make_unexpected(
+                    inner.rest(),
+                    vec![Expected::Node("operator".into()), Expected::Brace(')')],
                 );
+                if let NodeKind::Expression(e) = &mut inner.ast[expr].kind {
+                    e.rpn.push(err);
+                    inner.ast[err].parent = Some(expr);
+                }
             }
-        }
+            expr
+        });
 
         self.advance();
 
         Some(expr)
     }
     // prefix ops - operand - postfix ops
-    fn p_atom(&mut self, nodes: &mut Vec<Node>) -> usize {
+    fn p_atom(&mut self, nodes: &mut Vec<NodeId>) -> usize {
         let start_len = nodes.len();
         let mut was_prefix = false;
         let mut was_operand = false;
@@ -279,7 +353,7 @@ impl Parser<'_> {
         nodes.len() - start_len
     }
     // atom - infix op - atom - infix op - ...
-    fn p_flat_expr(&mut self) -> Vec<Node> {
+    fn p_flat_expr(&mut self) -> Vec<NodeId> {
         let mut nodes = Vec::with_capacity(16);
         let mut required_atom = false;
 
@@ -313,7 +387,13 @@ impl Parser<'_> {
 
         nodes
     }
-    pub fn p_expression(&mut self) -> Option<Node> {
+    pub fn p_expression(&mut self) -> Option<NodeId> {
+        if self.depth > MAX_DEPTH {
+            self.next()?;
+            let rest = self.rest();
+            self.pos = self.end;
+            return Some(self.make_error_for_tokens(ParsingError::NestingTooDeep, rest));
+        }
         self.make_node(|this| {
             let flat_form = this.p_flat_expr();
 
@@ -322,11 +402,11 @@ impl Parser<'_> {
             }
 
             let mut rpn = Vec::with_capacity(flat_form.len());
-            let mut op_stack = Vec::<Node>::with_capacity(flat_form.len() / 2);
+            let mut op_stack = Vec::<NodeId>::with_capacity(flat_form.len() / 2);
             let mut prefix_stack = Vec::with_capacity(8);
 
             for node in flat_form.into_iter() {
-                match &node.kind {
+                match &this.ast[node].kind {
                     NodeKind::Operation(op) => {
                         if op.is_prefix() {
                             prefix_stack.push(node);
@@ -334,11 +414,13 @@ impl Parser<'_> {
                             rpn.push(node);
                         } else {
                             while let Some(top) = op_stack.last() {
-                                if let NodeKind::Operation(top_op) = &top.kind {
-                                    if top_op.get_precedence() >= op.get_precedence() {
-                                        rpn.push(op_stack.pop().unwrap());
-                                        continue;
-                                    }
+                                if let NodeKind::Operation(top_op) = &this.ast[*top].kind
+                                    && (top_op.get_precedence() > op.get_precedence()
+                                        || top_op.get_precedence() == op.get_precedence()
+                                            && !op.is_right_assoc())
+                                {
+                                    rpn.push(op_stack.pop().unwrap());
+                                    continue;
                                 }
                                 break;
                             }
@@ -1,82 +1,150 @@
+pub mod ast;
+pub mod bigint;
 pub mod debugger;
 pub mod errors;
+pub mod export;
 pub mod expressions;
 pub mod nodes;
 pub mod primitives;
+pub mod printer;
 pub mod statements;
 pub mod expressionables;
-
-use crate::tokenizer::token::{Token, TokenKind};
+pub mod types;
+pub mod visit;
+
+use crate::{
+    source::{FileId, Span},
+    tokenizer::token::{Token, TokenKind},
+};
+use ast::*;
 use errors::*;
 use nodes::*;
 
+/// Braces and lambdas nested deeper than that are reported as an error, not overflowing the stack
+pub const MAX_DEPTH: usize = 256;
+
 pub struct Parser<'a> {
+    pub file: FileId,
     pub pos: usize,
     pub src: &'a [u8],
+    /// all tokens of the file, see [`TokenKind::close`]
     pub tks: &'a [Token],
+    /// index of the closing brace of the current group, or the number of tokens at the top level
+    pub end: usize,
+    /// nesting level of the current braces
+    pub depth: usize,
+    /// all parsed nodes
+    pub ast: Ast,
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(src: &'a [u8], tokens: &'a [Token]) -> Self {
+    pub fn new(file: FileId, src: &'a [u8], tokens: &'a [Token]) -> Self {
+        // most tokens become a single node, the arena rarely has to grow
+        Self::with_ast(file, src, tokens, Ast::with_capacity(tokens.len()))
+    }
+
+    /// Parser adding nodes to an existing tree, for reparsing a part of it
+    pub fn with_ast(file: FileId, src: &'a [u8], tokens: &'a [Token], ast: Ast) -> Self {
         Self {
+            file,
             pos: 0,
             src,
             tks: tokens,
+            end: tokens.len(),
+            depth: 0,
+            ast,
         }
     }
 
+    /// Runs `f` over the children of the braces at the current position, the braces are not consumed.
+    /// Inside, the closing brace is the end of the tokens
+    pub fn nested<T>(&mut self, close: usize, f: impl FnOnce(&mut Self) -> T) -> T {
+        let outer_pos = self.pos;
+        self.pos += 1;
+        let outer_end = std::mem::replace(&mut self.end, close);
+        self.depth += 1;
+
+        let result = f(self);
+
+        self.depth -= 1;
+        self.pos = outer_pos;
+        self.end = outer_end;
+        result
+    }
+
+    /// Tokens from the current position to the end of the braces
+    pub fn rest(&self) -> &'a [Token] {
+        &self.tks[self.pos.min(self.end)..self.end]
+    }
+
+    pub fn alloc(&mut self, kind: NodeKind, range: Span) -> NodeId {
+        self.ast.push(Node::new(kind, range))
+    }
+
     pub fn get_src(&self, range: std::ops::Range<usize>) -> &'a [u8] {
         &self.src[range]
     }
 
     #[inline(always)]
-    pub fn next(&self) -> Option<&Token> {
-        self.tks.get(self.pos)
+    pub fn next(&self) -> Option<&'a Token> {
+        self.tks[..self.end].get(self.pos)
     }
 
     #[inline(always)]
-    pub fn next_unwrap(&self) -> &Token {
+    pub fn next_unwrap(&self) -> &'a Token {
         &self.tks[self.pos]
     }
 
     #[inline(always)]
-    pub fn next_at(&self, n: usize) -> Option<&Token> {
-        self.tks.get(self.pos + n)
+    pub fn next_at(&self, n: usize) -> Option<&'a Token> {
+        // braces count as a single token
+        let mut idx = self.pos;
+        for _ in 0..n {
+            idx = self.tks[..self.end].get(idx)?.kind.close().map_or(idx + 1, |close| close + 1);
+        }
+        self.tks[..self.end].get(idx)
     }
 
+    /// Consumes the next token, braces are consumed with all the children
     #[inline(always)]
-    pub fn advance(&mut self) -> &Token {
-        self.pos += 1;
-        &self.tks[self.pos - 1]
+    pub fn advance(&mut self) -> &'a Token {
+        let token = &self.tks[self.pos];
+        self.pos = token.kind.close().map_or(self.pos + 1, |close| close + 1);
+        token
     }
 
     #[inline(always)]
     pub fn advance_on(&mut self, kind: TokenKind) -> bool {
-        if let Some(token) = self.next() {
-            if token.kind == kind {
-                self.advance();
-                return true;
-            }
+        if let Some(token) = self.next()
+            && token.kind == kind
+        {
+            self.advance();
+            return true;
         }
         false
     }
 
     #[inline(always)]
     pub fn rollback(&mut self) {
-        if self.pos == 0 {
-            unreachable!()
-        }
-
-        self.pos -= 1;
+        self.pos = match self.pos.checked_sub(1).map(|prev| &self.tks[prev].kind) {
+            Some(TokenKind::ClosingBrace { open }) => *open,
+            _ => self.pos.saturating_sub(1),
+        };
     }
 
     #[inline]
-    pub fn make_node(&mut self, f: impl FnOnce(&mut Self) -> Option<NodeKind>) -> Option<Node> {
+    pub fn make_node(&mut self, f: impl FnOnce(&mut Self) -> Option<NodeKind>) -> Option<NodeId> {
         let start_token = self.pos;
-        let node = f(self)?;
+        let mut node = f(self)?;
 
         if self.pos <= start_token {
-            panic!("This may be a bug in the parser, node is returned, but no tokens consumed")
+            // reachable from error recovery, so it is a diagnostic and not an assertion.
+            // A token is skipped anyway, so callers can't get stuck on it
+            self.next()?;
+            self.advance();
+            node = NodeKind::Error(ParsingError::Internal(
+                "node is returned, but no tokens consumed".into(),
+            ));
         }
 
         let start = self.tks.get(start_token).map_or(0, |t| t.range.start);
@@ -86,57 +154,69 @@ impl<'a> Parser<'a> {
             .map_or(self.src.len(), |t| t.range.end)
             .max(start);
 
-        Some(Node {
-            kind: node,
-            range: start..end,
-        })
+        Some(self.alloc(node, Span::new(self.file, start..end)))
     }
 
     #[inline]
-    pub fn make_error_for_tokens(&self, err: ParsingError, tokens: &[Token]) -> Node {
+    pub fn make_error_for_tokens(&mut self, err: ParsingError, tokens: &[Token]) -> NodeId {
         let start = tokens.first().map_or(0, |t| t.range.start);
         let end = tokens.last().map_or(0, |t| t.range.end).max(start);
-        Node {
-            kind: NodeKind::Error(err),
-            range: start..end,
-        }
+        self.alloc(NodeKind::Error(err), Span::new(self.file, start..end))
     }
 
     #[inline]
-    pub fn make_error_for_nodes(&self, err: ParsingError, nodes: &[Node]) -> Node {
-        let start = nodes.first().map_or(0, |n| n.range.start);
-        let end = nodes.last().map_or(0, |n| n.range.end).max(start);
-        Node {
-            kind: NodeKind::Error(err),
-            range: start..end,
-        }
+    pub fn make_error_for_nodes(&mut self, err: ParsingError, nodes: &[NodeId]) -> NodeId {
+        let start = nodes.first().map_or(0, |n| self.ast[*n].range.start);
+        let end = nodes.last().map_or(0, |n| self.ast[*n].range.end).max(start);
+        self.alloc(NodeKind::Error(err), Span::new(self.file, start..end))
     }
 
     #[inline]
-    pub fn make_error_here(&self, err: ParsingError) -> Node {
-        let start = if self.pos > 0 {
-            self.tks[self.pos - 1].range.end
-        } else {
-            0
-        };
-        let end = self.tks.get(self.pos).map(|t| t.range.start).unwrap_or(self.src.len());
-        Node {
-            kind: NodeKind::Error(err),
-            range: start..end,
-        }
+    pub fn make_error_here(&mut self, err: ParsingError) -> NodeId {
+        self.make_error_before_token_at(err, self.pos)
     }
 
+    /// Error for something missing before the next token
     #[inline]
-    pub fn make_error_before_token_at(&self, err: ParsingError, token_idx: usize) -> Node {
-        let start = if token_idx > 0 {
-            self.tks[token_idx - 1].range.end
-        } else {
-            0
+    pub fn make_expected_here(&mut self, expected: Vec<Expected>) -> NodeId {
+        let found = self.next().map(|t| &t.kind);
+        self.make_error_here(ParsingError::ExpectedDifferentToken(Mismatch::new(
+            found, expected,
+        )))
+    }
+
+    /// Error for tokens which can't be parsed
+    #[inline]
+    pub fn make_unexpected(&mut self, tokens: &[Token], expected: Vec<Expected>) -> NodeId {
+        let found = tokens.first().map(|t| &t.kind);
+        self.make_error_for_tokens(
+            ParsingError::UnexpectedToken(Mismatch::new(found, expected)),
+            tokens,
+        )
+    }
+
+    /// Error for something missing, points right after the previous token
+    #[inline]
+    pub fn make_error_before_token_at(&mut self, err: ParsingError, token_idx: usize) -> NodeId {
+        let range = self.point_before_token_at(token_idx);
+        self.alloc(NodeKind::Error(err), range)
+    }
+
+    /// Empty span between the previous and the next token, where a missing token goes
+    #[inline]
+    pub fn point_here(&self) -> Span {
+        self.point_before_token_at(self.pos)
+    }
+
+    pub fn point_before_token_at(&self, token_idx: usize) -> Span {
+        let pos = match token_idx.checked_sub(1).and_then(|i| self.tks.get(i)) {
+            Some(prev) if prev.kind.close().is_none() => prev.range.end,
+            // the first token of braces is pointed at itself, like the first one of the file
+            prev => match self.tks.get(token_idx) {
+                Some(next) if !matches!(next.kind, TokenKind::ClosingBrace { .. }) => next.range.start,
+                _ => prev.map_or(0, |t| t.range.end),
+            },
         };
-        let end = self.tks.get(token_idx).map(|t| t.range.start).unwrap_or(self.src.len());
-        Node {
-            kind: NodeKind::Error(err),
-            range: start..end,
-        }
+        Span::new(self.file, pos..pos)
     }
 }
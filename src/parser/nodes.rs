@@ -1,13 +1,16 @@
-use std::ops::Range;
+use crate::source::Span;
 use crate::parser::{
+    ast::NodeId,
     errors::*,
     expressions::*,
     expressionables::*,
     primitives::*,
     statements::{ValueDeclaration, Block, Assignment},
+    types::TypeExpr,
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NodeKind {
     // primitives
     IntegerLiteral(IntegerLiteral),
@@ -23,33 +26,89 @@ pub enum NodeKind {
     Expression(Expression),
 
     // Statements
-    ValueDeclaration(ValueDeclaration),
-    Block(Block),
+    // declarations and blocks are boxed, they are rare and the biggest payloads
+    ValueDeclaration(Box<ValueDeclaration>),
+    Block(Box<Block>),
     Assignment(Assignment),
 
     // Expressionables
     Lambda(Lambda),
+
+    // Types
+    Type(TypeExpr),
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Node {
     pub kind: NodeKind,
-    pub range: Range<usize>,
+    pub range: Span,
+    /// None for the roots, set when the parent is added to the [`Ast`]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub parent: Option<NodeId>,
 }
 
 impl Node {
-    pub fn new(kind: NodeKind, range: Range<usize>) -> Self {
-        Self { kind, range }
+    pub fn new(kind: NodeKind, range: Span) -> Self {
+        Self {
+            kind,
+            range,
+            parent: None,
+        }
+    }
+
+    pub fn visit_children(&self, visit: impl FnMut(NodeId)) {
+        self.kind.visit_children(visit);
+    }
+    /// Child ids can be changed, when nodes are moved inside of the arena
+    pub fn visit_children_mut(&mut self, visit: impl FnMut(&mut NodeId)) {
+        self.kind.visit_children_mut(visit);
+    }
+}
+
+impl NodeKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeKind::IntegerLiteral(_) => "IntegerLiteral",
+            NodeKind::FloatingLiteral(_) => "FloatingLiteral",
+            NodeKind::StringLiteral(_) => "StringLiteral",
+            NodeKind::BooleanLiteral(_) => "BooleanLiteral",
+            NodeKind::Identifier(_) => "Identifier",
+            NodeKind::DontCare(_) => "DontCare",
+            NodeKind::Error(_) => "Error",
+            NodeKind::Operation(_) => "Operation",
+            NodeKind::Expression(_) => "Expression",
+            NodeKind::ValueDeclaration(_) => "ValueDeclaration",
+            NodeKind::Block(_) => "Block",
+            NodeKind::Assignment(_) => "Assignment",
+            NodeKind::Lambda(_) => "Lambda",
+            NodeKind::Type(_) => "Type",
+        }
     }
 
-    pub fn visit_children<'a>(&'a self, visit: impl FnMut(&'a Node)) {
-        match &self.kind {
+    pub fn visit_children(&self, visit: impl FnMut(NodeId)) {
+        match self {
+            NodeKind::IntegerLiteral(v) => v.visit_children(visit),
             NodeKind::Operation(v) => v.visit_children(visit),
             NodeKind::Expression(v) => v.visit_children(visit),
             NodeKind::ValueDeclaration(v) => v.visit_children(visit),
             NodeKind::Block(v) => v.visit_children(visit),
             NodeKind::Assignment(v) => v.visit_children(visit),
             NodeKind::Lambda(v) => v.visit_children(visit),
+            NodeKind::Type(v) => v.visit_children(visit),
+            _ => (),
+        }
+    }
+    pub fn visit_children_mut(&mut self, visit: impl FnMut(&mut NodeId)) {
+        match self {
+            NodeKind::IntegerLiteral(v) => v.visit_children_mut(visit),
+            NodeKind::Operation(v) => v.visit_children_mut(visit),
+            NodeKind::Expression(v) => v.visit_children_mut(visit),
+            NodeKind::ValueDeclaration(v) => v.visit_children_mut(visit),
+            NodeKind::Block(v) => v.visit_children_mut(visit),
+            NodeKind::Assignment(v) => v.visit_children_mut(visit),
+            NodeKind::Lambda(v) => v.visit_children_mut(visit),
+            NodeKind::Type(v) => v.visit_children_mut(visit),
             _ => (),
         }
     }
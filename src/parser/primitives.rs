@@ -1,102 +1,86 @@
-use crate::{parser::errors::ParsingError, tokenizer::token::TokenKind};
+use crate::{
+    parser::{
+        bigint::{BigUint, MAX_DIGITS},
+        errors::ParsingError,
+    },
+    source::{Span, interner::Symbol},
+    tokenizer::token::TokenKind,
+};
 
 use super::nodes::*;
-use crate::parser::Parser;
+use crate::parser::{Parser, ast::NodeId};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IntegerLiteral {
-    pub value: u64,
-    pub error: Option<Box<Node>>,
+    /// any size, the range of the type is checked in [`consts`](crate::semantic::consts)
+    pub value: BigUint,
+    pub error: Option<NodeId>,
 }
 
 impl IntegerLiteral {
-    pub fn visit_children<'a>(&'a self, mut visit: impl FnMut(&'a Node)) {
-        if let Some(error) = self.error.as_ref() {
+    pub fn visit_children(&self, mut visit: impl FnMut(NodeId)) {
+        if let Some(error) = self.error {
+            visit(error);
+        }
+    }
+    pub fn visit_children_mut(&mut self, mut visit: impl FnMut(&mut NodeId)) {
+        if let Some(error) = &mut self.error {
             visit(error);
         }
     }
 }
 
 impl Parser<'_> {
-    pub fn p_integer_literal(&mut self) -> Option<Node> {
+    pub fn p_integer_literal(&mut self) -> Option<NodeId> {
         self.make_node(|this| {
-            let literal = match this.next().map(|token| &token.kind) {
-                Some(TokenKind::NumBinInt) => {
-                    let token = this.advance().clone();
-                    let bytes = this.get_src(token.range.clone());
-                    let value = u64::from_str_radix(std::str::from_utf8(bytes).unwrap(), 2);
-
-                    match value {
-                        Ok(v) => IntegerLiteral {
-                            value: v,
-                            error: None,
-                        },
-                        Err(_) => IntegerLiteral {
-                            value: 0,
-                            error: Some(Box::new(
-                                this.make_error_for_tokens(ParsingError::IntegerOverflow, &[token]),
-                            )),
-                        },
-                    }
-                }
-                Some(TokenKind::NumHexInt) => {
-                    let token = this.advance().clone();
-                    let bytes = this.get_src(token.range.clone());
-                    let value = u64::from_str_radix(std::str::from_utf8(bytes).unwrap(), 16);
-
-                    match value {
-                        Ok(v) => IntegerLiteral {
-                            value: v,
-                            error: None,
-                        },
-                        Err(_) => IntegerLiteral {
-                            value: 0,
-                            error: Some(Box::new(
-                                this.make_error_for_tokens(ParsingError::IntegerOverflow, &[token]),
-                            )),
-                        },
-                    }
-                }
-                Some(TokenKind::NumDecInt) => {
-                    let token = this.advance().clone();
-                    let bytes = this.get_src(token.range.clone());
-                    let value = str::parse::<u64>(std::str::from_utf8(bytes).unwrap());
-
-                    match value {
-                        Ok(v) => IntegerLiteral {
-                            value: v,
-                            error: None,
-                        },
-                        Err(_) => IntegerLiteral {
-                            value: 0,
-                            error: Some(Box::new(
-                                this.make_error_for_tokens(ParsingError::IntegerOverflow, &[token]),
-                            )),
-                        },
-                    }
-                }
+            // 0b and 0x prefixes are not a part of digits
+            let (radix, prefix) = match this.next().map(|token| &token.kind) {
+                Some(TokenKind::NumBinInt) => (2, 2),
+                Some(TokenKind::NumHexInt) => (16, 2),
+                Some(TokenKind::NumDecInt) => (10, 0),
                 _ => return None,
             };
+            let token = this.advance();
+            let bytes = this.get_src(token.range.as_range());
+
+            let digits = &bytes[prefix..];
+            let value = if digits.len() > MAX_DIGITS {
+                Err(ParsingError::NumberTooLong)
+            } else {
+                BigUint::from_digits(digits, radix).ok_or(ParsingError::InvalidNumber)
+            };
+            let literal = match value {
+                Ok(value) => IntegerLiteral { value, error: None },
+                Err(err) => IntegerLiteral {
+                    value: BigUint::default(),
+                    error: Some(this.make_error_for_tokens(err, std::slice::from_ref(token))),
+                },
+            };
             Some(NodeKind::IntegerLiteral(literal))
         })
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FloatingLiteral {
     pub value: f64,
 }
 
 impl Parser<'_> {
-    pub fn p_floating_literal(&mut self) -> Option<Node> {
+    pub fn p_floating_literal(&mut self) -> Option<NodeId> {
         self.make_node(|this| {
             let literal = match this.next().map(|token| &token.kind) {
                 Some(TokenKind::NumDecFloat) => {
-                    let range = this.advance().range.clone();
-                    let bytes = this.get_src(range);
-                    let value = str::parse::<f64>(std::str::from_utf8(bytes).unwrap()).unwrap();
+                    let range = this.advance().range;
+                    let bytes = this.get_src(range.as_range());
+                    let value = std::str::from_utf8(bytes).ok().and_then(|s| s.parse().ok());
 
-                    FloatingLiteral { value }
+                    match value {
+                        Some(value) => FloatingLiteral { value },
+                        None => return Some(NodeKind::Error(ParsingError::InvalidNumber)),
+                    }
                 }
                 _ => return None,
             };
@@ -106,21 +90,75 @@ impl Parser<'_> {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StringValue {
+    /// Text between the quotes, used as is when there are no escapes
+    Source(Span),
+    Decoded(Box<str>),
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StringLiteral {
-    pub value: String,
+    pub value: StringValue,
+}
+
+impl StringLiteral {
+    pub fn text<'a>(&'a self, src: &'a [u8]) -> &'a str {
+        match &self.value {
+            // checked to be UTF-8 when parsed
+            StringValue::Source(span) => {
+                std::str::from_utf8(&src[span.as_range()]).unwrap_or_default()
+            }
+            StringValue::Decoded(text) => text,
+        }
+    }
+}
+
+// \n, \t, \r, \0, \\, \" and \'
+fn decode_escapes(text: &str) -> Option<String> {
+    let mut decoded = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            decoded.push(c);
+            continue;
+        }
+        decoded.push(match chars.next()? {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            c @ ('\\' | '"' | '\'') => c,
+            _ => return None,
+        });
+    }
+    Some(decoded)
 }
 
 impl Parser<'_> {
-    pub fn p_string_literal(&mut self) -> Option<Node> {
+    pub fn p_string_literal(&mut self) -> Option<NodeId> {
         self.make_node(|this| {
-            // TODO: add escaping chars handling, add interpolation
+            // TODO: add interpolation
             let literal = match this.next().map(|token| &token.kind) {
                 Some(TokenKind::String) => {
-                    let range = this.advance().range.clone();
-                    let bytes = this.get_src(range.start + 1..range.end - 1);
-
-                    StringLiteral {
-                        value: String::from_utf8(bytes.to_vec()).unwrap(),
+                    let range = this.advance().range;
+                    let inner = Span::new(this.file, range.start + 1..range.end - 1);
+                    let Ok(text) = std::str::from_utf8(this.get_src(inner.as_range())) else {
+                        return Some(NodeKind::Error(ParsingError::InvalidUtf8));
+                    };
+
+                    if !text.contains('\\') {
+                        StringLiteral {
+                            value: StringValue::Source(inner),
+                        }
+                    } else {
+                        match decode_escapes(text) {
+                            Some(text) => StringLiteral {
+                                value: StringValue::Decoded(text.into()),
+                            },
+                            None => return Some(NodeKind::Error(ParsingError::InvalidEscape)),
+                        }
                     }
                 }
                 _ => return None,
@@ -131,12 +169,13 @@ impl Parser<'_> {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BooleanLiteral {
     pub value: bool,
 }
 
 impl Parser<'_> {
-    pub fn p_boolean_literal(&mut self) -> Option<Node> {
+    pub fn p_boolean_literal(&mut self) -> Option<NodeId> {
         self.make_node(|this| {
             let literal = match this.next().map(|token| &token.kind) {
                 Some(TokenKind::KwTrue) => BooleanLiteral { value: true },
@@ -150,33 +189,31 @@ impl Parser<'_> {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Identifier {
-    pub value: String,
+    /// interned by the tokenizer
+    pub name: Symbol,
 }
 
 impl Parser<'_> {
-    pub fn p_identifier(&mut self) -> Option<Node> {
+    pub fn p_identifier(&mut self) -> Option<NodeId> {
         self.make_node(|this| {
             let ident = match this.next().map(|token| &token.kind) {
-                Some(TokenKind::Identifier) => {
-                    let range = this.advance().range.clone();
-                    let bytes = this.get_src(range);
-                    Identifier {
-                        value: String::from_utf8(bytes.to_owned()).unwrap(),
-                    }
-                }
+                Some(TokenKind::Identifier(name)) => Identifier { name: *name },
                 _ => return None,
             };
+            this.advance();
             Some(NodeKind::Identifier(ident))
         })
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DontCare {}
 
 impl Parser<'_> {
-    pub fn p_dont_care(&mut self) -> Option<Node> {
+    pub fn p_dont_care(&mut self) -> Option<NodeId> {
         self.make_node(|this| match this.next().map(|token| &token.kind) {
             Some(TokenKind::Ignore) => {
                 this.advance();
@@ -0,0 +1,381 @@
+//! The tree back to ESL text, for code generators and refactorings.
+//! Formatting and redundant parentheses are not kept, so `parse(print(ast))` is the same tree
+//! up to spans and grouping. Error nodes are copied from the source as is
+
+use crate::{
+    parser::{
+        ast::NodeId,
+        export::AstFile,
+        expressionables::Lambda,
+        expressions::{Expression, Operation},
+        nodes::NodeKind,
+        primitives::StringLiteral,
+        statements::{Assignment, Block, ValueDeclaration},
+        types::TypeExpr,
+    },
+    source::Span,
+};
+
+const INDENT: &str = "    ";
+
+/// Prints the docs of the file and every root, one per line
+pub fn to_source(file: &AstFile, src: &[u8]) -> String {
+    let mut printer = Printer::new(file, src);
+    for doc in &file.ast.docs {
+        printer.doc(*doc);
+        printer.out.push('\n');
+    }
+    for root in &file.ast.roots {
+        printer.node(*root);
+        printer.out.push('\n');
+    }
+    printer.out
+}
+
+/// Prints a single node with its children, blocks are indented from the column 0
+pub fn node_to_source(file: &AstFile, src: &[u8], id: NodeId) -> String {
+    let mut printer = Printer::new(file, src);
+    printer.node(id);
+    printer.out
+}
+
+// Expression rebuilt from the RPN, nested expressions are inlined into their parent
+enum Term {
+    Operand(NodeId),
+    Prefix(NodeId, Box<Term>),
+    Postfix(NodeId, Box<Term>),
+    Infix(NodeId, Box<Term>, Box<Term>),
+}
+
+struct Printer<'a> {
+    file: &'a AstFile,
+    src: &'a [u8],
+    indent: usize,
+    out: String,
+}
+
+impl<'a> Printer<'a> {
+    fn new(file: &'a AstFile, src: &'a [u8]) -> Self {
+        Self {
+            file,
+            src,
+            indent: 0,
+            out: String::new(),
+        }
+    }
+
+    fn source(&mut self, id: NodeId) {
+        let range = self.file.ast[id].range.as_range();
+        let text = self.src.get(range).unwrap_or_default();
+        self.out.push_str(&String::from_utf8_lossy(text));
+    }
+
+    fn operation(&self, id: NodeId) -> Option<&'a Operation> {
+        match &self.file.ast[id].kind {
+            NodeKind::Operation(op) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn newline(&mut self) {
+        self.out.push('\n');
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    fn node(&mut self, id: NodeId) {
+        let file = self.file;
+        match &file.ast[id].kind {
+            NodeKind::IntegerLiteral(lit) if lit.error.is_none() => {
+                self.out.push_str(&lit.value.to_string());
+            }
+            NodeKind::FloatingLiteral(lit) => {
+                // `3` would be read back as an integer
+                let text = lit.value.to_string();
+                self.out.push_str(&text);
+                if !text.contains('.') {
+                    self.out.push_str(".0");
+                }
+            }
+            NodeKind::StringLiteral(lit) => self.string(lit),
+            NodeKind::BooleanLiteral(lit) => {
+                self.out.push_str(if lit.value { "true" } else { "false" });
+            }
+            NodeKind::Identifier(ident) => self.out.push_str(file.names.resolve(ident.name)),
+            NodeKind::DontCare(_) => self.out.push('_'),
+            NodeKind::Expression(expr) => self.expression(id, expr),
+            NodeKind::ValueDeclaration(decl) => self.declaration(decl),
+            NodeKind::Block(block) => self.block(block),
+            NodeKind::Assignment(assignment) => self.assignment(assignment),
+            NodeKind::Lambda(lambda) => self.lambda(lambda),
+            NodeKind::Type(ty) => self.type_expr(ty),
+            // operations are printed by their expression
+            NodeKind::IntegerLiteral(_) | NodeKind::Error(_) | NodeKind::Operation(_) => {
+                self.source(id)
+            }
+        }
+    }
+
+    fn string(&mut self, lit: &StringLiteral) {
+        self.out.push('"');
+        for c in lit.text(self.src).chars() {
+            match c {
+                '\n' => self.out.push_str("\\n"),
+                '\t' => self.out.push_str("\\t"),
+                '\r' => self.out.push_str("\\r"),
+                '\0' => self.out.push_str("\\0"),
+                '\\' | '"' => {
+                    self.out.push('\\');
+                    self.out.push(c);
+                }
+                _ => self.out.push(c),
+            }
+        }
+        self.out.push('"');
+    }
+
+    fn doc(&mut self, doc: Span) {
+        let text = self.src.get(doc.as_range()).unwrap_or_default();
+        self.out.push_str(String::from_utf8_lossy(text).trim_end());
+    }
+
+    fn block(&mut self, block: &Block) {
+        if block.items.is_empty() && block.docs.is_empty() {
+            self.out.push_str("{}");
+            return;
+        }
+        self.out.push('{');
+        self.indent += 1;
+        for doc in &block.docs {
+            self.newline();
+            self.doc(*doc);
+        }
+        for (i, item) in block.items.iter().enumerate() {
+            self.newline();
+            self.node(*item);
+            let is_last = i + 1 == block.items.len();
+            if !is_last || !block.returns_last {
+                self.out.push(';');
+            }
+        }
+        self.indent -= 1;
+        self.newline();
+        self.out.push('}');
+    }
+
+    fn declaration(&mut self, decl: &ValueDeclaration) {
+        if decl.constant {
+            self.out.push_str("@const ");
+        }
+        self.out.push_str(if decl.mutable { "var " } else { "let " });
+        self.node(decl.declaration);
+        if let Some(hint) = decl.type_hint {
+            self.out.push_str(": ");
+            self.node(hint);
+        }
+        self.out.push_str(" = ");
+        self.node(decl.assigned_value);
+    }
+
+    fn assignment(&mut self, assignment: &Assignment) {
+        self.node(assignment.target);
+        self.out.push(' ');
+        if let Some(op) = &assignment.operation {
+            self.out.push_str(op.text());
+        }
+        self.out.push_str("= ");
+        self.node(assignment.value);
+    }
+
+    fn lambda(&mut self, lambda: &Lambda) {
+        match lambda.args.as_slice() {
+            [arg] if arg.type_hint.is_none() && arg.default_value.is_none() => self.node(arg.name),
+            args => {
+                self.out.push('(');
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.node(arg.name);
+                    if let Some(hint) = arg.type_hint {
+                        self.out.push_str(": ");
+                        self.node(hint);
+                    }
+                    if let Some(default) = arg.default_value {
+                        self.out.push_str(" = ");
+                        self.node(default);
+                    }
+                }
+                self.out.push(')');
+            }
+        }
+        self.out.push_str(" -> ");
+        self.node(lambda.body);
+    }
+
+    fn type_expr(&mut self, ty: &TypeExpr) {
+        match ty {
+            TypeExpr::Named { name, args } => {
+                self.node(*name);
+                if !args.is_empty() {
+                    self.args("[", args, "]");
+                }
+            }
+            TypeExpr::Function { params, ret } => {
+                self.args("(", params, ") -> ");
+                self.node(*ret);
+            }
+            TypeExpr::Tuple { items } if items.len() == 1 => self.args("(", items, ",)"),
+            TypeExpr::Tuple { items } => self.args("(", items, ")"),
+            TypeExpr::Optional { inner } => {
+                // `ref T?` is `ref (T?)` and a return type takes the `?`
+                let parens = matches!(
+                    self.file.ast[*inner].kind,
+                    NodeKind::Type(TypeExpr::Function { .. } | TypeExpr::Ref { .. })
+                );
+                if parens {
+                    self.args("(", &[*inner], ")");
+                } else {
+                    self.node(*inner);
+                }
+                self.out.push('?');
+            }
+            TypeExpr::Ref { inner } => {
+                self.out.push_str("ref ");
+                self.node(*inner);
+            }
+        }
+    }
+
+    fn expression(&mut self, id: NodeId, expr: &Expression) {
+        match self.term(expr) {
+            Some(term) => self.term_to_source(&term, true),
+            // broken by a parsing error
+            None => self.source(id),
+        }
+    }
+
+    fn term(&self, expr: &Expression) -> Option<Term> {
+        let mut stack = Vec::new();
+        for id in expr.rpn.iter().copied() {
+            let term = match &self.file.ast[id].kind {
+                NodeKind::Operation(op) if op.is_prefix() => {
+                    Term::Prefix(id, Box::new(stack.pop()?))
+                }
+                NodeKind::Operation(op) if op.is_postfix() => {
+                    Term::Postfix(id, Box::new(stack.pop()?))
+                }
+                NodeKind::Operation(_) => {
+                    let rhs = stack.pop()?;
+                    let lhs = stack.pop()?;
+                    Term::Infix(id, Box::new(lhs), Box::new(rhs))
+                }
+                // parentheses of the source, placed again where needed
+                NodeKind::Expression(inner) => self.term(inner)?,
+                _ => Term::Operand(id),
+            };
+            stack.push(term);
+        }
+        let term = stack.pop()?;
+        stack.is_empty().then_some(term)
+    }
+
+    fn infix_precedence(&self, term: &Term) -> Option<u32> {
+        match term {
+            Term::Infix(op, ..) => self.operation(*op).map(Operation::get_precedence),
+            _ => None,
+        }
+    }
+
+    fn grouped(&mut self, parens: bool, term: &Term, tail: bool) {
+        if parens {
+            self.out.push('(');
+            self.term_to_source(term, true);
+            self.out.push(')');
+        } else {
+            self.term_to_source(term, tail);
+        }
+    }
+
+    /// `tail` is set when nothing of the expression follows the term,
+    /// only there a lambda with an expression body can go without parentheses
+    fn term_to_source(&mut self, term: &Term, tail: bool) {
+        match term {
+            Term::Operand(id) => {
+                let parens = !tail
+                    && matches!(&self.file.ast[*id].kind,
+                        NodeKind::Lambda(lambda)
+                            if !matches!(self.file.ast[lambda.body].kind, NodeKind::Block(_)));
+                if parens {
+                    self.out.push('(');
+                }
+                self.node(*id);
+                if parens {
+                    self.out.push(')');
+                }
+            }
+            // prefix operators bind to the operand, before any postfix ones
+            Term::Prefix(op, operand) => {
+                self.out.push_str(match self.operation(*op) {
+                    Some(Operation::Not) => "!",
+                    Some(Operation::Ref) => "ref ",
+                    _ => "-",
+                });
+                let parens = !matches!(**operand, Term::Operand(_) | Term::Prefix(..));
+                self.grouped(parens, operand, tail);
+            }
+            Term::Postfix(op, operand) => {
+                let parens = matches!(**operand, Term::Infix(..));
+                self.grouped(parens, operand, false);
+                match self.operation(*op) {
+                    Some(Operation::FuncCall { args } | Operation::PartialCall { args }) => {
+                        self.args("(", args, ")")
+                    }
+                    Some(Operation::ValueCtor { args }) => self.args("{", args, "}"),
+                    Some(Operation::TypeCtor { args }) => self.args("[", args, "]"),
+                    _ => self.out.push('?'),
+                }
+            }
+            Term::Infix(id, lhs, rhs) => {
+                let Some(op) = self.operation(*id) else {
+                    return;
+                };
+                let precedence = op.get_precedence();
+                let right_assoc = op.is_right_assoc();
+                let lhs_parens = self
+                    .infix_precedence(lhs)
+                    .is_some_and(|p| p < precedence || p == precedence && right_assoc);
+                let rhs_parens = self
+                    .infix_precedence(rhs)
+                    .is_some_and(|p| p < precedence || p == precedence && !right_assoc);
+
+                self.grouped(lhs_parens, lhs, false);
+                match op {
+                    // `1.x` is fine, `1.2` is a float
+                    Operation::Dot if !self.out.ends_with(|c: char| c.is_ascii_digit()) => {
+                        self.out.push('.')
+                    }
+                    Operation::Path => self.out.push_str("::"),
+                    _ => {
+                        self.out.push(' ');
+                        self.out.push_str(op.text());
+                        self.out.push(' ');
+                    }
+                }
+                self.grouped(rhs_parens, rhs, tail);
+            }
+        }
+    }
+
+    fn args(&mut self, open: &str, args: &[NodeId], close: &str) {
+        self.out.push_str(open);
+        for (i, arg) in args.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.node(*arg);
+        }
+        self.out.push_str(close);
+    }
+}
@@ -1,31 +1,39 @@
 use crate::{
     parser::{
         Parser,
-        errors::ParsingError,
+        errors::{Expected, Fix, Mismatch, ParsingError},
         expressions::Operation,
-        nodes::{Node, NodeKind},
+        ast::NodeId,
+        nodes::NodeKind,
     },
-    tokenizer::token::TokenKind,
+    diagnostics::suggest,
+    source::Span,
+    tokenizer::token::{KEYWORDS, Token, TokenKind},
 };
 
 /// target = value, target += value, etc.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Assignment {
-    pub target: Box<Node>,
-    pub value: Box<Node>,
+    pub target: NodeId,
+    pub value: NodeId,
     /// None for `=`, Some(Add) for `+=`, etc.
     pub operation: Option<Operation>,
 }
 
 impl Assignment {
-    pub fn visit_children<'a>(&'a self, mut visit: impl FnMut(&'a Node)) {
-        visit(&self.target);
-        visit(&self.value);
+    pub fn visit_children(&self, mut visit: impl FnMut(NodeId)) {
+        visit(self.target);
+        visit(self.value);
+    }
+    pub fn visit_children_mut(&mut self, mut visit: impl FnMut(&mut NodeId)) {
+        visit(&mut self.target);
+        visit(&mut self.value);
     }
 }
 
 impl Parser<'_> {
-    fn p_assignment_or_expr(&mut self) -> Option<Node> {
+    fn p_assignment_or_expr(&mut self) -> Option<NodeId> {
         let start = self.pos;
         let expr = self.p_expression()?;
 
@@ -36,8 +44,12 @@ impl Parser<'_> {
             Some(TokenKind::OpMulAsg) => Some(Some(Operation::Mul)),
             Some(TokenKind::OpDivAsg) => Some(Some(Operation::Div)),
             Some(TokenKind::OpModAsg) => Some(Some(Operation::Mod)),
+            Some(TokenKind::OpPowAsg) => Some(Some(Operation::Pow)),
             Some(TokenKind::OpAndAsg) => Some(Some(Operation::And)),
             Some(TokenKind::OpOrAsg) => Some(Some(Operation::Or)),
+            Some(TokenKind::OpXorAsg) => Some(Some(Operation::Xor)),
+            Some(TokenKind::OpShlAsg) => Some(Some(Operation::Shl)),
+            Some(TokenKind::OpShrAsg) => Some(Some(Operation::Shr)),
             _ => None,
         };
 
@@ -47,146 +59,380 @@ impl Parser<'_> {
         self.advance();
 
         let value = self.p_expression().unwrap_or_else(|| {
-            self.make_error_here(ParsingError::ExpectedDifferentToken {
-                expected: "value",
-            })
+            self.make_expected_here(vec![Expected::Node("value".into())])
         });
 
-        let range = self.tks.get(start).map_or(0, |t| t.range.start)..value.range.end;
+        let start = self.tks.get(start).map_or(0, |t| t.range.start);
+        let range = Span::new(self.file, start..self.ast[value].range.end);
 
-        Some(Node {
-            kind: NodeKind::Assignment(Assignment {
-                target: Box::new(expr),
-                value: Box::new(value),
-                operation: compound,
-            }),
-            range,
-        })
+        let assignment = Assignment {
+            target: expr,
+            value,
+            operation: compound,
+        };
+        Some(self.alloc(NodeKind::Assignment(assignment), range))
     }
 }
 
 /// { stmt; stmt; expr }
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Block {
     /// list of exprs or statements
-    pub items: Vec<Node>,
+    pub items: Vec<NodeId>,
     /// if block returns value (without ending semicolon)
     pub returns_last: bool,
+    /// leading //! comments, documenting the block itself
+    pub docs: Vec<Span>,
 }
 
 impl Block {
-    pub fn visit_children<'a>(&'a self, visit: impl FnMut(&'a Node)) {
-        self.items.iter().for_each(visit);
+    pub fn visit_children(&self, visit: impl FnMut(NodeId)) {
+        self.items.iter().copied().for_each(visit);
+    }
+    pub fn visit_children_mut(&mut self, visit: impl FnMut(&mut NodeId)) {
+        self.items.iter_mut().for_each(visit);
     }
 }
 
+/// Keywords and attributes starting a new declaration, parsing resumes from them after an error
+fn is_sync_keyword(kind: &TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::KwLet
+            | TokenKind::KwVar
+            | TokenKind::KwFun
+            | TokenKind::KwType
+            | TokenKind::KwStruct
+            | TokenKind::KwEnum
+            | TokenKind::KwTrait
+            | TokenKind::KwUse
+            | TokenKind::KwModule
+            | TokenKind::KwPub
+            | TokenKind::Attribute
+    )
+}
+
 impl Parser<'_> {
-    fn p_statement(&mut self) -> Option<Node> {
+    /// Skips tokens of a broken statement, up to `;` (consumed) or a declaration keyword.
+    /// The end of braces is a synchronization point too, since nested parser ends there.
+    /// Returns an error for the skipped tokens, except ones already reported by the tokenizer
+    fn synchronize(&mut self, expected: Vec<Expected>, fix: Option<Fix>) -> Option<NodeId> {
+        let start = self.pos;
+        self.advance();
+        while let Some(token) = self.next() {
+            if is_sync_keyword(&token.kind) {
+                break;
+            }
+            if token.kind == TokenKind::Semicolon {
+                break;
+            }
+            self.advance();
+        }
+        let is_reported = |t: &Token| matches!(t.kind, TokenKind::Error(_));
+        let skipped = &self.tks[start..self.pos];
+        let first = skipped.iter().position(|t| !is_reported(t));
+        let last = skipped.iter().rposition(|t| !is_reported(t));
+        self.advance_on(TokenKind::Semicolon);
+
+        let tokens = &skipped[first?..=last?];
+        let mut mismatch = Mismatch::new(tokens.first().map(|t| &t.kind), expected);
+        mismatch.fix = fix;
+        Some(self.make_error_for_tokens(ParsingError::UnexpectedToken(mismatch), tokens))
+    }
+
+    /// `whiel x {}` is parsed as an expression `whiel` followed by garbage,
+    /// so the first word of a broken statement is checked for typos
+    fn misspelled_keyword(&self, stmt_start: usize) -> Option<Fix> {
+        let token = self.tks.get(stmt_start)?;
+        if !matches!(token.kind, TokenKind::Identifier(_)) || token.range.len() < 2 {
+            return None;
+        }
+        let word = std::str::from_utf8(self.get_src(token.range.as_range())).ok()?;
+        let keyword = suggest::closest(word, KEYWORDS.iter().filter_map(TokenKind::text))?;
+        Some(Fix::Keyword {
+            span: token.range,
+            keyword: keyword.into(),
+        })
+    }
+    fn p_statement(&mut self) -> Option<NodeId> {
         self.p_value_declaration()
             .or_else(|| self.p_assignment_or_expr())
     }
-    pub fn p_block(&mut self) -> Option<Node> {
+    pub fn p_block(&mut self) -> Option<NodeId> {
         self.make_node(|this| {
-            let TokenKind::CurlyBraces { children } = &this.next()?.kind else {
+            let TokenKind::CurlyBraces { close } = this.next()?.kind else {
                 return None;
             };
 
-            let mut inner = Parser::new(this.src, children);
-            let mut items = Vec::new();
-            let mut returns_last = false;
+            let block = this.nested(close, |inner| inner.p_block_items());
+            this.advance();
+            Some(NodeKind::Block(Box::new(block)))
+        })
+    }
 
-            while let Some(stmt) = inner.p_statement() {
-                items.push(stmt);
-                if !inner.advance_on(TokenKind::Semicolon) {
-                    returns_last = true;
-                    break;
-                }
+    fn p_block_items(&mut self) -> Block {
+        let mut items = Vec::new();
+        let docs = self.p_inner_docs();
+        let mut returns_last = false;
+
+        while self.next().is_some() {
+            if let Some(err) = self.p_misplaced_inner_doc() {
+                items.push(err);
+                continue;
             }
+            returns_last = false;
+            let stmt_start = self.pos;
+            let Some(stmt) = self.p_statement() else {
+                items.extend(self.synchronize(vec![Expected::Node("statement".into())], None));
+                continue;
+            };
+            items.push(stmt);
 
-            if inner.pos < children.len() {
-                items.push(this.make_error_for_tokens(
-                    ParsingError::UnexpectedToken,
-                    &children[inner.pos..],
-                ));
+            if self.advance_on(TokenKind::Semicolon) {
+                continue;
             }
+            let expected = vec![Expected::Token(TokenKind::Semicolon), Expected::Brace('}')];
+            match self.next().map(|t| &t.kind) {
+                None => returns_last = true,
+                // let a = 1 let b = 2
+                Some(kind) if is_sync_keyword(kind) => {
+                    let at = self.point_here();
+                    let mismatch = Mismatch::new(Some(kind), expected)
+                        .with_fix(Fix::Insert { at, text: ";".into() });
+                    items.push(
+                        self.make_error_here(ParsingError::ExpectedDifferentToken(mismatch)),
+                    );
+                }
+                Some(_) => {
+                    let fix = self.misspelled_keyword(stmt_start);
+                    items.extend(self.synchronize(expected, fix));
+                }
+            }
+        }
 
-            this.advance();
-            Some(NodeKind::Block(Block { items, returns_last }))
-        })
+        Block {
+            items,
+            returns_last,
+            docs,
+        }
+    }
+}
+
+impl Parser<'_> {
+    /// `//!` comments at the current position
+    fn p_inner_docs(&mut self) -> Vec<Span> {
+        let mut docs = Vec::new();
+        while let Some(doc) = self.next()
+            && doc.kind == TokenKind::InnerDocComment
+        {
+            docs.push(doc.range);
+            self.advance();
+        }
+        docs
+    }
+
+    /// `//!` comment after the start of the file or block, reported and skipped
+    fn p_misplaced_inner_doc(&mut self) -> Option<NodeId> {
+        let doc = self.next().filter(|t| t.kind == TokenKind::InnerDocComment)?;
+        self.advance();
+        Some(self.make_error_for_tokens(ParsingError::MisplacedInnerDoc, std::slice::from_ref(doc)))
+    }
+
+    /// Top level of a file is a single block, everything else is reported as an error.
+    /// Parsed nodes become roots of the [`Ast`](crate::parser::ast::Ast),
+    /// leading `//!` comments its docs
+    pub fn p_file(&mut self) {
+        let mut nodes = Vec::new();
+        self.ast.docs = self.p_inner_docs();
+        loop {
+            // already reported by the tokenizer
+            while self.next().is_some_and(|t| matches!(t.kind, TokenKind::Error(_))) {
+                self.advance();
+            }
+            if self.next().is_none() {
+                break;
+            }
+            if let Some(err) = self.p_misplaced_inner_doc() {
+                nodes.push(err);
+                continue;
+            }
+            match self.p_block() {
+                Some(block) => nodes.push(block),
+                None => {
+                    let rest = self.rest();
+                    self.pos = self.end;
+                    nodes.push(self.make_unexpected(rest, vec![Expected::Brace('{')]));
+                }
+            }
+        }
+        self.ast.roots = nodes;
+        // reserved for a node per token, most files need less
+        self.ast.shrink_to_fit();
     }
 }
 
-/// let x: T = expr
+/// let x: T = expr, or @const let x: T = expr
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ValueDeclaration {
     /// x
-    pub declaraion: Box<Node>,
+    pub declaration: NodeId,
     /// T
-    pub type_hint: Option<Box<Node>>,
+    pub type_hint: Option<NodeId>,
     /// expr
-    pub assigned_value: Box<Node>,
+    pub assigned_value: NodeId,
     /// let (immutable) or var (mutable)
     pub mutable: bool,
+    /// the `let` or `var` keyword, empty where it is missing
+    pub keyword: Span,
+    /// @const, the value is computed at compile time
+    pub constant: bool,
 
-    /// if there is an error
-    pub error: Option<Box<Node>>,
+    /// errors of the attribute, the keyword and a missing `=`
+    pub errors: Vec<NodeId>,
 }
 
 impl ValueDeclaration {
-    pub fn visit_children<'a>(&'a self, mut visit: impl FnMut(&'a Node)) {
-        visit(&self.declaraion);
-        if let Some(hint) = &self.type_hint {
+    pub fn visit_children(&self, mut visit: impl FnMut(NodeId)) {
+        visit(self.declaration);
+        if let Some(hint) = self.type_hint {
             visit(hint);
         }
-        visit(&self.assigned_value);
-        if let Some(err) = &self.error {
-            visit(err);
+        visit(self.assigned_value);
+        self.errors.iter().copied().for_each(visit);
+    }
+    pub fn visit_children_mut(&mut self, mut visit: impl FnMut(&mut NodeId)) {
+        visit(&mut self.declaration);
+        if let Some(hint) = &mut self.type_hint {
+            visit(hint);
         }
+        visit(&mut self.assigned_value);
+        self.errors.iter_mut().for_each(visit);
     }
 }
 
 impl Parser<'_> {
-    pub fn p_value_declaration(&mut self) -> Option<Node> {
+    /// `@const` before a declaration, unknown attributes are reported with a fix
+    fn p_attribute(&mut self) -> Option<Result<(), NodeId>> {
+        let token = self.next().filter(|t| t.kind == TokenKind::Attribute)?;
+        self.advance();
+        let name = std::str::from_utf8(self.get_src(token.range.as_range())).unwrap_or_default();
+        if name == "@const" {
+            return Some(Ok(()));
+        }
+        let fix = suggest::closest(&name[1..], ["const"]).map(|_| Fix::Keyword {
+            span: token.range,
+            keyword: "@const".into(),
+        });
+        let expected = vec![Expected::Node("`@const`".into())];
+        let mut mismatch = Mismatch::new(Some(&token.kind), expected);
+        mismatch.fix = fix;
+        let err = ParsingError::UnexpectedToken(mismatch);
+        Some(Err(self.make_error_for_tokens(err, std::slice::from_ref(token))))
+    }
+
+    pub fn p_value_declaration(&mut self) -> Option<NodeId> {
         self.make_node(|this| {
-            let mutable = match this.next().map(|t| &t.kind) {
-                Some(TokenKind::KwVar) => true,
-                Some(TokenKind::KwLet) => false,
+            let attribute = this.p_attribute();
+            let mut errors: Vec<_> = attribute.and_then(Result::err).into_iter().collect();
+            let next = this.next();
+            let (mutable, keyword, missing_keyword) = match next.map(|t| &t.kind) {
+                Some(TokenKind::KwVar) => (true, this.advance().range, None),
+                Some(TokenKind::KwLet) => (false, this.advance().range, None),
+                // @const a = 1 -> @const let a = 1, the attribute is consumed already
+                found if attribute.is_some() => {
+                    let at = next.map_or_else(|| this.point_here(), |t| {
+                        Span::new(this.file, t.range.start..t.range.start)
+                    });
+                    let expected = vec![
+                        Expected::Token(TokenKind::KwLet),
+                        Expected::Token(TokenKind::KwVar),
+                    ];
+                    let mut mismatch = Mismatch::new(found, expected);
+                    if matches!(found, Some(TokenKind::Identifier(_) | TokenKind::Ignore)) {
+                        mismatch.fix = Some(Fix::Insert { at, text: "let ".into() });
+                    }
+                    let err = ParsingError::ExpectedDifferentToken(mismatch);
+                    (false, at, Some(this.alloc(NodeKind::Error(err), at)))
+                }
                 _ => return None,
             };
-            this.advance();
+            let keyword_missing = missing_keyword.is_some();
+            let constant = attribute == Some(Ok(()));
+            // @const var a = 1 -> @const let a = 1
+            if constant && mutable {
+                let kind = TokenKind::KwVar;
+                let mismatch = Mismatch::new(Some(&kind), vec![Expected::Token(TokenKind::KwLet)])
+                    .with_fix(Fix::Keyword {
+                        span: keyword,
+                        keyword: "let".into(),
+                    });
+                let err = ParsingError::ExpectedDifferentToken(mismatch);
+                errors.push(this.alloc(NodeKind::Error(err), keyword));
+            }
             // TODO: add destructuring, like let { x, y, z } = make_vec();
-            let decl = this.p_identifier().unwrap_or_else(|| {
-                this.make_error_here(ParsingError::ExpectedDifferentToken {
-                    expected: "variable name",
-                })
-            });
+            let name = this.p_identifier().or_else(|| this.p_dont_care());
+            let decl = match (name, missing_keyword) {
+                (Some(name), missing_keyword) => {
+                    errors.extend(missing_keyword);
+                    name
+                }
+                // @cnst 5: nothing looks like a declaration, the keyword error stands for the name
+                (None, Some(missing_keyword)) => missing_keyword,
+                (None, None) => this.make_expected_here(vec![
+                    Expected::Node("identifier".into()),
+                    Expected::Token(TokenKind::Ignore),
+                ]),
+            };
             let type_hint = if this.advance_on(TokenKind::OpTypedef) {
-                Some(Box::new(this.p_expression().unwrap_or_else(|| {
-                    this.make_error_here(ParsingError::ExpectedDifferentToken { expected: "type" })
-                })))
+                Some(this.p_type_or_value().unwrap_or_else(|| {
+                    this.make_expected_here(vec![Expected::Node("type".into())])
+                }))
             } else {
                 None
             };
-            let error = if this.advance_on(TokenKind::OpAsg) {
-                None
-            } else {
-                Some(Box::new(this.make_error_here(
-                    ParsingError::ExpectedDifferentToken { expected: "=" },
-                )))
+
+            let mut missing_asg = None;
+            let not_declaration = keyword_missing && name.is_none();
+            if !this.advance_on(TokenKind::OpAsg) && !not_declaration {
+                let mut expected = vec![Expected::Token(TokenKind::OpAsg)];
+                if type_hint.is_none() {
+                    expected.insert(0, Expected::Token(TokenKind::OpTypedef));
+                }
+                missing_asg = Some((
+                    this.point_here(),
+                    Mismatch::new(this.next().map(|t| &t.kind), expected),
+                ));
+            }
+            let (expr, missing_asg) = match (this.p_expression(), missing_asg) {
+                (Some(expr), None) => (expr, None),
+                // let a 5 -> let a = 5
+                (Some(expr), Some((at, mismatch))) => {
+                    let mismatch = mismatch.with_fix(Fix::Insert { at, text: " =".into() });
+                    let err = ParsingError::ExpectedDifferentToken(mismatch);
+                    (expr, Some(this.alloc(NodeKind::Error(err), at)))
+                }
+                // the value is missing too, reported once
+                (None, Some((at, mismatch))) => {
+                    let err = ParsingError::ExpectedDifferentToken(mismatch);
+                    (this.alloc(NodeKind::Error(err), at), None)
+                }
+                (None, None) => {
+                    (this.make_expected_here(vec![Expected::Node("variable value".into())]), None)
+                }
             };
-            let expr = this.p_expression().unwrap_or_else(|| {
-                this.make_error_here(ParsingError::ExpectedDifferentToken {
-                    expected: "variable value",
-                })
-            });
 
-            Some(NodeKind::ValueDeclaration(ValueDeclaration {
+            errors.extend(missing_asg);
+            Some(NodeKind::ValueDeclaration(Box::new(ValueDeclaration {
                 mutable,
-                error,
+                keyword,
+                constant: constant && !mutable,
+                errors,
                 type_hint,
-                declaraion: Box::new(decl),
-                assigned_value: Box::new(expr),
-            }))
+                declaration: decl,
+                assigned_value: expr,
+            })))
         })
     }
 }
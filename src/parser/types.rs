@@ -0,0 +1,259 @@
+//! Type grammar of hints and `T[...]` arguments, separate from values.
+//! `int`, `List[T]`, `(A, B) -> C`, `(A, B)`, `T?`, `ref T` and `_` for an inferred type
+
+use crate::{
+    parser::{
+        MAX_DEPTH, Parser,
+        ast::NodeId,
+        errors::{Expected, ParsingError},
+        nodes::NodeKind,
+    },
+    tokenizer::token::TokenKind,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TypeExpr {
+    /// int, List[T]
+    Named { name: NodeId, args: Vec<NodeId> },
+    /// (A, B) -> C
+    Function { params: Vec<NodeId>, ret: NodeId },
+    /// (A, B), `()` is the unit type and `(A,)` has a single item
+    Tuple { items: Vec<NodeId> },
+    /// T?
+    Optional { inner: NodeId },
+    /// ref T
+    Ref { inner: NodeId },
+}
+
+impl TypeExpr {
+    pub fn visit_children(&self, mut visit: impl FnMut(NodeId)) {
+        match self {
+            TypeExpr::Named { name, args } => {
+                visit(*name);
+                args.iter().copied().for_each(visit);
+            }
+            TypeExpr::Function { params, ret } => {
+                params.iter().copied().for_each(&mut visit);
+                visit(*ret);
+            }
+            TypeExpr::Tuple { items } => items.iter().copied().for_each(visit),
+            TypeExpr::Optional { inner } | TypeExpr::Ref { inner } => visit(*inner),
+        }
+    }
+    pub fn visit_children_mut(&mut self, mut visit: impl FnMut(&mut NodeId)) {
+        match self {
+            TypeExpr::Named { name, args } => {
+                visit(name);
+                args.iter_mut().for_each(visit);
+            }
+            TypeExpr::Function { params, ret } => {
+                params.iter_mut().for_each(&mut visit);
+                visit(ret);
+            }
+            TypeExpr::Tuple { items } => items.iter_mut().for_each(visit),
+            TypeExpr::Optional { inner } | TypeExpr::Ref { inner } => visit(inner),
+        }
+    }
+}
+
+/// Tokens starting a value but never a type, `let a: 1 = 1`
+fn starts_value(kind: &TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::NumDecInt
+            | TokenKind::NumBinInt
+            | TokenKind::NumHexInt
+            | TokenKind::NumDecFloat
+            | TokenKind::String
+            | TokenKind::KwTrue
+            | TokenKind::KwFalse
+            | TokenKind::OpSub
+            | TokenKind::OpNot
+            | TokenKind::CurlyBraces { .. }
+    )
+}
+
+/// Tokens continuing a value after a complete type, `let a: int + 1 = 2`
+fn continues_value(kind: &TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::OpAdd
+            | TokenKind::OpSub
+            | TokenKind::OpMul
+            | TokenKind::OpDiv
+            | TokenKind::OpMod
+            | TokenKind::OpPow
+            | TokenKind::OpGt
+            | TokenKind::OpGe
+            | TokenKind::OpLt
+            | TokenKind::OpLe
+            | TokenKind::OpNe
+            | TokenKind::OpEq
+            | TokenKind::OpOr
+            | TokenKind::OpAnd
+            | TokenKind::OpXor
+            | TokenKind::OpShl
+            | TokenKind::OpShr
+            | TokenKind::OpLogOr
+            | TokenKind::OpLogAnd
+            | TokenKind::OpDot
+            | TokenKind::OpRange
+            | TokenKind::OpRangeInc
+            | TokenKind::OpPipe
+            | TokenKind::OpArrow
+            | TokenKind::CurlyBraces { .. }
+    )
+}
+
+impl Parser<'_> {
+    /// Type where a value was written instead is reported as a single error for the whole value
+    pub fn p_type_or_value(&mut self) -> Option<NodeId> {
+        let (start, mark) = (self.pos, self.ast.len());
+        let ty = self.p_type();
+        let is_value = match (ty, self.next()) {
+            (None, Some(token)) => starts_value(&token.kind),
+            (Some(_), Some(token)) => continues_value(&token.kind),
+            (_, None) => false,
+        };
+        if !is_value {
+            return ty;
+        }
+        // the value grammar finds where the value ends, its nodes are not needed
+        self.ast.truncate(mark);
+        self.pos = start;
+        if self.p_expression().is_none() {
+            self.advance();
+        }
+        self.ast.truncate(mark);
+        let tokens = &self.tks[start..self.pos];
+        Some(self.make_error_for_tokens(ParsingError::ExpressionAsType, tokens))
+    }
+
+    pub fn p_type(&mut self) -> Option<NodeId> {
+        if self.depth > MAX_DEPTH {
+            self.next()?;
+            let rest = self.rest();
+            self.pos = self.end;
+            return Some(self.make_error_for_tokens(ParsingError::NestingTooDeep, rest));
+        }
+        let (start, mark) = (self.pos, self.ast.len());
+        let ty = self.make_node(|this| {
+            if !this.advance_on(TokenKind::OpRef) {
+                return None;
+            }
+            this.depth += 1;
+            let inner = this
+                .p_type()
+                .unwrap_or_else(|| this.make_expected_here(vec![Expected::Node("type".into())]));
+            this.depth -= 1;
+            Some(NodeKind::Type(TypeExpr::Ref { inner }))
+        });
+        let mut ty = match ty {
+            Some(ty) => return Some(ty),
+            None => self.p_type_primary()?,
+        };
+        while self.next().is_some_and(|t| t.kind == TokenKind::OpTry) {
+            self.advance();
+            let range = self.ast[ty].range.join(self.tks[self.pos - 1].range);
+            ty = self.alloc(NodeKind::Type(TypeExpr::Optional { inner: ty }), range);
+        }
+
+        // int -> int, the return type is parsed to know where the error ends
+        let is_group = matches!(self.tks[start].kind, TokenKind::RoundBraces { .. });
+        if !is_group && self.advance_on(TokenKind::OpLam) {
+            self.p_type();
+            self.ast.truncate(mark);
+            let tokens = &self.tks[start..self.pos];
+            return Some(
+                self.make_error_for_tokens(ParsingError::FunctionTypeWithoutParens, tokens),
+            );
+        }
+        Some(ty)
+    }
+
+    // name, name[args], _, (group), (tuple) or (params) -> ret
+    fn p_type_primary(&mut self) -> Option<NodeId> {
+        if let Some(dc) = self.p_dont_care() {
+            return Some(dc);
+        }
+        if let Some(TokenKind::RoundBraces { close }) = self.next().map(|t| &t.kind) {
+            return self.p_type_parens(*close);
+        }
+        self.make_node(|this| {
+            let name = this.p_identifier()?;
+            let args = match this.next().map(|t| &t.kind) {
+                Some(TokenKind::SquareBraces { close }) => {
+                    let args = this.p_type_args(*close);
+                    this.advance();
+                    args
+                }
+                // List(int)
+                Some(TokenKind::RoundBraces { close }) => {
+                    let parens = &this.tks[this.pos..=*close];
+                    this.advance();
+                    let err = ParsingError::TypeArgumentsInParens;
+                    vec![this.make_error_for_tokens(err, parens)]
+                }
+                _ => Vec::new(),
+            };
+            Some(NodeKind::Type(TypeExpr::Named { name, args }))
+        })
+    }
+
+    /// Types of `[...]`, at least one is required
+    pub fn p_type_args(&mut self, close: usize) -> Vec<NodeId> {
+        let (types, _) = self.p_type_list(close, ']');
+        if !types.is_empty() {
+            return types;
+        }
+        let brackets = &self.tks[self.pos..=close];
+        vec![self.make_error_for_tokens(ParsingError::EmptyTypeArguments, brackets)]
+    }
+
+    fn p_type_parens(&mut self, close: usize) -> Option<NodeId> {
+        let start = self.pos;
+        let (items, trailing_comma) = self.p_type_list(close, ')');
+        self.advance();
+        if self.advance_on(TokenKind::OpLam) {
+            self.depth += 1;
+            let ret = self.p_type().unwrap_or_else(|| {
+                self.make_expected_here(vec![Expected::Node("return type".into())])
+            });
+            self.depth -= 1;
+            let range = self.tks[start].range.join(self.ast[ret].range);
+            let ty = TypeExpr::Function { params: items, ret };
+            return Some(self.alloc(NodeKind::Type(ty), range));
+        }
+        match items.as_slice() {
+            // (T) is just T
+            [single] if !trailing_comma => Some(*single),
+            _ => {
+                let range = self.tks[start].range.join(self.tks[close].range);
+                Some(self.alloc(NodeKind::Type(TypeExpr::Tuple { items }), range))
+            }
+        }
+    }
+
+    // type, type, ..., type with optional trailing comma, the braces are not consumed
+    fn p_type_list(&mut self, close: usize, closing: char) -> (Vec<NodeId>, bool) {
+        self.nested(close, |inner| {
+            let mut types = Vec::new();
+            let mut trailing_comma = false;
+            while let Some(ty) = inner.p_type_or_value() {
+                types.push(ty);
+                trailing_comma = inner.advance_on(TokenKind::OpComma);
+                if !trailing_comma {
+                    break;
+                }
+            }
+            if !inner.rest().is_empty() {
+                types.push(inner.make_unexpected(
+                    inner.rest(),
+                    vec![Expected::Token(TokenKind::OpComma), Expected::Brace(closing)],
+                ));
+            }
+            (types, trailing_comma)
+        })
+    }
+}
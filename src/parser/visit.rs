@@ -0,0 +1,252 @@
+//! Traversals of the [`Ast`] with a hook per [`NodeKind`].
+//!
+//! Every hook walks into the children by default. An overriding hook can prune the subtree
+//! by not walking, or continue with `payload.visit_children(|c| self.visit_node(ast, c))`
+//! (`self.fold_node` for [`Fold`])
+
+use crate::parser::{
+    ast::{Ast, NodeId},
+    errors::ParsingError,
+    expressionables::Lambda,
+    expressions::{Expression, Operation},
+    nodes::NodeKind,
+    primitives::*,
+    statements::{Assignment, Block, ValueDeclaration},
+    types::TypeExpr,
+};
+
+/// Read-only traversal, `'a` lets visitors keep references into the tree
+pub trait Visitor<'a> {
+    fn visit_node(&mut self, ast: &'a Ast, id: NodeId) {
+        walk_node(self, ast, id);
+    }
+
+    fn visit_integer_literal(&mut self, ast: &'a Ast, _id: NodeId, lit: &'a IntegerLiteral) {
+        lit.visit_children(|c| self.visit_node(ast, c));
+    }
+    fn visit_floating_literal(&mut self, _ast: &'a Ast, _id: NodeId, _lit: &'a FloatingLiteral) {}
+    fn visit_string_literal(&mut self, _ast: &'a Ast, _id: NodeId, _lit: &'a StringLiteral) {}
+    fn visit_boolean_literal(&mut self, _ast: &'a Ast, _id: NodeId, _lit: &'a BooleanLiteral) {}
+    fn visit_identifier(&mut self, _ast: &'a Ast, _id: NodeId, _ident: &'a Identifier) {}
+    fn visit_dont_care(&mut self, _ast: &'a Ast, _id: NodeId, _dc: &'a DontCare) {}
+    fn visit_error(&mut self, _ast: &'a Ast, _id: NodeId, _err: &'a ParsingError) {}
+
+    fn visit_operation(&mut self, ast: &'a Ast, _id: NodeId, op: &'a Operation) {
+        op.visit_children(|c| self.visit_node(ast, c));
+    }
+    fn visit_expression(&mut self, ast: &'a Ast, _id: NodeId, expr: &'a Expression) {
+        expr.visit_children(|c| self.visit_node(ast, c));
+    }
+
+    fn visit_value_declaration(&mut self, ast: &'a Ast, _id: NodeId, decl: &'a ValueDeclaration) {
+        decl.visit_children(|c| self.visit_node(ast, c));
+    }
+    fn visit_block(&mut self, ast: &'a Ast, _id: NodeId, block: &'a Block) {
+        block.visit_children(|c| self.visit_node(ast, c));
+    }
+    fn visit_assignment(&mut self, ast: &'a Ast, _id: NodeId, asg: &'a Assignment) {
+        asg.visit_children(|c| self.visit_node(ast, c));
+    }
+
+    fn visit_lambda(&mut self, ast: &'a Ast, _id: NodeId, lambda: &'a Lambda) {
+        lambda.visit_children(|c| self.visit_node(ast, c));
+    }
+
+    fn visit_type(&mut self, ast: &'a Ast, _id: NodeId, ty: &'a TypeExpr) {
+        ty.visit_children(|c| self.visit_node(ast, c));
+    }
+}
+
+/// Calls the hook of the node kind
+pub fn walk_node<'a, V: Visitor<'a> + ?Sized>(v: &mut V, ast: &'a Ast, id: NodeId) {
+    match &ast[id].kind {
+        NodeKind::IntegerLiteral(x) => v.visit_integer_literal(ast, id, x),
+        NodeKind::FloatingLiteral(x) => v.visit_floating_literal(ast, id, x),
+        NodeKind::StringLiteral(x) => v.visit_string_literal(ast, id, x),
+        NodeKind::BooleanLiteral(x) => v.visit_boolean_literal(ast, id, x),
+        NodeKind::Identifier(x) => v.visit_identifier(ast, id, x),
+        NodeKind::DontCare(dc) => v.visit_dont_care(ast, id, dc),
+        NodeKind::Error(x) => v.visit_error(ast, id, x),
+        NodeKind::Operation(x) => v.visit_operation(ast, id, x),
+        NodeKind::Expression(x) => v.visit_expression(ast, id, x),
+        NodeKind::ValueDeclaration(x) => v.visit_value_declaration(ast, id, x),
+        NodeKind::Block(x) => v.visit_block(ast, id, x),
+        NodeKind::Assignment(x) => v.visit_assignment(ast, id, x),
+        NodeKind::Lambda(x) => v.visit_lambda(ast, id, x),
+        NodeKind::Type(x) => v.visit_type(ast, id, x),
+    }
+}
+
+/// In-place traversal. While a hook runs, its payload is moved out of the tree,
+/// so the hook can change both the payload and the rest of the [`Ast`]
+pub trait VisitorMut {
+    fn visit_node(&mut self, ast: &mut Ast, id: NodeId) {
+        walk_node_mut(self, ast, id);
+    }
+
+    fn visit_integer_literal(&mut self, ast: &mut Ast, _id: NodeId, lit: &mut IntegerLiteral) {
+        lit.visit_children(|c| self.visit_node(ast, c));
+    }
+    fn visit_floating_literal(&mut self, _ast: &mut Ast, _id: NodeId, _lit: &mut FloatingLiteral) {}
+    fn visit_string_literal(&mut self, _ast: &mut Ast, _id: NodeId, _lit: &mut StringLiteral) {}
+    fn visit_boolean_literal(&mut self, _ast: &mut Ast, _id: NodeId, _lit: &mut BooleanLiteral) {}
+    fn visit_identifier(&mut self, _ast: &mut Ast, _id: NodeId, _ident: &mut Identifier) {}
+    fn visit_dont_care(&mut self, _ast: &mut Ast, _id: NodeId, _dc: &mut DontCare) {}
+    fn visit_error(&mut self, _ast: &mut Ast, _id: NodeId, _err: &mut ParsingError) {}
+
+    fn visit_operation(&mut self, ast: &mut Ast, _id: NodeId, op: &mut Operation) {
+        op.visit_children(|c| self.visit_node(ast, c));
+    }
+    fn visit_expression(&mut self, ast: &mut Ast, _id: NodeId, expr: &mut Expression) {
+        expr.visit_children(|c| self.visit_node(ast, c));
+    }
+
+    fn visit_value_declaration(&mut self, ast: &mut Ast, _id: NodeId, decl: &mut ValueDeclaration) {
+        decl.visit_children(|c| self.visit_node(ast, c));
+    }
+    fn visit_block(&mut self, ast: &mut Ast, _id: NodeId, block: &mut Block) {
+        block.visit_children(|c| self.visit_node(ast, c));
+    }
+    fn visit_assignment(&mut self, ast: &mut Ast, _id: NodeId, asg: &mut Assignment) {
+        asg.visit_children(|c| self.visit_node(ast, c));
+    }
+
+    fn visit_lambda(&mut self, ast: &mut Ast, _id: NodeId, lambda: &mut Lambda) {
+        lambda.visit_children(|c| self.visit_node(ast, c));
+    }
+
+    fn visit_type(&mut self, ast: &mut Ast, _id: NodeId, ty: &mut TypeExpr) {
+        ty.visit_children(|c| self.visit_node(ast, c));
+    }
+}
+
+/// Calls the hook of the node kind
+pub fn walk_node_mut<V: VisitorMut + ?Sized>(v: &mut V, ast: &mut Ast, id: NodeId) {
+    let mut kind = take_kind(ast, id);
+    match &mut kind {
+        NodeKind::IntegerLiteral(x) => v.visit_integer_literal(ast, id, x),
+        NodeKind::FloatingLiteral(x) => v.visit_floating_literal(ast, id, x),
+        NodeKind::StringLiteral(x) => v.visit_string_literal(ast, id, x),
+        NodeKind::BooleanLiteral(x) => v.visit_boolean_literal(ast, id, x),
+        NodeKind::Identifier(x) => v.visit_identifier(ast, id, x),
+        NodeKind::DontCare(dc) => v.visit_dont_care(ast, id, dc),
+        NodeKind::Error(x) => v.visit_error(ast, id, x),
+        NodeKind::Operation(x) => v.visit_operation(ast, id, x),
+        NodeKind::Expression(x) => v.visit_expression(ast, id, x),
+        NodeKind::ValueDeclaration(x) => v.visit_value_declaration(ast, id, x),
+        NodeKind::Block(x) => v.visit_block(ast, id, x),
+        NodeKind::Assignment(x) => v.visit_assignment(ast, id, x),
+        NodeKind::Lambda(x) => v.visit_lambda(ast, id, x),
+        NodeKind::Type(x) => v.visit_type(ast, id, x),
+    }
+    ast.set_kind(id, kind);
+}
+
+/// Rewriting traversal, every hook takes the payload and returns the new kind of the node,
+/// which may differ from the original one (desugaring, constant folding)
+pub trait Fold {
+    fn fold_node(&mut self, ast: &mut Ast, id: NodeId) {
+        walk_fold(self, ast, id);
+    }
+
+    fn fold_integer_literal(
+        &mut self,
+        ast: &mut Ast,
+        _id: NodeId,
+        lit: IntegerLiteral,
+    ) -> NodeKind {
+        lit.visit_children(|c| self.fold_node(ast, c));
+        NodeKind::IntegerLiteral(lit)
+    }
+    fn fold_floating_literal(
+        &mut self,
+        _ast: &mut Ast,
+        _id: NodeId,
+        lit: FloatingLiteral,
+    ) -> NodeKind {
+        NodeKind::FloatingLiteral(lit)
+    }
+    fn fold_string_literal(&mut self, _ast: &mut Ast, _id: NodeId, lit: StringLiteral) -> NodeKind {
+        NodeKind::StringLiteral(lit)
+    }
+    fn fold_boolean_literal(
+        &mut self,
+        _ast: &mut Ast,
+        _id: NodeId,
+        lit: BooleanLiteral,
+    ) -> NodeKind {
+        NodeKind::BooleanLiteral(lit)
+    }
+    fn fold_identifier(&mut self, _ast: &mut Ast, _id: NodeId, ident: Identifier) -> NodeKind {
+        NodeKind::Identifier(ident)
+    }
+    fn fold_dont_care(&mut self, _ast: &mut Ast, _id: NodeId, dc: DontCare) -> NodeKind {
+        NodeKind::DontCare(dc)
+    }
+    fn fold_error(&mut self, _ast: &mut Ast, _id: NodeId, err: ParsingError) -> NodeKind {
+        NodeKind::Error(err)
+    }
+
+    fn fold_operation(&mut self, ast: &mut Ast, _id: NodeId, op: Operation) -> NodeKind {
+        op.visit_children(|c| self.fold_node(ast, c));
+        NodeKind::Operation(op)
+    }
+    fn fold_expression(&mut self, ast: &mut Ast, _id: NodeId, expr: Expression) -> NodeKind {
+        expr.visit_children(|c| self.fold_node(ast, c));
+        NodeKind::Expression(expr)
+    }
+
+    fn fold_value_declaration(
+        &mut self,
+        ast: &mut Ast,
+        _id: NodeId,
+        decl: ValueDeclaration,
+    ) -> NodeKind {
+        decl.visit_children(|c| self.fold_node(ast, c));
+        NodeKind::ValueDeclaration(Box::new(decl))
+    }
+    fn fold_block(&mut self, ast: &mut Ast, _id: NodeId, block: Block) -> NodeKind {
+        block.visit_children(|c| self.fold_node(ast, c));
+        NodeKind::Block(Box::new(block))
+    }
+    fn fold_assignment(&mut self, ast: &mut Ast, _id: NodeId, asg: Assignment) -> NodeKind {
+        asg.visit_children(|c| self.fold_node(ast, c));
+        NodeKind::Assignment(asg)
+    }
+
+    fn fold_lambda(&mut self, ast: &mut Ast, _id: NodeId, lambda: Lambda) -> NodeKind {
+        lambda.visit_children(|c| self.fold_node(ast, c));
+        NodeKind::Lambda(lambda)
+    }
+
+    fn fold_type(&mut self, ast: &mut Ast, _id: NodeId, ty: TypeExpr) -> NodeKind {
+        ty.visit_children(|c| self.fold_node(ast, c));
+        NodeKind::Type(ty)
+    }
+}
+
+/// Calls the hook of the node kind
+pub fn walk_fold<F: Fold + ?Sized>(f: &mut F, ast: &mut Ast, id: NodeId) {
+    let kind = match take_kind(ast, id) {
+        NodeKind::IntegerLiteral(x) => f.fold_integer_literal(ast, id, x),
+        NodeKind::FloatingLiteral(x) => f.fold_floating_literal(ast, id, x),
+        NodeKind::StringLiteral(x) => f.fold_string_literal(ast, id, x),
+        NodeKind::BooleanLiteral(x) => f.fold_boolean_literal(ast, id, x),
+        NodeKind::Identifier(x) => f.fold_identifier(ast, id, x),
+        NodeKind::DontCare(dc) => f.fold_dont_care(ast, id, dc),
+        NodeKind::Error(x) => f.fold_error(ast, id, x),
+        NodeKind::Operation(x) => f.fold_operation(ast, id, x),
+        NodeKind::Expression(x) => f.fold_expression(ast, id, x),
+        NodeKind::ValueDeclaration(x) => f.fold_value_declaration(ast, id, *x),
+        NodeKind::Block(x) => f.fold_block(ast, id, *x),
+        NodeKind::Assignment(x) => f.fold_assignment(ast, id, x),
+        NodeKind::Lambda(x) => f.fold_lambda(ast, id, x),
+        NodeKind::Type(x) => f.fold_type(ast, id, x),
+    };
+    ast.set_kind(id, kind);
+}
+
+// the placeholder is a leaf, so a hook looking at the node sees no children
+fn take_kind(ast: &mut Ast, id: NodeId) -> NodeKind {
+    std::mem::replace(&mut ast[id].kind, NodeKind::DontCare(DontCare {}))
+}
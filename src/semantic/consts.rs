@@ -0,0 +1,560 @@
+//! Compile-time evaluation of literals, operators and `@const` bindings.
+//!
+//! Integers are checked against the range of their type, `int` or a sized one like `u8`.
+//! Literals out of range, overflowing results and division by zero are reported where
+//! they happen, and the expressions containing them are not constants.
+//! [`fold`] replaces constant expressions with literals
+
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    parser::{
+        ast::{Ast, NodeId},
+        bigint::BigUint,
+        export::AstFile,
+        expressions::{Expression, Operation},
+        nodes::{Node, NodeKind},
+        primitives::{BooleanLiteral, FloatingLiteral, IntegerLiteral, StringLiteral, StringValue},
+        statements::{Assignment, ValueDeclaration},
+        visit::{Fold, Visitor},
+    },
+    semantic::{
+        errors::ConstError,
+        resolve::Resolution,
+        typeck::Typing,
+        types::{IntType, Type},
+    },
+    source::Span,
+};
+
+/// Value of any integer type, from the minimum of `i128` to the maximum of `u128`.
+/// Operations return None when the result is out of that range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Integer {
+    /// below zero
+    Negative(i128),
+    NonNegative(u128),
+}
+
+impl Integer {
+    pub const ZERO: Integer = Integer::NonNegative(0);
+
+    pub fn from_sign(negative: bool, magnitude: u128) -> Option<Self> {
+        match negative && magnitude > 0 {
+            true => 0i128.checked_sub_unsigned(magnitude).map(Integer::Negative),
+            false => Some(Integer::NonNegative(magnitude)),
+        }
+    }
+
+    pub fn is_negative(self) -> bool {
+        matches!(self, Integer::Negative(_))
+    }
+
+    pub fn magnitude(self) -> u128 {
+        match self {
+            Integer::Negative(value) => value.unsigned_abs(),
+            Integer::NonNegative(value) => value,
+        }
+    }
+
+    pub fn fits(self, ty: IntType) -> bool {
+        match self {
+            Integer::Negative(value) => value >= ty.min(),
+            Integer::NonNegative(value) => value <= ty.max(),
+        }
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        Self::from_sign(!self.is_negative(), self.magnitude())
+    }
+
+    fn add_parts(self, negative: bool, magnitude: u128) -> Option<Self> {
+        let (own, other) = (self.magnitude(), magnitude);
+        match self.is_negative() == negative {
+            true => Self::from_sign(negative, own.checked_add(other)?),
+            false if own >= other => Self::from_sign(self.is_negative(), own - other),
+            false => Self::from_sign(negative, other - own),
+        }
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.add_parts(other.is_negative(), other.magnitude())
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.add_parts(!other.is_negative(), other.magnitude())
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        let magnitude = self.magnitude().checked_mul(other.magnitude())?;
+        Self::from_sign(self.is_negative() != other.is_negative(), magnitude)
+    }
+
+    /// Rounds towards zero, None for zero
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        let magnitude = self.magnitude().checked_div(other.magnitude())?;
+        Self::from_sign(self.is_negative() != other.is_negative(), magnitude)
+    }
+
+    /// Has the sign of `self`, None for zero
+    pub fn checked_rem(self, other: Self) -> Option<Self> {
+        let magnitude = self.magnitude().checked_rem(other.magnitude())?;
+        Self::from_sign(self.is_negative(), magnitude)
+    }
+
+    pub fn checked_pow(self, exp: u128) -> Option<Self> {
+        let odd = exp % 2 == 1;
+        let magnitude = match u32::try_from(exp) {
+            Ok(exp) => self.magnitude().checked_pow(exp)?,
+            // only 0, 1 and -1 survive such an exponent
+            Err(_) if self.magnitude() <= 1 => self.magnitude(),
+            Err(_) => return None,
+        };
+        Self::from_sign(self.is_negative() && odd, magnitude)
+    }
+
+    /// Two's complement, extended to 128 bits
+    fn to_bits(self) -> u128 {
+        match self {
+            Integer::Negative(value) => value as u128,
+            Integer::NonNegative(value) => value,
+        }
+    }
+
+    /// Bits above the width of `ty` are dropped
+    fn from_bits(bits: u128, ty: IntType) -> Self {
+        let unused = 128 - ty.bits;
+        let bits = bits << unused;
+        match ty.signed {
+            true => match (bits as i128) >> unused {
+                value if value < 0 => Integer::Negative(value),
+                value => Integer::NonNegative(value as u128),
+            },
+            false => Integer::NonNegative(bits >> unused),
+        }
+    }
+}
+
+impl fmt::Display for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Integer::Negative(value) => write!(f, "{value}"),
+            Integer::NonNegative(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+/// Value known at compile time
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(Integer),
+    Float(f64),
+    Bool(bool),
+    String(Box<str>),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Constants {
+    /// values of constant expressions, their operations and operands, and `@const` bindings
+    pub values: HashMap<NodeId, Value>,
+    /// in the order they were found
+    pub errors: Vec<(ConstError, Span)>,
+}
+
+impl Constants {
+    pub fn value(&self, id: NodeId) -> Option<&Value> {
+        self.values.get(&id)
+    }
+}
+
+pub fn evaluate(file: &AstFile, src: &[u8], resolution: &Resolution, typing: &Typing) -> Constants {
+    let mut evaluator = Evaluator {
+        src,
+        resolution,
+        typing,
+        constants: Constants::default(),
+    };
+    for root in &file.ast.roots {
+        evaluator.visit_node(&file.ast, *root);
+    }
+    evaluator.constants
+}
+
+struct Evaluator<'a> {
+    src: &'a [u8],
+    resolution: &'a Resolution,
+    typing: &'a Typing,
+    constants: Constants,
+}
+
+impl Evaluator<'_> {
+    fn operand(&self, ast: &Ast, id: NodeId) -> Option<Value> {
+        match &ast[id].kind {
+            NodeKind::FloatingLiteral(lit) => Some(Value::Float(lit.value)),
+            NodeKind::StringLiteral(lit) => Some(Value::String(lit.text(self.src).into())),
+            NodeKind::BooleanLiteral(lit) => Some(Value::Bool(lit.value)),
+            // only `@const` bindings have values
+            NodeKind::Identifier(_) => {
+                let definition = self.resolution.definition(id)?;
+                self.constants.value(definition).cloned()
+            }
+            NodeKind::Expression(_) => self.constants.value(id).cloned(),
+            _ => None,
+        }
+    }
+
+    fn expression(&mut self, ast: &Ast, expr: &Expression) -> Option<Value> {
+        // operands with the spans they cover
+        let mut stack = Vec::<(Option<Value>, Span)>::new();
+        for (i, id) in expr.rpn.iter().copied().enumerate() {
+            let NodeKind::Operation(op) = &ast[id].kind else {
+                let value = match &ast[id].kind {
+                    NodeKind::IntegerLiteral(lit) if lit.error.is_none() => {
+                        let neg = expr.rpn.get(i + 1).filter(|next| {
+                            matches!(ast[**next].kind, NodeKind::Operation(Operation::Neg))
+                        });
+                        let span = match neg {
+                            Some(neg) => ast[*neg].range.join(ast[id].range),
+                            None => ast[id].range,
+                        };
+                        self.literal(id, &lit.value, neg.is_some(), span)
+                    }
+                    _ => self.operand(ast, id),
+                };
+                self.record(id, &value);
+                let span = match &ast[id].kind {
+                    NodeKind::Expression(_) => self.with_parens(ast[id].range),
+                    _ => ast[id].range,
+                };
+                stack.push((value, span));
+                continue;
+            };
+            let op_span = ast[id].range;
+            let (value, span) = if op.is_prefix() || op.is_postfix() {
+                let (operand, span) = stack.pop()?;
+                let span = span.join(op_span);
+                let value = match (op, operand) {
+                    (Operation::Neg | Operation::Not, Some(operand)) => {
+                        self.prefix(op, operand, &self.int_type(id), span)
+                    }
+                    _ => None,
+                };
+                (value, span)
+            } else {
+                let (rhs, rhs_span) = stack.pop()?;
+                let (lhs, lhs_span) = stack.pop()?;
+                let span = lhs_span.join(rhs_span);
+                let value = match (lhs, rhs) {
+                    (Some(lhs), Some(rhs)) => self.binary(op, lhs, rhs, &self.int_type(id), span),
+                    _ => None,
+                };
+                (value, span)
+            };
+            self.record(id, &value);
+            stack.push((value, span));
+        }
+        match stack.pop() {
+            Some((value, _)) if stack.is_empty() => value,
+            _ => None,
+        }
+    }
+
+    // Nested expressions do not cover their parentheses, `1 / (2 - 2)` should be reported whole
+    fn with_parens(&self, span: Span) -> Span {
+        let before = self.src[..span.start].trim_ascii_end();
+        let after = self
+            .src
+            .get(span.end..)
+            .unwrap_or_default()
+            .trim_ascii_start();
+        match (before.last(), after.first()) {
+            (Some(b'('), Some(b')')) => {
+                let end = self.src.len() - after.len() + 1;
+                Span::new(span.file, before.len() - 1..end)
+            }
+            _ => span,
+        }
+    }
+
+    fn record(&mut self, id: NodeId, value: &Option<Value>) {
+        if let Some(value) = value {
+            self.constants.values.insert(id, value.clone());
+        }
+    }
+
+    fn error(&mut self, err: ConstError, span: Span) -> Option<Value> {
+        self.constants.errors.push((err, span));
+        None
+    }
+
+    /// `int` or the sized integer type of a node, `int` for other types
+    fn int_type(&self, id: NodeId) -> Type {
+        self.typing
+            .type_of(id)
+            .filter(|ty| ty.int_type().is_some())
+            .cloned()
+            .unwrap_or(Type::Int)
+    }
+
+    /// `-128` is in range of `i8` while `128` is not, so the `-` is a part of the literal
+    fn literal(&mut self, id: NodeId, value: &BigUint, negated: bool, span: Span) -> Option<Value> {
+        let ty = self.int_type(id);
+        let range = ty.int_type().unwrap_or(IntType::INT);
+        let value = value.to_u128().map(Integer::NonNegative);
+        let in_range = value
+            .and_then(|value| {
+                if negated {
+                    value.checked_neg()
+                } else {
+                    Some(value)
+                }
+            })
+            .is_some_and(|value| value.fits(range));
+        match (value, in_range) {
+            (Some(value), true) => Some(Value::Int(value)),
+            _ => self.error(ConstError::LiteralOutOfRange { ty }, span),
+        }
+    }
+
+    /// Result of an integer operation, reported if it does not fit into the type
+    fn int(
+        &mut self,
+        op: &Operation,
+        value: Option<Integer>,
+        ty: &Type,
+        span: Span,
+    ) -> Option<Value> {
+        let range = ty.int_type().unwrap_or(IntType::INT);
+        match value.filter(|value| value.fits(range)) {
+            Some(value) => Some(Value::Int(value)),
+            None => {
+                let err = ConstError::Overflow {
+                    op: op.text(),
+                    ty: ty.clone(),
+                };
+                self.error(err, span)
+            }
+        }
+    }
+
+    fn prefix(&mut self, op: &Operation, operand: Value, ty: &Type, span: Span) -> Option<Value> {
+        let range = ty.int_type().unwrap_or(IntType::INT);
+        match (op, operand) {
+            (Operation::Neg, Value::Int(value)) => self.int(op, value.checked_neg(), ty, span),
+            (Operation::Neg, Value::Float(value)) => Some(Value::Float(-value)),
+            (Operation::Not, Value::Int(value)) => {
+                Some(Value::Int(Integer::from_bits(!value.to_bits(), range)))
+            }
+            (Operation::Not, Value::Bool(value)) => Some(Value::Bool(!value)),
+            _ => None,
+        }
+    }
+
+    fn binary(
+        &mut self,
+        op: &Operation,
+        lhs: Value,
+        rhs: Value,
+        ty: &Type,
+        span: Span,
+    ) -> Option<Value> {
+        use Value::*;
+
+        let range = ty.int_type().unwrap_or(IntType::INT);
+        let bits = |bits: u128| Some(Int(Integer::from_bits(bits, range)));
+        match (lhs, rhs) {
+            (Int(a), Int(b)) => match op {
+                Operation::Add => self.int(op, a.checked_add(b), ty, span),
+                Operation::Sub => self.int(op, a.checked_sub(b), ty, span),
+                Operation::Mul => self.int(op, a.checked_mul(b), ty, span),
+                Operation::Div | Operation::Mod if b == Integer::ZERO => {
+                    self.error(ConstError::DivisionByZero, span)
+                }
+                Operation::Div => self.int(op, a.checked_div(b), ty, span),
+                Operation::Mod => self.int(op, a.checked_rem(b), ty, span),
+                Operation::Pow if b.is_negative() => self.error(ConstError::NegativeExponent, span),
+                Operation::Pow => self.int(op, a.checked_pow(b.magnitude()), ty, span),
+                Operation::Shl | Operation::Shr
+                    if b.is_negative() || b.magnitude() >= u128::from(range.bits) =>
+                {
+                    let err = ConstError::ShiftOutOfRange {
+                        amount: b,
+                        bits: range.bits,
+                    };
+                    self.error(err, span)
+                }
+                // bits shifted out are lost, like at runtime
+                Operation::Shl => bits(a.to_bits() << b.magnitude()),
+                Operation::Shr if range.signed => {
+                    bits(((a.to_bits() as i128) >> b.magnitude()) as u128)
+                }
+                Operation::Shr => bits(a.to_bits() >> b.magnitude()),
+                Operation::And => bits(a.to_bits() & b.to_bits()),
+                Operation::Or => bits(a.to_bits() | b.to_bits()),
+                Operation::Xor => bits(a.to_bits() ^ b.to_bits()),
+                _ => compare(op, &a, &b),
+            },
+            (Float(a), Float(b)) => match op {
+                Operation::Add => Some(Float(a + b)),
+                Operation::Sub => Some(Float(a - b)),
+                Operation::Mul => Some(Float(a * b)),
+                Operation::Div => Some(Float(a / b)),
+                Operation::Mod => Some(Float(a % b)),
+                Operation::Pow => Some(Float(a.powf(b))),
+                _ => compare(op, &a, &b),
+            },
+            (Bool(a), Bool(b)) => match op {
+                Operation::And | Operation::LogAnd => Some(Bool(a && b)),
+                Operation::Or | Operation::LogOr => Some(Bool(a || b)),
+                Operation::Xor => Some(Bool(a ^ b)),
+                Operation::Eq => Some(Bool(a == b)),
+                Operation::Ne => Some(Bool(a != b)),
+                _ => None,
+            },
+            (String(a), String(b)) => match op {
+                Operation::Add => Some(String(format!("{a}{b}").into())),
+                _ => compare(op, &a, &b),
+            },
+            // mixed types are reported by the type checker
+            _ => None,
+        }
+    }
+}
+
+fn compare<T: PartialOrd>(op: &Operation, a: &T, b: &T) -> Option<Value> {
+    let result = match op {
+        Operation::Eq => a == b,
+        Operation::Ne => a != b,
+        Operation::Lt => a < b,
+        Operation::Le => a <= b,
+        Operation::Gt => a > b,
+        Operation::Ge => a >= b,
+        _ => return None,
+    };
+    Some(Value::Bool(result))
+}
+
+// The first operand or operation without a value, `f` in `@const let a = 1 + f(2)`
+fn first_unknown(ast: &Ast, constants: &Constants, id: NodeId) -> NodeId {
+    let NodeKind::Expression(expr) = &ast[id].kind else {
+        return id;
+    };
+    match expr
+        .rpn
+        .iter()
+        .find(|item| constants.value(**item).is_none())
+    {
+        Some(item) => first_unknown(ast, constants, *item),
+        None => id,
+    }
+}
+
+impl<'a> Visitor<'a> for Evaluator<'_> {
+    fn visit_expression(&mut self, ast: &'a Ast, id: NodeId, expr: &'a Expression) {
+        // nested expressions and arguments first
+        expr.visit_children(|c| self.visit_node(ast, c));
+        let value = self.expression(ast, expr);
+        self.record(id, &value);
+    }
+
+    fn visit_value_declaration(&mut self, ast: &'a Ast, _id: NodeId, decl: &'a ValueDeclaration) {
+        let errors = self.constants.errors.len();
+        decl.visit_children(|c| self.visit_node(ast, c));
+        if !decl.constant {
+            return;
+        }
+        let value = decl.assigned_value;
+        match self.constants.value(value).cloned() {
+            Some(known) => {
+                self.constants.values.insert(decl.declaration, known);
+            }
+            // already reported inside the value
+            None if self.constants.errors.len() > errors => (),
+            None => {
+                let unknown = ast[first_unknown(ast, &self.constants, value)].range;
+                self.error(ConstError::NotConstant { unknown }, ast[value].range);
+            }
+        }
+    }
+}
+
+/// Replaces every constant expression with a literal, negative numbers keep their `-`.
+/// Targets of assignments are left as is. The replaced operands stay in the arena,
+/// but are not reachable from the roots anymore
+pub fn fold(file: &mut AstFile, constants: &Constants) {
+    let mut folder = Folder { constants };
+    for root in file.ast.roots.clone() {
+        folder.fold_node(&mut file.ast, root);
+    }
+}
+
+struct Folder<'a> {
+    constants: &'a Constants,
+}
+
+// Literal with an optional `-`, None for values without one, like NaN
+fn literal(ast: &mut Ast, value: &Value, range: Span) -> Option<Vec<NodeId>> {
+    let (kind, negative) = match value {
+        Value::Int(value) => {
+            let literal = IntegerLiteral {
+                value: BigUint::from(value.magnitude()),
+                error: None,
+            };
+            (NodeKind::IntegerLiteral(literal), value.is_negative())
+        }
+        Value::Float(value) if value.is_finite() => {
+            let literal = FloatingLiteral { value: value.abs() };
+            (NodeKind::FloatingLiteral(literal), value.is_sign_negative())
+        }
+        Value::Float(_) => return None,
+        Value::Bool(value) => (
+            NodeKind::BooleanLiteral(BooleanLiteral { value: *value }),
+            false,
+        ),
+        Value::String(text) => {
+            let literal = StringLiteral {
+                value: StringValue::Decoded(text.clone()),
+            };
+            (NodeKind::StringLiteral(literal), false)
+        }
+    };
+    let mut rpn = vec![ast.push(Node::new(kind, range))];
+    if negative {
+        // empty, siblings should not overlap
+        let at = Span::new(range.file, range.start..range.start);
+        rpn.push(ast.push(Node::new(NodeKind::Operation(Operation::Neg), at)));
+    }
+    Some(rpn)
+}
+
+// `1`, `-1` and `"a"` are literals already
+fn is_literal(ast: &Ast, expr: &Expression) -> bool {
+    expr.rpn.iter().all(|id| match &ast[*id].kind {
+        NodeKind::Operation(op) => matches!(op, Operation::Neg),
+        kind => matches!(
+            kind,
+            NodeKind::IntegerLiteral(_)
+                | NodeKind::FloatingLiteral(_)
+                | NodeKind::StringLiteral(_)
+                | NodeKind::BooleanLiteral(_)
+        ),
+    })
+}
+
+impl Fold for Folder<'_> {
+    fn fold_expression(&mut self, ast: &mut Ast, id: NodeId, expr: Expression) -> NodeKind {
+        if let Some(value) = self.constants.value(id)
+            && !is_literal(ast, &expr)
+            && let Some(rpn) = literal(ast, value, ast[id].range)
+        {
+            return NodeKind::Expression(Expression { rpn });
+        }
+        expr.visit_children(|c| self.fold_node(ast, c));
+        NodeKind::Expression(expr)
+    }
+
+    fn fold_assignment(&mut self, ast: &mut Ast, _id: NodeId, asg: Assignment) -> NodeKind {
+        self.fold_node(ast, asg.value);
+        NodeKind::Assignment(asg)
+    }
+}
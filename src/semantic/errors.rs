@@ -0,0 +1,147 @@
+use thiserror::Error;
+
+use crate::{
+    diagnostics::Severity,
+    semantic::{consts::Integer, types::Type},
+    source::Span,
+};
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ResolveError {
+    // print(vlaue)
+    #[error("Cannot find `{name}` in this scope")]
+    Unresolved {
+        name: Box<str>,
+        /// visible name close to this one
+        similar: Option<Box<str>>,
+    },
+    // f(x); let x = 1;
+    #[error("`{name}` is used before its declaration")]
+    UsedBeforeDeclaration { name: Box<str>, declared_at: Span },
+    // let x = 1; { let x = 2; }
+    #[error("`{name}` shadows an earlier declaration")]
+    Shadowed { name: Box<str>, previous: Span },
+}
+
+impl ResolveError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ResolveError::Unresolved { .. } => "E0201",
+            ResolveError::UsedBeforeDeclaration { .. } => "E0202",
+            ResolveError::Shadowed { .. } => "W0201",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            ResolveError::Shadowed { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum MutabilityError {
+    // let a = 1; a += 1
+    #[error("Cannot assign to `{name}`, it is declared with `let`")]
+    AssignToLet {
+        name: Box<str>,
+        declared_at: Span,
+        /// the `let` keyword, replaced with `var` by the fix
+        keyword: Span,
+    },
+    // (a) -> { a = 1 }
+    #[error("Cannot assign to argument `{name}`")]
+    AssignToArgument { name: Box<str>, declared_at: Span },
+    // @const let a = 1; a = 2
+    #[error("Cannot assign to `{name}`, it is a compile time constant")]
+    AssignToConst { name: Box<str>, declared_at: Span },
+}
+
+impl MutabilityError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            MutabilityError::AssignToLet { .. } => "E0301",
+            MutabilityError::AssignToArgument { .. } => "E0302",
+            MutabilityError::AssignToConst { .. } => "E0303",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum TypeError {
+    // let a: int = "text"
+    #[error("Mismatched types, expected `{expected}`, found `{found}`")]
+    Mismatch {
+        expected: Type,
+        found: Type,
+        /// hint or declaration the expected type comes from
+        expected_at: Option<Span>,
+    },
+    // f(1, 2) for f = x -> x
+    #[error("Function takes {expected} arguments, but {found} were given")]
+    ArgumentCount { expected: usize, found: usize },
+    // 1(2)
+    #[error("`{0}` is not a function")]
+    NotCallable(Type),
+    // true + 1
+    #[error("Operator `{op}` can not be applied to `{found}`")]
+    UnsupportedOperand { op: &'static str, found: Type },
+    // f = x -> x(x)
+    #[error("Type `{0}` would contain itself")]
+    Recursive(Type),
+}
+
+impl TypeError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            TypeError::Mismatch { .. } => "E0401",
+            TypeError::ArgumentCount { .. } => "E0402",
+            TypeError::NotCallable(_) => "E0403",
+            TypeError::UnsupportedOperand { .. } => "E0404",
+            TypeError::Recursive(_) => "E0405",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ConstError {
+    // 9223372036854775807 + 1
+    #[error("Integer overflow in `{op}`")]
+    Overflow { op: &'static str, ty: Type },
+    // let a: u8 = 256
+    #[error("Integer literal out of range for `{ty}`")]
+    LiteralOutOfRange { ty: Type },
+    // 1 / 0
+    #[error("Division by zero")]
+    DivisionByZero,
+    // 1 << 64
+    #[error("Shift by {amount} bits is out of range")]
+    ShiftOutOfRange {
+        amount: Integer,
+        /// width of the shifted value
+        bits: u32,
+    },
+    // 2 ** -1
+    #[error("Integer power with a negative exponent")]
+    NegativeExponent,
+    // @const let a = f(1)
+    #[error("Value of a `@const` is not known at compile time")]
+    NotConstant {
+        /// first part of the value which is not a constant
+        unknown: Span,
+    },
+}
+
+impl ConstError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ConstError::Overflow { .. } => "E0501",
+            ConstError::DivisionByZero => "E0502",
+            ConstError::ShiftOutOfRange { .. } => "E0503",
+            ConstError::NegativeExponent => "E0504",
+            ConstError::NotConstant { .. } => "E0505",
+            ConstError::LiteralOutOfRange { .. } => "E0506",
+        }
+    }
+}
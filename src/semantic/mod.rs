@@ -0,0 +1,50 @@
+//! Passes over a parsed tree. They leave the [`Ast`](crate::parser::ast::Ast) as is
+//! and return side tables keyed by [`NodeId`](crate::parser::ast::NodeId),
+//! except [`consts::fold`], which rewrites constant expressions
+
+pub mod consts;
+pub mod errors;
+pub mod mutability;
+pub mod resolve;
+pub mod typeck;
+pub mod types;
+
+use crate::{
+    diagnostics::{self, Diagnostic},
+    parser::export::AstFile,
+    semantic::{consts::Constants, resolve::Resolution, typeck::Typing},
+    tokenizer::token::Token,
+};
+
+/// Side tables of every pass over a file, with the diagnostics of all stages
+pub struct Analysis {
+    pub resolution: Resolution,
+    pub typing: Typing,
+    pub constants: Constants,
+    /// tokenizer and parser errors too, in the order of the source
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Runs the passes in order, each one uses the tables of the previous ones
+pub fn analyze(tokens: &[Token], file: &AstFile, src: &[u8]) -> Analysis {
+    let mut diagnostics = diagnostics::collect(tokens, &file.ast);
+    let resolution = resolve::resolve(file);
+    diagnostics::collect_resolver(&resolution, &mut diagnostics);
+    diagnostics::collect_mutability(&mutability::check(file, &resolution), &mut diagnostics);
+    let typing = typeck::check(file, &resolution);
+    diagnostics::collect_types(&typing, &mut diagnostics);
+    let constants = consts::evaluate(file, src, &resolution, &typing);
+    diagnostics::collect_consts(&constants, &mut diagnostics);
+    diagnostics.sort_by_key(|d| d.primary.span.start);
+    Analysis {
+        resolution,
+        typing,
+        constants,
+        diagnostics,
+    }
+}
+
+/// Diagnostics of the whole front end for a parsed file
+pub fn check(tokens: &[Token], file: &AstFile, src: &[u8]) -> Vec<Diagnostic> {
+    analyze(tokens, file, src).diagnostics
+}
@@ -0,0 +1,100 @@
+//! Assignments to `let` and `@const` bindings and lambda arguments.
+//! `a = 1`, `a += 1`, `a.b.c = 1` and `a[i]?.b = 1` all change `a`, so `a` has to be a `var`
+
+use crate::{
+    parser::{
+        ast::{Ast, NodeId},
+        export::AstFile,
+        expressions::Operation,
+        nodes::NodeKind,
+    },
+    semantic::{errors::MutabilityError, resolve::Resolution},
+    source::Span,
+};
+
+/// Errors in the order of the assignments
+pub fn check(file: &AstFile, resolution: &Resolution) -> Vec<(MutabilityError, Span)> {
+    let ast = &file.ast;
+    let mut errors = Vec::new();
+    for id in ast.ids() {
+        let NodeKind::Assignment(assignment) = &ast[id].kind else {
+            continue;
+        };
+        let Some(target) = assigned_name(ast, assignment.target) else {
+            continue;
+        };
+        let Some(definition) = resolution.definition(target) else {
+            continue;
+        };
+        let NodeKind::Identifier(ident) = &ast[definition].kind else {
+            continue;
+        };
+        let name = file.names.resolve(ident.name).into();
+        let declared_at = ast[definition].range;
+
+        let err = match ast.parent(definition).map(|parent| &ast[parent].kind) {
+            Some(NodeKind::ValueDeclaration(decl)) if decl.constant => {
+                MutabilityError::AssignToConst { name, declared_at }
+            }
+            Some(NodeKind::ValueDeclaration(decl)) if !decl.mutable => {
+                MutabilityError::AssignToLet {
+                    name,
+                    declared_at,
+                    keyword: decl.keyword,
+                }
+            }
+            Some(NodeKind::Lambda(_)) => {
+                MutabilityError::AssignToArgument { name, declared_at }
+            }
+            _ => continue,
+        };
+        errors.push((err, ast[id].range));
+    }
+    errors
+}
+
+// Identifier changed by the assignment: `a`, `(a)`, or the root of a place reached through
+// members and postfix operators, like `a.b[i]?.c`
+fn assigned_name(ast: &Ast, id: NodeId) -> Option<NodeId> {
+    match &ast[id].kind {
+        NodeKind::Identifier(_) => Some(id),
+        NodeKind::Expression(expr) => {
+            // the operator of the root comes last: `a?.c` is `a ? c .`, so the operand
+            // of a postfix operator and the left side of a dot are before it
+            let mut rpn = expr.rpn.as_slice();
+            while let [rest @ .., last] = rpn {
+                rpn = match &ast[*last].kind {
+                    NodeKind::Operation(Operation::Dot) => {
+                        &rest[..last_operand_start(ast, rest)?]
+                    }
+                    NodeKind::Operation(op) if op.is_postfix() => rest,
+                    _ => break,
+                };
+            }
+            match rpn {
+                [single] => assigned_name(ast, *single),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// Index where the last complete operand of the RPN starts
+fn last_operand_start(ast: &Ast, rpn: &[NodeId]) -> Option<usize> {
+    let mut needed = 1;
+    for (idx, id) in rpn.iter().enumerate().rev() {
+        needed -= 1;
+        if let NodeKind::Operation(op) = &ast[*id].kind {
+            needed += if op.is_prefix() || op.is_postfix() {
+                1
+            } else {
+                2
+            };
+        }
+        if needed == 0 {
+            return Some(idx);
+        }
+    }
+    None
+}
@@ -0,0 +1,235 @@
+//! Links every identifier use to the declaration introducing it.
+//!
+//! Blocks and lambdas open lexical scopes. A `let`/`var` is visible from the next statement,
+//! except inside lambdas of its own value, so `let f = x -> f(x)` is recursive.
+//! Types and members live in other namespaces and are not resolved here:
+//! type hints, `T[...]`, the type of `T{...}`, both sides of `::` and the right side of `.`.
+//! On the left of an assignment `a[...]` indexes the value `a`, so `a` is resolved there
+
+use std::collections::HashMap;
+
+use crate::{
+    diagnostics::suggest,
+    parser::{
+        ast::{Ast, NodeId},
+        export::AstFile,
+        expressionables::Lambda,
+        expressions::{Expression, Operation},
+        nodes::NodeKind,
+        primitives::Identifier,
+        statements::{Assignment, Block, ValueDeclaration},
+        visit::Visitor,
+    },
+    semantic::errors::ResolveError,
+    source::{Span, interner::Symbol},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Resolution {
+    /// identifier use -> identifier of its `let`/`var` or lambda argument
+    pub definitions: HashMap<NodeId, NodeId>,
+    /// in the order of the traversal
+    pub errors: Vec<(ResolveError, Span)>,
+}
+
+impl Resolution {
+    pub fn definition(&self, use_id: NodeId) -> Option<NodeId> {
+        self.definitions.get(&use_id).copied()
+    }
+}
+
+#[derive(Default)]
+struct Scope {
+    /// visible bindings, later ones shadow earlier ones
+    bindings: Vec<(Symbol, NodeId)>,
+    /// declarations of the block below the current statement
+    later: Vec<(Symbol, NodeId)>,
+}
+
+struct Resolver<'a> {
+    file: &'a AstFile,
+    scopes: Vec<Scope>,
+    resolution: Resolution,
+    /// target of the assignment being visited
+    target: Option<NodeId>,
+}
+
+pub fn resolve(file: &AstFile) -> Resolution {
+    let mut resolver = Resolver {
+        file,
+        scopes: Vec::new(),
+        resolution: Resolution::default(),
+        target: None,
+    };
+    for root in &file.ast.roots {
+        resolver.visit_node(&file.ast, *root);
+    }
+    resolver.resolution
+}
+
+impl Resolver<'_> {
+    fn name(&self, symbol: Symbol) -> Box<str> {
+        self.file.names.resolve(symbol).into()
+    }
+
+    fn lookup(&self, symbol: Symbol) -> Option<NodeId> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.bindings.iter().rev())
+            .find(|(name, _)| *name == symbol)
+            .map(|(_, id)| *id)
+    }
+
+    fn declare(&mut self, ast: &Ast, id: NodeId) {
+        let NodeKind::Identifier(ident) = &ast[id].kind else {
+            // `_` binds nothing
+            return;
+        };
+        if let Some(previous) = self.lookup(ident.name) {
+            let err = ResolveError::Shadowed {
+                name: self.name(ident.name),
+                previous: ast[previous].range,
+            };
+            self.resolution.errors.push((err, ast[id].range));
+        }
+        let Some(scope) = self.scopes.last_mut() else {
+            return;
+        };
+        scope.later.retain(|(_, later)| *later != id);
+        scope.bindings.push((ident.name, id));
+    }
+
+    fn in_scope(&mut self, f: impl FnOnce(&mut Self)) {
+        self.scopes.push(Scope::default());
+        f(self);
+        self.scopes.pop();
+    }
+
+    fn unresolved(&self, symbol: Symbol) -> ResolveError {
+        let name = self.name(symbol);
+        // declared further down, the order matters
+        let later = self
+            .scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.later.iter())
+            .find(|(later, _)| *later == symbol);
+        if let Some((_, declaration)) = later {
+            return ResolveError::UsedBeforeDeclaration {
+                name,
+                declared_at: self.file.ast[*declaration].range,
+            };
+        }
+
+        let names = &self.file.names;
+        let visible = self
+            .scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.bindings.iter().rev())
+            .map(|(name, _)| names.resolve(*name));
+        // any visible one letter name is a single edit away from another one
+        let similar = if name.chars().count() > 2 {
+            suggest::closest(&name, visible).map(Into::into)
+        } else {
+            None
+        };
+        ResolveError::Unresolved { name, similar }
+    }
+}
+
+impl<'a> Visitor<'a> for Resolver<'a> {
+    fn visit_identifier(&mut self, ast: &'a Ast, id: NodeId, ident: &'a Identifier) {
+        match self.lookup(ident.name) {
+            Some(definition) => {
+                self.resolution.definitions.insert(id, definition);
+            }
+            None => {
+                let err = self.unresolved(ident.name);
+                self.resolution.errors.push((err, ast[id].range));
+            }
+        }
+    }
+
+    fn visit_expression(&mut self, ast: &'a Ast, id: NodeId, expr: &'a Expression) {
+        let target = self.target == Some(id);
+        // operands of every operation on the stack, to find the ones in type and member positions
+        let mut stack = Vec::<Vec<NodeId>>::new();
+        let mut skipped = Vec::new();
+        for id in expr.rpn.iter().copied() {
+            let NodeKind::Operation(op) = &ast[id].kind else {
+                stack.push(vec![id]);
+                continue;
+            };
+            let count = if op.is_prefix() || op.is_postfix() {
+                1
+            } else {
+                2
+            };
+            let operands = stack.split_off(stack.len().saturating_sub(count));
+            match op {
+                Operation::Path => skipped.extend(operands.iter().flatten()),
+                Operation::Dot => skipped.extend(operands.iter().skip(1).flatten()),
+                Operation::TypeCtor { .. } if target => (),
+                Operation::ValueCtor { .. } | Operation::TypeCtor { .. } => {
+                    skipped.extend(operands.iter().flatten())
+                }
+                _ => (),
+            }
+            stack.push(operands.concat());
+        }
+
+        for id in expr.rpn.iter().copied() {
+            match &ast[id].kind {
+                // arguments of `T[...]` are types too
+                NodeKind::Operation(Operation::TypeCtor { .. }) => (),
+                NodeKind::Identifier(_) if skipped.contains(&id) => (),
+                _ => self.visit_node(ast, id),
+            }
+        }
+    }
+
+    fn visit_assignment(&mut self, ast: &'a Ast, _id: NodeId, asg: &'a Assignment) {
+        self.target = Some(asg.target);
+        asg.visit_children(|c| self.visit_node(ast, c));
+    }
+
+    fn visit_value_declaration(&mut self, ast: &'a Ast, _id: NodeId, decl: &'a ValueDeclaration) {
+        // a lambda can call itself by the name it is assigned to
+        if ast.is_lambda(decl.assigned_value) {
+            self.declare(ast, decl.declaration);
+            self.visit_node(ast, decl.assigned_value);
+        } else {
+            self.visit_node(ast, decl.assigned_value);
+            self.declare(ast, decl.declaration);
+        }
+    }
+
+    fn visit_block(&mut self, ast: &'a Ast, _id: NodeId, block: &'a Block) {
+        self.in_scope(|this| {
+            let scope = this.scopes.last_mut().expect("scope was just pushed");
+            for item in &block.items {
+                if let NodeKind::ValueDeclaration(decl) = &ast[*item].kind
+                    && let NodeKind::Identifier(ident) = &ast[decl.declaration].kind
+                {
+                    scope.later.push((ident.name, decl.declaration));
+                }
+            }
+            block.visit_children(|item| this.visit_node(ast, item));
+        });
+    }
+
+    fn visit_lambda(&mut self, ast: &'a Ast, _id: NodeId, lambda: &'a Lambda) {
+        self.in_scope(|this| {
+            // defaults see the arguments before them
+            for arg in &lambda.args {
+                if let Some(default) = arg.default_value {
+                    this.visit_node(ast, default);
+                }
+                this.declare(ast, arg.name);
+            }
+            this.visit_node(ast, lambda.body);
+        });
+    }
+}
@@ -0,0 +1,683 @@
+//! Hindley–Milner type inference.
+//!
+//! Every expression gets a type, unknown ones start as variables and are solved by unification.
+//! A `let` of a lambda is generalized, so `let id = x -> x` works for any argument type.
+//! Operators accept a class of types (numbers, `+` on strings, ...), checked after
+//! everything is unified. Variables left in a numeric class default to `int`.
+//! Integer literals take the sized type they are used as, like `u8` in `let a: u8 = 1`
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    parser::{
+        ast::NodeId,
+        export::AstFile,
+        expressionables::Lambda,
+        expressions::{Expression, Operation},
+        nodes::NodeKind,
+        statements::{Assignment, Block, ValueDeclaration},
+        types::TypeExpr,
+    },
+    semantic::{
+        errors::TypeError,
+        resolve::Resolution,
+        types::{Scheme, Type, TypeVar},
+    },
+    source::Span,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Typing {
+    /// types of expressions, their operations and operands, bindings and type hints
+    pub types: HashMap<NodeId, Type>,
+    /// in the order they were found
+    pub errors: Vec<(TypeError, Span)>,
+}
+
+impl Typing {
+    pub fn type_of(&self, id: NodeId) -> Option<&Type> {
+        self.types.get(&id)
+    }
+}
+
+/// Types accepted by an operator
+#[derive(Debug, Clone, Copy)]
+enum Class {
+    /// - * / % **
+    Number,
+    /// +
+    Addable,
+    /// < <= > >=
+    Ordered,
+    /// << >> ..
+    Integer,
+    /// & | ^ !
+    Bitwise,
+}
+
+impl Class {
+    fn contains(self, ty: &Type) -> bool {
+        match self {
+            Class::Number => matches!(ty, Type::Int | Type::Sized(_) | Type::Float),
+            Class::Addable | Class::Ordered => {
+                matches!(ty, Type::Int | Type::Sized(_) | Type::Float | Type::String)
+            }
+            Class::Integer => matches!(ty, Type::Int | Type::Sized(_)),
+            Class::Bitwise => matches!(ty, Type::Int | Type::Sized(_) | Type::Bool),
+        }
+    }
+}
+
+struct Constraint {
+    ty: Type,
+    class: Class,
+    op: &'static str,
+    span: Span,
+}
+
+enum UnifyError {
+    Mismatch,
+    Recursive,
+}
+
+struct Checker<'a> {
+    file: &'a AstFile,
+    resolution: &'a Resolution,
+    /// solutions of the variables, indexed by [`TypeVar`]
+    bindings: Vec<Option<Type>>,
+    /// variables of integer literals, solved only to integer types and `int` by default
+    int_vars: HashSet<TypeVar>,
+    /// bindings keyed by their identifier, as in [`Resolution::definitions`]
+    env: HashMap<NodeId, Scheme>,
+    constraints: Vec<Constraint>,
+    typing: Typing,
+}
+
+pub fn check(file: &AstFile, resolution: &Resolution) -> Typing {
+    let mut checker = Checker {
+        file,
+        resolution,
+        bindings: Vec::new(),
+        int_vars: HashSet::new(),
+        env: HashMap::new(),
+        constraints: Vec::new(),
+        typing: Typing::default(),
+    };
+    for root in &file.ast.roots {
+        checker.infer(*root);
+    }
+    checker.default_ints();
+    checker.solve_constraints();
+
+    let mut typing = std::mem::take(&mut checker.typing);
+    for ty in typing.types.values_mut() {
+        *ty = checker.zonk(ty);
+    }
+    typing
+}
+
+impl Checker<'_> {
+    fn fresh(&mut self) -> Type {
+        self.bindings.push(None);
+        Type::Var(TypeVar(self.bindings.len() as u32 - 1))
+    }
+
+    fn fresh_int(&mut self) -> Type {
+        let ty = self.fresh();
+        if let Type::Var(var) = ty {
+            self.int_vars.insert(var);
+        }
+        ty
+    }
+
+    fn default_ints(&mut self) {
+        for var in &self.int_vars {
+            if let Type::Var(var) = self.shallow(&Type::Var(*var)) {
+                self.bindings[var.0 as usize] = Some(Type::Int);
+            }
+        }
+    }
+
+    fn span(&self, id: NodeId) -> Span {
+        self.file.ast[id].range
+    }
+
+    fn error(&mut self, err: TypeError, span: Span) {
+        self.typing.errors.push((err, span));
+    }
+
+    /// Follows solved variables at the top level
+    fn shallow(&self, ty: &Type) -> Type {
+        let mut ty = ty.clone();
+        while let Type::Var(var) = ty
+            && let Some(solved) = &self.bindings[var.0 as usize]
+        {
+            ty = solved.clone();
+        }
+        ty
+    }
+
+    /// Replaces every solved variable
+    fn zonk(&self, ty: &Type) -> Type {
+        match self.shallow(ty) {
+            Type::Function { params, ret } => Type::Function {
+                params: params.iter().map(|param| self.zonk(param)).collect(),
+                ret: Box::new(self.zonk(&ret)),
+            },
+            Type::Named { name, args } => Type::Named {
+                name,
+                args: args.iter().map(|arg| self.zonk(arg)).collect(),
+            },
+            Type::Tuple(items) => Type::Tuple(items.iter().map(|item| self.zonk(item)).collect()),
+            Type::Optional(inner) => Type::Optional(Box::new(self.zonk(&inner))),
+            Type::Ref(inner) => Type::Ref(Box::new(self.zonk(&inner))),
+            ty => ty,
+        }
+    }
+
+    /// Type for messages, integer literals not solved yet are `int`
+    fn report(&self, ty: &Type) -> Type {
+        let ty = self.zonk(ty);
+        let mut ints = HashMap::new();
+        ty.visit_vars(&mut |var| {
+            if self.int_vars.contains(&var) {
+                ints.insert(var, Type::Int);
+            }
+        });
+        substitute(&ty, &ints)
+    }
+
+    fn occurs(&self, var: TypeVar, ty: &Type) -> bool {
+        let mut found = false;
+        self.zonk(ty).visit_vars(&mut |v| found |= v == var);
+        found
+    }
+
+    fn unify(&mut self, a: &Type, b: &Type) -> Result<(), UnifyError> {
+        match (self.shallow(a), self.shallow(b)) {
+            (Type::Var(a), Type::Var(b)) if a == b => Ok(()),
+            (Type::Var(var), ty) | (ty, Type::Var(var)) => {
+                if self.occurs(var, &ty) {
+                    return Err(UnifyError::Recursive);
+                }
+                if self.int_vars.contains(&var) {
+                    match &ty {
+                        Type::Var(other) => {
+                            self.int_vars.insert(*other);
+                        }
+                        Type::Int | Type::Sized(_) => (),
+                        _ => return Err(UnifyError::Mismatch),
+                    }
+                }
+                self.bindings[var.0 as usize] = Some(ty);
+                Ok(())
+            }
+            (
+                Type::Function { params, ret },
+                Type::Function {
+                    params: other_params,
+                    ret: other_ret,
+                },
+            ) if params.len() == other_params.len() => {
+                for (param, other) in params.iter().zip(&other_params) {
+                    self.unify(param, other)?;
+                }
+                self.unify(&ret, &other_ret)
+            }
+            (
+                Type::Named { name, args },
+                Type::Named {
+                    name: other_name,
+                    args: other_args,
+                },
+            ) if name == other_name && args.len() == other_args.len() => {
+                for (arg, other) in args.iter().zip(&other_args) {
+                    self.unify(arg, other)?;
+                }
+                Ok(())
+            }
+            (Type::Tuple(items), Type::Tuple(other_items)) if items.len() == other_items.len() => {
+                for (item, other) in items.iter().zip(&other_items) {
+                    self.unify(item, other)?;
+                }
+                Ok(())
+            }
+            (Type::Optional(inner), Type::Optional(other))
+            | (Type::Ref(inner), Type::Ref(other)) => self.unify(&inner, &other),
+            (a, b) if a == b => Ok(()),
+            _ => Err(UnifyError::Mismatch),
+        }
+    }
+
+    /// Unifies and reports a mismatch at `span`, the place of the `found` value
+    fn expect(&mut self, expected: &Type, found: &Type, span: Span, expected_at: Option<Span>) {
+        let err = match self.unify(expected, found) {
+            Ok(()) => return,
+            Err(UnifyError::Mismatch) => TypeError::Mismatch {
+                expected: self.report(expected),
+                found: self.report(found),
+                expected_at,
+            },
+            Err(UnifyError::Recursive) => TypeError::Recursive(self.zonk(found)),
+        };
+        self.error(err, span);
+    }
+
+    fn constrain(&mut self, ty: &Type, class: Class, op: &'static str, span: Span) {
+        self.constraints.push(Constraint {
+            ty: ty.clone(),
+            class,
+            op,
+            span,
+        });
+    }
+
+    fn solve_constraints(&mut self) {
+        for constraint in std::mem::take(&mut self.constraints) {
+            let ty = match self.shallow(&constraint.ty) {
+                // every class has `int`
+                Type::Var(var) => {
+                    self.bindings[var.0 as usize] = Some(Type::Int);
+                    continue;
+                }
+                ty => ty,
+            };
+            if !constraint.class.contains(&ty) {
+                let err = TypeError::UnsupportedOperand {
+                    op: constraint.op,
+                    found: self.zonk(&ty),
+                };
+                self.error(err, constraint.span);
+            }
+        }
+    }
+
+    fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        let fresh = scheme
+            .vars
+            .iter()
+            .map(|var| (*var, self.fresh()))
+            .collect::<HashMap<_, _>>();
+        substitute(&self.zonk(&scheme.ty), &fresh)
+    }
+
+    /// Variables not bound by the environment or operator constraints become parameters
+    fn generalize(&self, ty: &Type) -> Scheme {
+        let ty = self.zonk(ty);
+        let mut fixed = HashSet::new();
+        for scheme in self.env.values() {
+            self.zonk(&scheme.ty).visit_vars(&mut |var| {
+                if !scheme.vars.contains(&var) {
+                    fixed.insert(var);
+                }
+            });
+        }
+        // a class is checked once, so `let add = (a, b) -> a + b` stays monomorphic
+        for constraint in &self.constraints {
+            self.zonk(&constraint.ty).visit_vars(&mut |var| {
+                fixed.insert(var);
+            });
+        }
+        fixed.extend(&self.int_vars);
+        let mut vars = Vec::new();
+        ty.visit_vars(&mut |var| {
+            if !fixed.contains(&var) && !vars.contains(&var) {
+                vars.push(var);
+            }
+        });
+        Scheme { vars, ty }
+    }
+
+    fn infer(&mut self, id: NodeId) -> Type {
+        let file = self.file;
+        let ty = match &file.ast[id].kind {
+            NodeKind::IntegerLiteral(_) => self.fresh_int(),
+            NodeKind::FloatingLiteral(_) => Type::Float,
+            NodeKind::StringLiteral(_) => Type::String,
+            NodeKind::BooleanLiteral(_) => Type::Bool,
+            NodeKind::Identifier(_) => match self.resolution.definition(id) {
+                Some(definition) => match self.env.get(&definition).cloned() {
+                    Some(scheme) => self.instantiate(&scheme),
+                    // used before its declaration, already reported
+                    None => self.fresh(),
+                },
+                None => self.fresh(),
+            },
+            NodeKind::DontCare(_) | NodeKind::Error(_) | NodeKind::Operation(_) => self.fresh(),
+            NodeKind::Expression(expr) => self.expression(expr),
+            NodeKind::ValueDeclaration(decl) => {
+                self.declaration(decl);
+                Type::Unit
+            }
+            NodeKind::Block(block) => self.block(block),
+            NodeKind::Assignment(assignment) => {
+                self.assignment(id, assignment);
+                Type::Unit
+            }
+            NodeKind::Lambda(lambda) => self.lambda(lambda),
+            // not a value, only reached through the arguments of `T[...]`
+            NodeKind::Type(_) => return self.hint(id),
+        };
+        self.typing.types.insert(id, ty.clone());
+        ty
+    }
+
+    fn expression(&mut self, expr: &Expression) -> Type {
+        let file = self.file;
+        // operands with the spans they cover
+        let mut stack = Vec::<(Type, Span)>::new();
+        for id in expr.rpn.iter().copied() {
+            let NodeKind::Operation(op) = &file.ast[id].kind else {
+                stack.push((self.infer(id), self.span(id)));
+                continue;
+            };
+            let op_span = self.span(id);
+            let rhs = match op.is_prefix() || op.is_postfix() {
+                true => None,
+                false => stack.pop(),
+            };
+            // broken by a parsing error
+            let Some(lhs) = stack.pop() else {
+                return self.fresh();
+            };
+            let span = match &rhs {
+                Some((_, rhs_span)) => lhs.1.join(*rhs_span),
+                None => lhs.1.join(op_span),
+            };
+            let ty = match rhs {
+                Some(rhs) => self.binary(op, lhs, rhs, op_span),
+                None if op.is_prefix() => self.prefix(op, lhs, op_span),
+                None => self.postfix(op, lhs, span),
+            };
+            self.typing.types.insert(id, ty.clone());
+            stack.push((ty, span));
+        }
+        match stack.as_slice() {
+            [(ty, _)] => ty.clone(),
+            _ => self.fresh(),
+        }
+    }
+
+    fn prefix(&mut self, op: &Operation, (ty, span): (Type, Span), op_span: Span) -> Type {
+        let op_span = op_span.join(span);
+        match op {
+            Operation::Neg => {
+                self.constrain(&ty, Class::Number, op.text(), op_span);
+                ty
+            }
+            Operation::Not => {
+                self.constrain(&ty, Class::Bitwise, op.text(), op_span);
+                ty
+            }
+            Operation::Ref => Type::Ref(Box::new(ty)),
+            _ => self.fresh(),
+        }
+    }
+
+    fn postfix(&mut self, op: &Operation, callee: (Type, Span), span: Span) -> Type {
+        match op {
+            Operation::FuncCall { args } => {
+                let args = args
+                    .iter()
+                    .map(|arg| (self.infer(*arg), self.span(*arg)))
+                    .collect();
+                self.call(callee, args, span)
+            }
+            // f(_, b) takes the arguments in place of `_`
+            Operation::PartialCall { args: ids } => {
+                let args = ids
+                    .iter()
+                    .map(|arg| (self.infer(*arg), self.span(*arg)))
+                    .collect::<Vec<_>>();
+                let params = args
+                    .iter()
+                    .zip(ids)
+                    .filter(|(_, id)| self.file.ast.is_dont_care(**id))
+                    .map(|((ty, _), _)| ty.clone())
+                    .collect();
+                let ret = self.call(callee, args, span);
+                Type::Function {
+                    params,
+                    ret: Box::new(ret),
+                }
+            }
+            Operation::ValueCtor { args } => {
+                for arg in args {
+                    self.infer(*arg);
+                }
+                self.fresh()
+            }
+            // `?` and `T[...]` have no types yet
+            _ => self.fresh(),
+        }
+    }
+
+    fn call(
+        &mut self,
+        (callee, callee_span): (Type, Span),
+        args: Vec<(Type, Span)>,
+        span: Span,
+    ) -> Type {
+        match self.shallow(&callee) {
+            Type::Function { params, ret } if params.len() != args.len() => {
+                let err = TypeError::ArgumentCount {
+                    expected: params.len(),
+                    found: args.len(),
+                };
+                self.error(err, span);
+                *ret
+            }
+            Type::Function { params, ret } => {
+                for (param, (arg, arg_span)) in params.iter().zip(&args) {
+                    self.expect(param, arg, *arg_span, None);
+                }
+                *ret
+            }
+            // `1(2)` is not a call, whatever the type of `1` is
+            Type::Var(var) if !self.int_vars.contains(&var) => {
+                let ret = self.fresh();
+                let function = Type::Function {
+                    params: args.into_iter().map(|(ty, _)| ty).collect(),
+                    ret: Box::new(ret.clone()),
+                };
+                self.expect(&callee, &function, callee_span, None);
+                ret
+            }
+            ty => {
+                let err = TypeError::NotCallable(self.report(&ty));
+                self.error(err, callee_span);
+                self.fresh()
+            }
+        }
+    }
+
+    fn binary(
+        &mut self,
+        op: &Operation,
+        (lhs, lhs_span): (Type, Span),
+        (rhs, rhs_span): (Type, Span),
+        op_span: Span,
+    ) -> Type {
+        let span = lhs_span.join(rhs_span);
+        // both sides of the same type from the class
+        let same = |this: &mut Self, class: Class| {
+            this.expect(&lhs, &rhs, rhs_span, Some(lhs_span));
+            this.constrain(&lhs, class, op.text(), span);
+            lhs.clone()
+        };
+        match op {
+            Operation::Add => same(self, Class::Addable),
+            Operation::Sub | Operation::Mul | Operation::Div | Operation::Mod | Operation::Pow => {
+                same(self, Class::Number)
+            }
+            Operation::And | Operation::Or | Operation::Xor => same(self, Class::Bitwise),
+            Operation::Gt | Operation::Ge | Operation::Lt | Operation::Le => {
+                same(self, Class::Ordered);
+                Type::Bool
+            }
+            Operation::Eq | Operation::Ne => {
+                self.expect(&lhs, &rhs, rhs_span, Some(lhs_span));
+                Type::Bool
+            }
+            Operation::Shl | Operation::Shr => {
+                self.constrain(&lhs, Class::Integer, op.text(), lhs_span);
+                self.constrain(&rhs, Class::Integer, op.text(), rhs_span);
+                lhs
+            }
+            Operation::LogAnd | Operation::LogOr => {
+                self.expect(&Type::Bool, &lhs, lhs_span, None);
+                self.expect(&Type::Bool, &rhs, rhs_span, None);
+                Type::Bool
+            }
+            Operation::Range | Operation::RangeInc => {
+                let element = same(self, Class::Integer);
+                Type::Named {
+                    name: "Range".into(),
+                    args: vec![element],
+                }
+            }
+            // x |> f is f(x)
+            Operation::Pipe => self.call((rhs, rhs_span), vec![(lhs, lhs_span)], op_span),
+            // members, paths and `=>` have no types yet
+            _ => self.fresh(),
+        }
+    }
+
+    fn declaration(&mut self, decl: &ValueDeclaration) {
+        let hint = decl
+            .type_hint
+            .map(|hint| (self.hint(hint), self.span(hint)));
+        let value_span = self.span(decl.assigned_value);
+
+        // the name is visible in its own lambda, see `resolve`
+        let recursive = self.file.ast.is_lambda(decl.assigned_value);
+        let ty = match &hint {
+            Some((ty, _)) => ty.clone(),
+            None => self.fresh(),
+        };
+        if recursive {
+            self.env.insert(decl.declaration, Scheme::mono(ty.clone()));
+        }
+        let value = self.infer(decl.assigned_value);
+        let hint_span = hint.map(|(_, span)| span);
+        self.expect(&ty, &value, value_span, hint_span);
+
+        // only values are generalized, a `var` can be assigned something else
+        self.env.remove(&decl.declaration);
+        let scheme = if recursive && !decl.mutable {
+            self.generalize(&ty)
+        } else {
+            Scheme::mono(ty.clone())
+        };
+        self.env.insert(decl.declaration, scheme);
+        self.typing.types.insert(decl.declaration, ty);
+    }
+
+    fn hint(&mut self, id: NodeId) -> Type {
+        let file = self.file;
+        let ty = match &file.ast[id].kind {
+            NodeKind::Type(TypeExpr::Named { name, args }) => {
+                let name = match &file.ast[*name].kind {
+                    NodeKind::Identifier(ident) => file.names.resolve(ident.name),
+                    _ => "",
+                };
+                match Type::builtin(name) {
+                    Some(ty) if args.is_empty() => ty,
+                    _ => Type::Named {
+                        name: name.into(),
+                        args: args.iter().map(|arg| self.hint(*arg)).collect(),
+                    },
+                }
+            }
+            NodeKind::Type(TypeExpr::Function { params, ret }) => Type::Function {
+                params: params.iter().map(|param| self.hint(*param)).collect(),
+                ret: Box::new(self.hint(*ret)),
+            },
+            NodeKind::Type(TypeExpr::Tuple { items }) if items.is_empty() => Type::Unit,
+            NodeKind::Type(TypeExpr::Tuple { items }) => {
+                Type::Tuple(items.iter().map(|item| self.hint(*item)).collect())
+            }
+            NodeKind::Type(TypeExpr::Optional { inner }) => {
+                Type::Optional(Box::new(self.hint(*inner)))
+            }
+            NodeKind::Type(TypeExpr::Ref { inner }) => Type::Ref(Box::new(self.hint(*inner))),
+            // let a: _ = 1, or a type broken by a parsing error
+            _ => self.fresh(),
+        };
+        self.typing.types.insert(id, ty.clone());
+        ty
+    }
+
+    fn block(&mut self, block: &Block) -> Type {
+        let mut last = Type::Unit;
+        for item in &block.items {
+            last = self.infer(*item);
+        }
+        for item in &block.items {
+            if let NodeKind::ValueDeclaration(decl) = &self.file.ast[*item].kind {
+                self.env.remove(&decl.declaration);
+            }
+        }
+        if block.returns_last { last } else { Type::Unit }
+    }
+
+    fn assignment(&mut self, id: NodeId, assignment: &Assignment) {
+        let target = (self.infer(assignment.target), self.span(assignment.target));
+        let value = (self.infer(assignment.value), self.span(assignment.value));
+        match &assignment.operation {
+            None => self.expect(&target.0, &value.0, value.1, Some(target.1)),
+            // a += b is a = a + b
+            Some(op) => {
+                let result = self.binary(op, target.clone(), value, self.span(id));
+                self.expect(&target.0, &result, self.span(id), Some(target.1));
+            }
+        }
+    }
+
+    fn lambda(&mut self, lambda: &Lambda) -> Type {
+        let mut params = Vec::with_capacity(lambda.args.len());
+        for arg in &lambda.args {
+            let hint = arg.type_hint.map(|hint| (self.hint(hint), self.span(hint)));
+            let ty = match &hint {
+                Some((ty, _)) => ty.clone(),
+                None => self.fresh(),
+            };
+            if let Some(default) = arg.default_value {
+                let value = self.infer(default);
+                self.expect(&ty, &value, self.span(default), hint.map(|(_, span)| span));
+            }
+            self.env.insert(arg.name, Scheme::mono(ty.clone()));
+            self.typing.types.insert(arg.name, ty.clone());
+            params.push(ty);
+        }
+        let ret = self.infer(lambda.body);
+        // out of scope, their variables can be generalized now
+        for arg in &lambda.args {
+            self.env.remove(&arg.name);
+        }
+        Type::Function {
+            params,
+            ret: Box::new(ret),
+        }
+    }
+}
+
+fn substitute(ty: &Type, vars: &HashMap<TypeVar, Type>) -> Type {
+    match ty {
+        Type::Var(var) => vars.get(var).cloned().unwrap_or(Type::Var(*var)),
+        Type::Function { params, ret } => Type::Function {
+            params: params.iter().map(|param| substitute(param, vars)).collect(),
+            ret: Box::new(substitute(ret, vars)),
+        },
+        Type::Named { name, args } => Type::Named {
+            name: name.clone(),
+            args: args.iter().map(|arg| substitute(arg, vars)).collect(),
+        },
+        Type::Tuple(items) => {
+            Type::Tuple(items.iter().map(|item| substitute(item, vars)).collect())
+        }
+        Type::Optional(inner) => Type::Optional(Box::new(substitute(inner, vars))),
+        Type::Ref(inner) => Type::Ref(Box::new(substitute(inner, vars))),
+        ty => ty.clone(),
+    }
+}
@@ -0,0 +1,185 @@
+//! Types of values, as inferred by [`typeck`](crate::semantic::typeck)
+
+use std::fmt;
+
+/// `i8`..`i128` and `u8`..`u128`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IntType {
+    pub signed: bool,
+    pub bits: u32,
+}
+
+impl IntType {
+    /// range of `int`, which is a type of its own
+    pub const INT: IntType = IntType {
+        signed: true,
+        bits: 64,
+    };
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (signed, bits) = match name.split_at_checked(1)? {
+            ("i", bits) => (true, bits),
+            ("u", bits) => (false, bits),
+            _ => return None,
+        };
+        match bits {
+            "8" | "16" | "32" | "64" | "128" => Some(IntType {
+                signed,
+                bits: bits.parse().ok()?,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn min(self) -> i128 {
+        match self.signed {
+            true => i128::MIN >> (128 - self.bits),
+            false => 0,
+        }
+    }
+
+    pub fn max(self) -> u128 {
+        u128::MAX >> (128 - self.bits + u32::from(self.signed))
+    }
+}
+
+impl fmt::Display for IntType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.signed { 'i' } else { 'u' };
+        write!(f, "{sign}{}", self.bits)
+    }
+}
+
+/// Unknown type, solved by unification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TypeVar(pub u32);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Int,
+    /// i8, u128
+    Sized(IntType),
+    Float,
+    Bool,
+    String,
+    /// value of statements and blocks without a result
+    Unit,
+    /// (A, B) -> C
+    Function {
+        params: Vec<Type>,
+        ret: Box<Type>,
+    },
+    /// (A, B), never empty, `()` is [`Type::Unit`]
+    Tuple(Vec<Type>),
+    /// T?
+    Optional(Box<Type>),
+    /// ref T
+    Ref(Box<Type>),
+    /// user type like `Point` or `List[int]`, equal only to the same name and arguments
+    Named {
+        name: Box<str>,
+        args: Vec<Type>,
+    },
+    Var(TypeVar),
+}
+
+impl Type {
+    /// Type written in a hint as a single name
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "int" => Some(Type::Int),
+            "float" => Some(Type::Float),
+            "bool" => Some(Type::Bool),
+            "string" => Some(Type::String),
+            _ => IntType::from_name(name).map(Type::Sized),
+        }
+    }
+
+    /// Range of `int` and the sized integers
+    pub fn int_type(&self) -> Option<IntType> {
+        match self {
+            Type::Int => Some(IntType::INT),
+            Type::Sized(ty) => Some(*ty),
+            _ => None,
+        }
+    }
+
+    /// Calls `f` for every variable, in the order of appearance
+    pub fn visit_vars(&self, f: &mut impl FnMut(TypeVar)) {
+        match self {
+            Type::Function { params, ret } => {
+                params.iter().for_each(|param| param.visit_vars(f));
+                ret.visit_vars(f);
+            }
+            Type::Named { args, .. } | Type::Tuple(args) => {
+                args.iter().for_each(|arg| arg.visit_vars(f))
+            }
+            Type::Optional(inner) | Type::Ref(inner) => inner.visit_vars(f),
+            Type::Var(var) => f(*var),
+            _ => (),
+        }
+    }
+}
+
+/// Unknown parts are shown as `_`
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |f: &mut fmt::Formatter<'_>, types: &[Type]| {
+            for (i, ty) in types.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{ty}")?;
+            }
+            Ok(())
+        };
+        match self {
+            Type::Int => write!(f, "int"),
+            Type::Sized(ty) => write!(f, "{ty}"),
+            Type::Float => write!(f, "float"),
+            Type::Bool => write!(f, "bool"),
+            Type::String => write!(f, "string"),
+            Type::Unit => write!(f, "()"),
+            Type::Function { params, ret } => {
+                write!(f, "(")?;
+                list(f, params)?;
+                write!(f, ") -> {ret}")
+            }
+            Type::Tuple(items) if items.len() == 1 => write!(f, "({},)", items[0]),
+            Type::Tuple(items) => {
+                write!(f, "(")?;
+                list(f, items)?;
+                write!(f, ")")
+            }
+            Type::Optional(inner) if matches!(**inner, Type::Function { .. } | Type::Ref(_)) => {
+                write!(f, "({inner})?")
+            }
+            Type::Optional(inner) => write!(f, "{inner}?"),
+            Type::Ref(inner) => write!(f, "ref {inner}"),
+            Type::Named { name, args } if args.is_empty() => write!(f, "{name}"),
+            Type::Named { name, args } => {
+                write!(f, "{name}[")?;
+                list(f, args)?;
+                write!(f, "]")
+            }
+            Type::Var(_) => write!(f, "_"),
+        }
+    }
+}
+
+/// Type of a `let` binding, `vars` are replaced with fresh ones at every use
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scheme {
+    pub vars: Vec<TypeVar>,
+    pub ty: Type,
+}
+
+impl Scheme {
+    /// Same type at every use, like the type of a lambda argument
+    pub fn mono(ty: Type) -> Self {
+        Self {
+            vars: Vec::new(),
+            ty,
+        }
+    }
+}
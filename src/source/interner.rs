@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+/// Handle of an interned string. Symbols from the same [`Interner`] are equal
+/// only if their strings are equal, so comparing them is a single integer comparison
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+pub struct Symbol(u32);
+
+impl Symbol {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Deduplicated identifiers. Filled by the tokenizer, used to get names back in later passes
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "Vec<Box<str>>", into = "Vec<Box<str>>")
+)]
+pub struct Interner {
+    symbols: HashMap<Box<str>, Symbol>,
+    names: Vec<Box<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(name) {
+            return *symbol;
+        }
+        let symbol = Symbol(self.names.len() as u32);
+        self.names.push(name.into());
+        self.symbols.insert(name.into(), symbol);
+        symbol
+    }
+
+    /// Symbol of the name, if it was interned before
+    pub fn get(&self, name: &str) -> Option<Symbol> {
+        self.symbols.get(name).copied()
+    }
+
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.names[symbol.index()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+/// Names in the order of their symbols, a repeated name would shift the symbols after it
+impl TryFrom<Vec<Box<str>>> for Interner {
+    type Error = String;
+
+    fn try_from(names: Vec<Box<str>>) -> Result<Self, String> {
+        let mut interner = Self::new();
+        for name in &names {
+            if interner.get(name).is_some() {
+                return Err(format!("name `{name}` is repeated"));
+            }
+            interner.intern(name);
+        }
+        Ok(interner)
+    }
+}
+
+impl From<Interner> for Vec<Box<str>> {
+    fn from(interner: Interner) -> Self {
+        interner.names
+    }
+}
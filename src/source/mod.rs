@@ -0,0 +1,208 @@
+pub mod interner;
+
+use std::ops::Range;
+
+/// Index of a file registered in the [`SourceMap`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileId(pub u32);
+
+/// Byte range inside of a specific file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub file: FileId,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(file: FileId, range: Range<usize>) -> Self {
+        Self {
+            file,
+            start: range.start,
+            end: range.end,
+        }
+    }
+    pub fn as_range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+    /// Span covering both spans (and everything between them)
+    pub fn join(&self, other: Span) -> Span {
+        Span {
+            file: self.file,
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+    pub fn contains(&self, other: Span) -> bool {
+        self.file == other.file && self.start <= other.start && other.end <= self.end
+    }
+}
+
+/// Zero-based position of a byte offset.
+/// Columns are counted in UTF-8 bytes and UTF-16 code units (LSP uses the latter by default)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct LineCol {
+    pub line: usize,
+    pub col_utf8: usize,
+    pub col_utf16: usize,
+}
+
+impl std::fmt::Display for LineCol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line + 1, self.col_utf8 + 1)
+    }
+}
+
+pub struct SourceFile {
+    pub id: FileId,
+    pub name: String,
+    pub src: Vec<u8>,
+    /// offsets of the first byte of every line
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    pub fn new(id: FileId, name: String, src: Vec<u8>) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(src.iter().enumerate().filter(|(_, b)| **b == b'\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            id,
+            name,
+            src,
+            line_starts,
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Zero-based line index of the offset, offsets past the end are clamped to the last line
+    pub fn line_index(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        }
+    }
+
+    /// Byte range of the line, without the line break
+    pub fn line_range(&self, line: usize) -> Range<usize> {
+        let start = self.line_starts[line];
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .map_or(self.src.len(), |next| next - 1);
+        let end = if end > start && self.src[end - 1] == b'\r' {
+            end - 1
+        } else {
+            end
+        };
+        start..end
+    }
+
+    pub fn line_col(&self, offset: usize) -> LineCol {
+        let offset = offset.min(self.src.len());
+        let line = self.line_index(offset);
+        let start = self.line_starts[line];
+        let col_utf16 = self.src[start..offset].iter().map(|b| utf16_units(*b)).sum();
+
+        LineCol {
+            line,
+            col_utf8: offset - start,
+            col_utf16,
+        }
+    }
+
+    /// Reverse of [`SourceFile::line_col`] for editor positions (zero-based, UTF-16 column).
+    /// Positions past the end of line are clamped to the end of line
+    pub fn offset_utf16(&self, line: usize, col_utf16: usize) -> usize {
+        if line >= self.line_starts.len() {
+            return self.src.len();
+        }
+        let range = self.line_range(line);
+
+        // counted byte by byte like in `line_col`, so invalid UTF-8 does not shift the offsets
+        let mut units = 0;
+        for offset in range.clone() {
+            let byte = self.src[offset];
+            if is_continuation(byte) {
+                continue;
+            }
+            if units >= col_utf16 {
+                return offset;
+            }
+            units += utf16_units(byte);
+        }
+        range.end
+    }
+}
+
+fn is_continuation(byte: u8) -> bool {
+    matches!(byte, 0x80..=0xBF)
+}
+
+/// UTF-16 units of the char starting with that byte, every char starts with a non-continuation
+/// byte and 4-byte chars take two units
+fn utf16_units(byte: u8) -> usize {
+    match byte {
+        0x80..=0xBF => 0,
+        0xF0..=0xFF => 2,
+        _ => 1,
+    }
+}
+
+/// Registry of all source files of a compilation
+#[derive(Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_file(&mut self, name: impl Into<String>, src: Vec<u8>) -> FileId {
+        let id = FileId(self.files.len() as u32);
+        self.files.push(SourceFile::new(id, name.into(), src));
+        id
+    }
+
+    pub fn load_file(&mut self, path: &str) -> std::io::Result<FileId> {
+        let src = std::fs::read(path)?;
+        Ok(self.add_file(path, src))
+    }
+
+    pub fn get(&self, id: FileId) -> &SourceFile {
+        &self.files[id.0 as usize]
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &SourceFile> {
+        self.files.iter()
+    }
+
+    pub fn source(&self, span: Span) -> &[u8] {
+        &self.get(span.file).src[span.as_range()]
+    }
+
+    /// Positions of span start and end
+    pub fn lookup(&self, span: Span) -> (LineCol, LineCol) {
+        let file = self.get(span.file);
+        (file.line_col(span.start), file.line_col(span.end))
+    }
+
+    /// `file.esl:12:5`
+    pub fn describe(&self, span: Span) -> String {
+        let file = self.get(span.file);
+        format!("{}:{}", file.name, file.line_col(span.start))
+    }
+}
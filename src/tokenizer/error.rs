@@ -1,15 +1,34 @@
 use thiserror::Error;
 
 #[derive(Debug, Clone, PartialEq, Error)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TokenizeError {
     #[error("String literal should be closed (\"example\")")]
     UnterminatedString,
     #[error("Attribute name cannot be empty (@example)")]
     InvalidAttributeName,
     #[error("Invalid sequence ({0})")]
-    UnexpectedChar(String),
+    UnexpectedChar(Box<str>),
     #[error("Unexpected closing brace ({0})")]
-    UnexpectedClosingDelimiter(String),
+    UnexpectedClosingDelimiter(Box<str>),
     #[error("Unexpected end of file, brace pair was not closed")]
-    UnclosedDelimiter,
-}
\ No newline at end of file
+    UnclosedDelimiter { opened_at: usize },
+    #[error("Block comment should be closed (/* example */)")]
+    UnterminatedBlockComment,
+    #[error("Braces are nested too deep")]
+    NestingTooDeep,
+}
+
+impl TokenizeError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            TokenizeError::UnterminatedString => "E0001",
+            TokenizeError::InvalidAttributeName => "E0002",
+            TokenizeError::UnexpectedChar(_) => "E0003",
+            TokenizeError::UnexpectedClosingDelimiter(_) => "E0004",
+            TokenizeError::UnclosedDelimiter { .. } => "E0005",
+            TokenizeError::UnterminatedBlockComment => "E0006",
+            TokenizeError::NestingTooDeep => "E0007",
+        }
+    }
+}
@@ -1,39 +1,67 @@
 pub mod error;
 pub mod token;
 
+use crate::source::{FileId, Span, interner::Interner};
 use error::TokenizeError;
 use token::{Token, TokenKind};
 
+/// Braces nested deeper than that are reported as an error instead of overflowing the stack
+pub const MAX_NESTING: usize = 256;
+
 pub struct Tokenizer<'a> {
+    pub file: FileId,
     pub pos: usize,
     pub src: &'a [u8],
+    /// flat, braces are followed by their children and the matching closing brace
     pub tokens: Vec<Token>,
+    /// names of identifiers, see [`TokenKind::Identifier`]
+    pub interner: Interner,
+    /// closing braces of currently opened groups
+    open_braces: Vec<u8>,
 }
 
 /*************************************************
  *               PUBLIC INTERFACE                *
  *************************************************/
 impl<'a> Tokenizer<'a> {
-    pub fn new(src: &'a [u8]) -> Self {
+    pub fn new(file: FileId, src: &'a [u8]) -> Self {
+        Self::with_interner(file, src, Interner::new())
+    }
+    /// Continues with symbols of other files, so they can be compared with each other
+    pub fn with_interner(file: FileId, src: &'a [u8], interner: Interner) -> Self {
         Tokenizer {
+            file,
             src,
             pos: 0,
             tokens: Vec::new(),
+            interner,
+            open_braces: Vec::new(),
         }
     }
     pub fn tokenize(&mut self) {
         loop {
-            self.skip_ignored();
-            match self.next_token() {
-                None => break,
-                Some(t) => self.tokens.push(t),
+            if let Some(error) = self.skip_ignored() {
+                self.tokens.push(error);
             }
+            if self.next().is_none() {
+                break;
+            }
+            self.push_next_token();
+        }
+    }
+    /// Tokenizes only the braces group at `pos`, which is nested into groups closed by `outer`
+    /// (the outermost first). Used to re-tokenize a part of an edited file
+    pub fn tokenize_group(&mut self, outer: &[u8]) {
+        self.open_braces = outer.to_vec();
+        if self.next().is_some() {
+            self.t_braces();
         }
+        self.open_braces.clear();
     }
     pub fn print_tokens(&self) {
         for token in self.tokens.iter() {
-            let content = &self.src[token.range.clone()];
-            let con_str = std::str::from_utf8(content).unwrap();
+            let content = &self.src[token.range.as_range()];
+            let con_str = String::from_utf8_lossy(content);
             println!("{:#?} -> {}", token.kind, con_str);
         }
     }
@@ -51,7 +79,7 @@ impl<'a> Tokenizer<'a> {
             self.mov();
         }
     }
-    fn skip_and_get(&mut self, f: impl Fn(u8) -> bool) -> &[u8] {
+    fn skip_and_get(&mut self, f: impl Fn(u8) -> bool) -> &'a [u8] {
         let start = self.pos;
         self.skip(f);
         &self.src[start..self.pos]
@@ -74,7 +102,7 @@ impl<'a> Tokenizer<'a> {
         if let Some(tok) = tok {
             return Some(Token {
                 kind: tok,
-                range: pos..self.pos,
+                range: Span::new(self.file, pos..self.pos),
             });
         }
         None
@@ -127,6 +155,7 @@ impl<'a> Tokenizer<'a> {
 
         self.make_token(|this| {
             match this.skip_and_get(|b| b.is_ascii_alphanumeric() || b == b'_') {
+                b"_" => Some(TokenKind::Ignore),
                 b"as" => Some(TokenKind::OpAs),
                 b"if" => Some(TokenKind::KwIf),
                 b"or" => Some(TokenKind::KwOr),
@@ -149,7 +178,11 @@ impl<'a> Tokenizer<'a> {
                 b"module" => Some(TokenKind::KwModule),
                 b"struct" => Some(TokenKind::KwStruct),
 
-                _ => Some(TokenKind::Identifier),
+                word => {
+                    // words are ASCII
+                    let name = std::str::from_utf8(word).unwrap_or_default();
+                    Some(TokenKind::Identifier(this.interner.intern(name)))
+                }
             }
         })
     }
@@ -182,8 +215,19 @@ impl<'a> Tokenizer<'a> {
                     Some(TokenKind::OpSub)
                 }
             },
-            b'*' => match this.next_at(1) {
-                Some(b'=') => {
+            b'*' => match (this.next_at(1), this.next_at(2)) {
+                (Some(b'*'), Some(b'=')) => {
+                    this.mov();
+                    this.mov();
+                    this.mov();
+                    Some(TokenKind::OpPowAsg)
+                }
+                (Some(b'*'), _) => {
+                    this.mov();
+                    this.mov();
+                    Some(TokenKind::OpPow)
+                }
+                (Some(b'='), _) => {
                     this.mov();
                     this.mov();
                     Some(TokenKind::OpMulAsg)
@@ -215,8 +259,19 @@ impl<'a> Tokenizer<'a> {
                     Some(TokenKind::OpMod)
                 }
             },
-            b'>' => match this.next_at(1) {
-                Some(b'=') => {
+            b'>' => match (this.next_at(1), this.next_at(2)) {
+                (Some(b'>'), Some(b'=')) => {
+                    this.mov();
+                    this.mov();
+                    this.mov();
+                    Some(TokenKind::OpShrAsg)
+                }
+                (Some(b'>'), _) => {
+                    this.mov();
+                    this.mov();
+                    Some(TokenKind::OpShr)
+                }
+                (Some(b'='), _) => {
                     this.mov();
                     this.mov();
                     Some(TokenKind::OpGe)
@@ -226,8 +281,19 @@ impl<'a> Tokenizer<'a> {
                     Some(TokenKind::OpGt)
                 }
             },
-            b'<' => match this.next_at(1) {
-                Some(b'=') => {
+            b'<' => match (this.next_at(1), this.next_at(2)) {
+                (Some(b'<'), Some(b'=')) => {
+                    this.mov();
+                    this.mov();
+                    this.mov();
+                    Some(TokenKind::OpShlAsg)
+                }
+                (Some(b'<'), _) => {
+                    this.mov();
+                    this.mov();
+                    Some(TokenKind::OpShl)
+                }
+                (Some(b'='), _) => {
                     this.mov();
                     this.mov();
                     Some(TokenKind::OpLe)
@@ -254,6 +320,11 @@ impl<'a> Tokenizer<'a> {
                     this.mov();
                     Some(TokenKind::OpEq)
                 }
+                Some(b'>') => {
+                    this.mov();
+                    this.mov();
+                    Some(TokenKind::OpArrow)
+                }
                 _ => {
                     this.mov();
                     Some(TokenKind::OpAsg)
@@ -265,6 +336,16 @@ impl<'a> Tokenizer<'a> {
                     this.mov();
                     Some(TokenKind::OpOrAsg)
                 }
+                Some(b'|') => {
+                    this.mov();
+                    this.mov();
+                    Some(TokenKind::OpLogOr)
+                }
+                Some(b'>') => {
+                    this.mov();
+                    this.mov();
+                    Some(TokenKind::OpPipe)
+                }
                 _ => {
                     this.mov();
                     Some(TokenKind::OpOr)
@@ -276,19 +357,55 @@ impl<'a> Tokenizer<'a> {
                     this.mov();
                     Some(TokenKind::OpAndAsg)
                 }
+                Some(b'&') => {
+                    this.mov();
+                    this.mov();
+                    Some(TokenKind::OpLogAnd)
+                }
                 _ => {
                     this.mov();
                     Some(TokenKind::OpAnd)
                 }
             },
-            b'.' => {
-                this.mov();
-                Some(TokenKind::OpDot)
-            }
-            b':' => {
-                this.mov();
-                Some(TokenKind::OpTypedef)
-            }
+            b'^' => match this.next_at(1) {
+                Some(b'=') => {
+                    this.mov();
+                    this.mov();
+                    Some(TokenKind::OpXorAsg)
+                }
+                _ => {
+                    this.mov();
+                    Some(TokenKind::OpXor)
+                }
+            },
+            b'.' => match (this.next_at(1), this.next_at(2)) {
+                (Some(b'.'), Some(b'=')) => {
+                    this.mov();
+                    this.mov();
+                    this.mov();
+                    Some(TokenKind::OpRangeInc)
+                }
+                (Some(b'.'), _) => {
+                    this.mov();
+                    this.mov();
+                    Some(TokenKind::OpRange)
+                }
+                _ => {
+                    this.mov();
+                    Some(TokenKind::OpDot)
+                }
+            },
+            b':' => match this.next_at(1) {
+                Some(b':') => {
+                    this.mov();
+                    this.mov();
+                    Some(TokenKind::OpPath)
+                }
+                _ => {
+                    this.mov();
+                    Some(TokenKind::OpTypedef)
+                }
+            },
             b'?' => {
                 this.mov();
                 Some(TokenKind::OpTry)
@@ -297,10 +414,6 @@ impl<'a> Tokenizer<'a> {
                 this.mov();
                 Some(TokenKind::OpComma)
             }
-            b'_' => {
-                this.mov();
-                Some(TokenKind::Ignore)
-            }
             b';' => {
                 this.mov();
                 Some(TokenKind::Semicolon)
@@ -351,69 +464,110 @@ impl<'a> Tokenizer<'a> {
         })
     }
     fn t_doc(&mut self) -> Option<Token> {
-        if self.next_unwrap() == b'/'
-            && self.next_at(1).is_some_and(|b| b == b'/')
-            && self.next_at(2).is_some_and(|b| b == b'/')
-        {
-            self.make_token(|this| {
-                this.mov();
-                this.mov();
-                this.mov();
-                this.skip(|b| b != b'\n');
-                Some(TokenKind::DocComment)
-            })
-        } else {
-            None
+        if self.next_unwrap() != b'/' || self.next_at(1).is_none_or(|b| b != b'/') {
+            return None;
         }
+        let kind = match self.next_at(2) {
+            Some(b'/') => TokenKind::DocComment,
+            Some(b'!') => TokenKind::InnerDocComment,
+            _ => return None,
+        };
+
+        self.make_token(|this| {
+            this.mov();
+            this.mov();
+            this.mov();
+            this.skip(|b| b != b'\n');
+            Some(kind)
+        })
     }
-    fn t_braces(&mut self) -> Option<Token> {
+    // Pushes the whole group: the opening brace, the children and the closing brace
+    fn t_braces(&mut self) {
         let closing = match self.next() {
             Some(b'{') => b'}',
             Some(b'(') => b')',
             Some(b'[') => b']',
-            _ => return None,
+            // braces inside of a group are handled by the group itself
+            Some(b'}') | Some(b']') | Some(b')') => {
+                let token = self.make_token(|this| {
+                    let brace = this.next_unwrap();
+                    this.mov();
+                    Some(TokenKind::Error(TokenizeError::UnexpectedClosingDelimiter(
+                        String::from_utf8_lossy(&[brace]).into(),
+                    )))
+                });
+                self.tokens.extend(token);
+                return;
+            }
+            _ => return,
         };
-        self.make_token(|this| {
-            this.mov();
+        if self.open_braces.len() >= MAX_NESTING {
+            let token = self.make_token(|this| {
+                this.pos = this.src.len();
+                Some(TokenKind::Error(TokenizeError::NestingTooDeep))
+            });
+            self.tokens.extend(token);
+            return;
+        }
 
-            let mut children = Vec::new();
-            loop {
-                this.skip_ignored();
-                match this.next() {
-                    Some(b'}') | Some(b']') | Some(b')') => {
-                        if this.next_unwrap() == closing {
-                            this.mov();
-                        } else {
-                            this.tokens.push(Token {
-                                range: this.pos..this.pos + 1,
-                                kind: TokenKind::Error(TokenizeError::UnexpectedClosingDelimiter(
-                                    String::from_utf8_lossy(&[this.next_unwrap()]).to_string(),
-                                )),
-                            });
-                        }
-                        break;
+        let open = self.tokens.len();
+        let opened_at = self.pos;
+        self.mov();
+        // the index of the closing brace is set once it is found
+        self.tokens.push(Token {
+            range: Span::new(self.file, opened_at..self.pos),
+            kind: TokenKind::ClosingBrace { open },
+        });
+
+        self.open_braces.push(closing);
+        let closed_at = loop {
+            if let Some(error) = self.skip_ignored() {
+                self.tokens.push(error);
+            }
+            match self.next() {
+                Some(b) if b == closing => {
+                    self.mov();
+                    break self.pos - 1..self.pos;
+                }
+                Some(b @ (b'}' | b']' | b')')) => {
+                    // closes one of the outer groups, so this one stays unclosed
+                    if self.open_braces.contains(&b) {
+                        self.tokens.push(Token {
+                            range: Span::new(self.file, self.pos..self.pos),
+                            kind: TokenKind::Error(TokenizeError::UnclosedDelimiter { opened_at }),
+                        });
+                        break self.pos..self.pos;
                     }
-                    _ => match this.next_token() {
-                        Some(t) => children.push(t),
-                        None => {
-                            this.tokens.push(Token {
-                                range: this.pos..this.pos,
-                                kind: TokenKind::Error(TokenizeError::UnclosedDelimiter),
-                            });
-                            break;
-                        }
-                    },
+                    self.tokens.push(Token {
+                        range: Span::new(self.file, self.pos..self.pos + 1),
+                        kind: TokenKind::Error(TokenizeError::UnexpectedClosingDelimiter(
+                            String::from_utf8_lossy(&[b]).into(),
+                        )),
+                    });
+                    self.mov();
+                }
+                Some(_) => self.push_next_token(),
+                None => {
+                    self.tokens.push(Token {
+                        range: Span::new(self.file, self.pos..self.pos),
+                        kind: TokenKind::Error(TokenizeError::UnclosedDelimiter { opened_at }),
+                    });
+                    break self.pos..self.pos;
                 }
             }
+        };
+        self.open_braces.pop();
 
-            match closing {
-                b'}' => Some(TokenKind::CurlyBraces { children }),
-                b')' => Some(TokenKind::RoundBraces { children }),
-                b']' => Some(TokenKind::SquareBraces { children }),
-
-                _ => unreachable!(),
-            }
-        })
+        let close = self.tokens.len();
+        self.tokens.push(Token {
+            range: Span::new(self.file, closed_at),
+            kind: TokenKind::ClosingBrace { open },
+        });
+        self.tokens[open].kind = match closing {
+            b'}' => TokenKind::CurlyBraces { close },
+            b')' => TokenKind::RoundBraces { close },
+            _ => TokenKind::SquareBraces { close },
+        };
     }
 
     fn skip_error(&mut self) -> Option<Token> {
@@ -428,11 +582,33 @@ impl<'a> Tokenizer<'a> {
             });
 
             Some(TokenKind::Error(TokenizeError::UnexpectedChar(
-                String::from_utf8_lossy(invalid).to_string(),
+                String::from_utf8_lossy(invalid).into(),
             )))
         })
     }
-    fn skip_ignored(&mut self) {
+    // Returns false if end of file was reached before the comment was closed
+    fn skip_block_comment(&mut self) -> bool {
+        let mut depth = 0usize;
+        while let Some(b) = self.next() {
+            if b == b'/' && self.next_at(1).is_some_and(|b| b == b'*') {
+                self.mov();
+                self.mov();
+                depth += 1;
+            } else if b == b'*' && self.next_at(1).is_some_and(|b| b == b'/') {
+                self.mov();
+                self.mov();
+                depth -= 1;
+                if depth == 0 {
+                    return true;
+                }
+            } else {
+                self.mov();
+            }
+        }
+        false
+    }
+    // Returns error if there is an unterminated block comment
+    fn skip_ignored(&mut self) -> Option<Token> {
         while let Some(b) = self.next() {
             if b.is_ascii_whitespace() {
                 self.mov();
@@ -441,27 +617,43 @@ impl<'a> Tokenizer<'a> {
             // Ensure that we don't skip a doc comment
             if b == b'/'
                 && self.next_at(1).is_some_and(|b| b == b'/')
-                && self.next_at(2).is_none_or(|b| b != b'/')
+                && self.next_at(2).is_none_or(|b| b != b'/' && b != b'!')
             {
                 self.mov();
                 self.mov();
                 self.skip(|b| b != b'\n');
                 continue;
             }
+            if b == b'/' && self.next_at(1).is_some_and(|b| b == b'*') {
+                let start = self.pos;
+                if !self.skip_block_comment() {
+                    return Some(Token {
+                        range: Span::new(self.file, start..self.pos),
+                        kind: TokenKind::Error(TokenizeError::UnterminatedBlockComment),
+                    });
+                }
+                continue;
+            }
             break;
         }
+        None
     }
-    fn next_token(&mut self) -> Option<Token> {
-        if self.next().is_none() {
-            return None;
-        }
-        self.t_word()
-            .or_else(|| self.t_number())
-            .or_else(|| self.t_doc()) // WARN: doc should go before op, to not match /// as three divisions
-            .or_else(|| self.t_symbol())
-            .or_else(|| self.t_braces())
-            .or_else(|| self.t_string())
-            .or_else(|| self.t_attribute())
-            .or_else(|| self.skip_error())
+    // Braces push the whole group, everything else is a single token
+    fn push_next_token(&mut self) {
+        // the first byte decides which matcher to try, the rest are fallbacks
+        let token = match self.next_unwrap() {
+            b'{' | b'(' | b'[' | b'}' | b')' | b']' => {
+                self.t_braces();
+                return;
+            }
+            b'a'..=b'z' | b'A'..=b'Z' | b'_' => self.t_word(),
+            b'0'..=b'9' => self.t_number(),
+            b'"' => self.t_string(),
+            b'@' => self.t_attribute(),
+            // WARN: doc should go before op, to not match /// as three divisions
+            _ => self.t_doc().or_else(|| self.t_symbol()),
+        };
+        let token = token.or_else(|| self.skip_error());
+        self.tokens.extend(token);
     }
 }
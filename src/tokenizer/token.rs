@@ -1,14 +1,21 @@
-use crate::tokenizer::error::TokenizeError;
+use crate::{
+    source::{Span, interner::Symbol},
+    tokenizer::error::TokenizeError,
+};
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Token {
-    pub range: std::ops::Range<usize>,
+    pub range: Span,
     pub kind: TokenKind,
 }
 
 impl Token {
     pub fn new(kind: TokenKind) -> Self {
-        Self { range: 0..0, kind }
+        Self {
+            range: Span::default(),
+            kind,
+        }
     }
     pub fn is(&self, kind: TokenKind) -> bool {
         self.kind == kind
@@ -16,6 +23,7 @@ impl Token {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TokenKind {
     // Numeric literals
     NumBinInt,   // 0b0101
@@ -24,35 +32,50 @@ pub enum TokenKind {
     NumDecFloat, // 1234.5
 
     // Operators
-    OpAdd,     // +
-    OpSub,     // -
-    OpMul,     // *
-    OpDiv,     // /
-    OpMod,     // %
-    OpGt,      // >
-    OpGe,      // >=
-    OpLt,      // <
-    OpLe,      // <=
-    OpNe,      // !=
-    OpEq,      // ==
-    OpOr,      // |
-    OpAnd,     // &
-    OpNot,     // !
-    OpDot,     // .
-    OpComma,   // ,
-    OpTry,     // ?
-    OpLam,     // ->
-    OpAsg,     // =
-    OpAddAsg,  // +=
-    OpSubAsg,  // -=
-    OpMulAsg,  // *=
-    OpDivAsg,  // /=
-    OpModAsg,  // %=
-    OpAndAsg,  // &=
-    OpOrAsg,   // |=
-    OpAs,      // as
-    OpRef,     // ref
-    OpTypedef, // :
+    OpAdd,      // +
+    OpSub,      // -
+    OpMul,      // *
+    OpDiv,      // /
+    OpMod,      // %
+    OpPow,      // **
+    OpGt,       // >
+    OpGe,       // >=
+    OpLt,       // <
+    OpLe,       // <=
+    OpNe,       // !=
+    OpEq,       // ==
+    OpOr,       // |
+    OpAnd,      // &
+    OpXor,      // ^
+    OpShl,      // <<
+    OpShr,      // >>
+    OpLogOr,    // ||
+    OpLogAnd,   // &&
+    OpNot,      // !
+    OpDot,      // .
+    OpRange,    // ..
+    OpRangeInc, // ..=
+    OpPath,     // ::
+    OpPipe,     // |>
+    OpArrow,    // =>
+    OpComma,    // ,
+    OpTry,      // ?
+    OpLam,      // ->
+    OpAsg,      // =
+    OpAddAsg,   // +=
+    OpSubAsg,   // -=
+    OpMulAsg,   // *=
+    OpDivAsg,   // /=
+    OpModAsg,   // %=
+    OpPowAsg,   // **=
+    OpAndAsg,   // &=
+    OpOrAsg,    // |=
+    OpXorAsg,   // ^=
+    OpShlAsg,   // <<=
+    OpShrAsg,   // >>=
+    OpAs,       // as
+    OpRef,      // ref
+    OpTypedef,  // :
 
     // Keywords
     KwIf,
@@ -76,18 +99,161 @@ pub enum TokenKind {
     KwFalse,
 
     // Other tokens
-    Ignore,     // _
-    Semicolon,  // ;
-    Attribute,  // @attr
-    Identifier, // some_ident
-    String,     // "string"
-    DocComment, // /// comment
+    Ignore,          // _
+    Semicolon,       // ;
+    Attribute,       // @attr
+    Identifier(Symbol), // some_ident
+    String,          // "string"
+    DocComment,      // /// comment
+    InnerDocComment, // //! comment
 
-    // Structure
-    RoundBraces  { children: Vec<Token> },
-    SquareBraces { children: Vec<Token> },
-    CurlyBraces  { children: Vec<Token> },
+    // Structure, children follow the opening brace up to the matching closing one
+    RoundBraces  { close: usize },
+    SquareBraces { close: usize },
+    CurlyBraces  { close: usize },
+    ClosingBrace { open: usize }, // empty when the group was not closed
 
     // Errors
     Error(TokenizeError),
 }
+
+/// Keywords in the order they are suggested for misspelled words, declarations go first
+pub const KEYWORDS: &[TokenKind] = &[
+    TokenKind::KwLet,
+    TokenKind::KwVar,
+    TokenKind::KwFun,
+    TokenKind::KwType,
+    TokenKind::KwStruct,
+    TokenKind::KwEnum,
+    TokenKind::KwTrait,
+    TokenKind::KwUse,
+    TokenKind::KwModule,
+    TokenKind::KwPub,
+    TokenKind::KwIf,
+    TokenKind::KwOr,
+    TokenKind::KwMatch,
+    TokenKind::KwFor,
+    TokenKind::KwIn,
+    TokenKind::KwWhile,
+    TokenKind::KwLoop,
+    TokenKind::KwTrue,
+    TokenKind::KwFalse,
+];
+
+impl TokenKind {
+    /// Source text of operators and keywords
+    pub fn text(&self) -> Option<&'static str> {
+        let text = match self {
+            TokenKind::OpAdd => "+",
+            TokenKind::OpSub => "-",
+            TokenKind::OpMul => "*",
+            TokenKind::OpDiv => "/",
+            TokenKind::OpMod => "%",
+            TokenKind::OpPow => "**",
+            TokenKind::OpGt => ">",
+            TokenKind::OpGe => ">=",
+            TokenKind::OpLt => "<",
+            TokenKind::OpLe => "<=",
+            TokenKind::OpNe => "!=",
+            TokenKind::OpEq => "==",
+            TokenKind::OpOr => "|",
+            TokenKind::OpAnd => "&",
+            TokenKind::OpXor => "^",
+            TokenKind::OpShl => "<<",
+            TokenKind::OpShr => ">>",
+            TokenKind::OpLogOr => "||",
+            TokenKind::OpLogAnd => "&&",
+            TokenKind::OpNot => "!",
+            TokenKind::OpDot => ".",
+            TokenKind::OpRange => "..",
+            TokenKind::OpRangeInc => "..=",
+            TokenKind::OpPath => "::",
+            TokenKind::OpPipe => "|>",
+            TokenKind::OpArrow => "=>",
+            TokenKind::OpComma => ",",
+            TokenKind::OpTry => "?",
+            TokenKind::OpLam => "->",
+            TokenKind::OpAsg => "=",
+            TokenKind::OpAddAsg => "+=",
+            TokenKind::OpSubAsg => "-=",
+            TokenKind::OpMulAsg => "*=",
+            TokenKind::OpDivAsg => "/=",
+            TokenKind::OpModAsg => "%=",
+            TokenKind::OpPowAsg => "**=",
+            TokenKind::OpAndAsg => "&=",
+            TokenKind::OpOrAsg => "|=",
+            TokenKind::OpXorAsg => "^=",
+            TokenKind::OpShlAsg => "<<=",
+            TokenKind::OpShrAsg => ">>=",
+            TokenKind::OpAs => "as",
+            TokenKind::OpRef => "ref",
+            TokenKind::OpTypedef => ":",
+            TokenKind::KwIf => "if",
+            TokenKind::KwOr => "or",
+            TokenKind::KwMatch => "match",
+            TokenKind::KwFor => "for",
+            TokenKind::KwIn => "in",
+            TokenKind::KwWhile => "while",
+            TokenKind::KwLoop => "loop",
+            TokenKind::KwLet => "let",
+            TokenKind::KwVar => "var",
+            TokenKind::KwFun => "fun",
+            TokenKind::KwType => "type",
+            TokenKind::KwStruct => "struct",
+            TokenKind::KwEnum => "enum",
+            TokenKind::KwTrait => "trait",
+            TokenKind::KwUse => "use",
+            TokenKind::KwModule => "module",
+            TokenKind::KwPub => "pub",
+            TokenKind::KwTrue => "true",
+            TokenKind::KwFalse => "false",
+            TokenKind::Ignore => "_",
+            TokenKind::Semicolon => ";",
+            _ => return None,
+        };
+        Some(text)
+    }
+
+    /// Name for error messages, like `=` or identifier
+    pub fn describe(&self) -> String {
+        if let Some(text) = self.text() {
+            return format!("`{text}`");
+        }
+        match self {
+            TokenKind::NumBinInt | TokenKind::NumHexInt | TokenKind::NumDecInt => "integer",
+            TokenKind::NumDecFloat => "float",
+            TokenKind::Attribute => "attribute",
+            TokenKind::Identifier(_) => "identifier",
+            TokenKind::String => "string",
+            TokenKind::DocComment | TokenKind::InnerDocComment => "doc comment",
+            TokenKind::RoundBraces { .. } => "`(`",
+            TokenKind::SquareBraces { .. } => "`[`",
+            TokenKind::CurlyBraces { .. } => "`{`",
+            TokenKind::ClosingBrace { .. } => "closing brace",
+            TokenKind::Error(_) => "invalid token",
+            _ => "token",
+        }
+        .to_string()
+    }
+
+    /// Copy without the indices of braces, so it stays the same when tokens are moved
+    pub fn shallow(&self) -> TokenKind {
+        match self {
+            TokenKind::RoundBraces { .. } => TokenKind::RoundBraces { close: 0 },
+            TokenKind::SquareBraces { .. } => TokenKind::SquareBraces { close: 0 },
+            TokenKind::CurlyBraces { .. } => TokenKind::CurlyBraces { close: 0 },
+            TokenKind::ClosingBrace { .. } => TokenKind::ClosingBrace { open: 0 },
+            kind => kind.clone(),
+        }
+    }
+
+    /// Index of the matching closing brace, for opening braces
+    pub fn close(&self) -> Option<usize> {
+        match self {
+            TokenKind::RoundBraces { close }
+            | TokenKind::SquareBraces { close }
+            | TokenKind::CurlyBraces { close } => Some(*close),
+            _ => None,
+        }
+    }
+}
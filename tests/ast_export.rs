@@ -0,0 +1,187 @@
+//! `esl ast` output formats, JSON needs `cargo test --features serde`
+
+mod common;
+
+use esl::parser::export::*;
+
+use common::parse;
+
+const SOURCE: &str = "{ let a = f(x, \"s\\n\"); a += -1.5 }";
+
+const SEXPR: &str = "\
+(Block 0..34 returns
+  (ValueDeclaration 2..21 let
+    (Identifier 6..7 a)
+    (Expression 10..21
+      (Identifier 10..11 f)
+      (Operation 11..21 FuncCall
+        (Expression 12..13
+          (Identifier 12..13 x))
+        (Expression 15..20
+          (StringLiteral 15..20 \"s\\n\")))))
+  (Assignment 23..32 Add
+    (Expression 23..24
+      (Identifier 23..24 a))
+    (Expression 28..32
+      (FloatingLiteral 29..32 1.5)
+      (Operation 28..29 Neg))))
+";
+
+#[test]
+fn sexpr_shows_kinds_spans_and_values() {
+    assert_eq!(to_sexpr(&parse(SOURCE), SOURCE.as_bytes()), SEXPR);
+}
+
+#[test]
+fn sexpr_shows_error_codes() {
+    let src = "{ let a = 0x; }";
+    let sexpr = to_sexpr(&parse(src), src.as_bytes());
+    assert!(sexpr.contains("(Error 10..12 E0109)"), "{sexpr}");
+}
+
+#[cfg(feature = "serde")]
+mod json {
+    use esl::parser::bigint::MAX_DIGITS;
+
+    use super::*;
+
+    #[test]
+    fn json_round_trips() {
+        let sources = [
+            SOURCE,
+            "{ var s = (p, q: int = 2) -> { p + q }; s(_, 3)?; }",
+            // errors and fixes are kept too
+            "{ let a 1; whiel x; let b = 0x; \"\\q\"; (a + ) }",
+        ];
+        for src in sources {
+            let file = parse(src);
+            let read = from_json(&to_json(&file)).unwrap();
+            assert_eq!(
+                to_sexpr(&read, src.as_bytes()),
+                to_sexpr(&file, src.as_bytes())
+            );
+            assert_eq!(read.ast.len(), file.ast.len());
+            for id in file.ast.ids() {
+                assert_eq!(read.ast.parent(id), file.ast.parent(id));
+            }
+        }
+    }
+
+    #[test]
+    fn expected_tree_from_json() {
+        let src = "{ x }";
+        let expected = from_json(
+            r#"{
+                "names": ["x"],
+                "ast": {
+                    "nodes": [
+                        {"kind": {"Identifier": {"name": 0}}, "range": {"file": 0, "start": 2, "end": 3}},
+                        {"kind": {"Expression": {"rpn": [0]}}, "range": {"file": 0, "start": 2, "end": 3}},
+                        {
+                            "kind": {"Block": {"items": [1], "returns_last": true, "docs": []}},
+                            "range": {"file": 0, "start": 0, "end": 5}
+                        }
+                    ],
+                    "roots": [2],
+                    "docs": []
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            to_sexpr(&expected, src.as_bytes()),
+            to_sexpr(&parse(src), src.as_bytes())
+        );
+    }
+
+    #[test]
+    fn unknown_child_is_an_error() {
+        let err = from_json(
+            r#"{"names": [], "ast": {"nodes": [
+                {"kind": {"Expression": {"rpn": [7]}}, "range": {"file": 0, "start": 0, "end": 1}}
+            ], "roots": [0], "docs": []}}"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown child 7"), "{err}");
+    }
+
+    // File of blocks with the given items
+    fn blocks(items: &[&[u32]], roots: &[u32]) -> String {
+        let nodes = items
+            .iter()
+            .map(|items| {
+                format!(
+                    r#"{{"kind": {{"Block": {{"items": {items:?}, "returns_last": false, "docs": []}}}},
+                        "range": {{"file": 0, "start": 0, "end": 2}}}}"#
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(r#"{{"names": [], "ast": {{"nodes": [{nodes}], "roots": {roots:?}, "docs": []}}}}"#)
+    }
+
+    #[test]
+    fn arena_invariants_are_checked() {
+        let cases = [
+            (blocks(&[&[0]], &[0]), "node 0 has child 0 which is not before it"),
+            (blocks(&[&[1], &[0]], &[0]), "node 0 has child 1 which is not before it"),
+            (blocks(&[&[], &[0], &[0]], &[1, 2]), "node 0 has more than one parent"),
+            (blocks(&[&[], &[0, 0]], &[1]), "node 0 has more than one parent"),
+            (blocks(&[&[], &[0]], &[0, 1]), "root 0 has a parent or is repeated"),
+            (blocks(&[&[], &[0]], &[1, 1]), "root 1 has a parent or is repeated"),
+            (blocks(&[&[], &[]], &[1]), "node 0 has no parent and is not a root"),
+        ];
+        for (json, message) in cases {
+            let err = from_json(&json).unwrap_err();
+            assert!(err.to_string().contains(message), "{json}: {err}");
+        }
+        assert!(from_json(&blocks(&[&[], &[0]], &[1])).is_ok());
+    }
+
+    #[test]
+    fn repeated_name_is_an_error() {
+        // `y` would become symbol 1 after the repeated `x` is dropped
+        let err = from_json(
+            r#"{"names": ["x", "x", "y"], "ast": {"nodes": [], "roots": [], "docs": []}}"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("name `x` is repeated"), "{err}");
+    }
+
+    #[test]
+    fn unknown_symbol_is_an_error() {
+        let err = from_json(
+            r#"{"names": ["x"], "ast": {"nodes": [
+                {"kind": {"Identifier": {"name": 1}}, "range": {"file": 0, "start": 0, "end": 1}}
+            ], "roots": [0], "docs": []}}"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown symbol 1"), "{err}");
+    }
+
+    #[test]
+    fn integers_are_decimal_strings() {
+        let src = "{ 0x1F; 340282366920938463463374607431768211456 }";
+        let json = to_json(&parse(src));
+        assert!(json.contains(r#""value": "31""#), "{json}");
+        assert!(json.contains(r#""value": "340282366920938463463374607431768211456""#), "{json}");
+
+        let node = |value: &str| {
+            format!(
+                r#"{{"names": [], "ast": {{"nodes": [
+                    {{"kind": {{"IntegerLiteral": {{"value": {value}, "error": null}}}},
+                      "range": {{"file": 0, "start": 0, "end": 1}}}}
+                ], "roots": [0], "docs": []}}}}"#
+            )
+        };
+        assert!(from_json(&node(r#""4294967296""#)).is_ok());
+        for value in [r#""""#, r#""-1""#, r#""0x1F""#, "[5, 1]"] {
+            assert!(from_json(&node(value)).is_err(), "{value}");
+        }
+        let err = from_json(&node(r#""12a""#)).unwrap_err();
+        assert!(err.to_string().contains("`12a` is not a decimal integer"), "{err}");
+        let long = format!("\"{}\"", "1".repeat(MAX_DIGITS + 1));
+        let err = from_json(&node(&long)).unwrap_err();
+        assert!(err.to_string().contains("more than 1024 digits"), "{err}");
+    }
+}
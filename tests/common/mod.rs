@@ -0,0 +1,70 @@
+//! Fixtures shared by the integration tests, each test uses only some of them
+#![allow(dead_code)]
+
+use esl::{
+    diagnostics,
+    parser::{Parser, export::AstFile},
+    source::FileId,
+    tokenizer::{Tokenizer, token::Token},
+};
+
+/// Tokens and tree of `src` as file 0, syntax errors stay in the tree
+pub fn parse_with_tokens(src: &str) -> (Vec<Token>, AstFile) {
+    let mut tokenizer = Tokenizer::new(FileId(0), src.as_bytes());
+    tokenizer.tokenize();
+    let mut parser = Parser::new(FileId(0), src.as_bytes(), &tokenizer.tokens);
+    parser.p_file();
+    let file = AstFile {
+        names: tokenizer.interner,
+        ast: parser.ast,
+    };
+    (tokenizer.tokens, file)
+}
+
+pub fn parse(src: &str) -> AstFile {
+    parse_with_tokens(src).1
+}
+
+/// Panics on syntax errors, for tests of the passes after the parser
+pub fn parse_valid(src: &str) -> AstFile {
+    let (tokens, file) = parse_with_tokens(src);
+    assert!(
+        diagnostics::collect(&tokens, &file.ast).is_empty(),
+        "{src} has syntax errors"
+    );
+    file
+}
+
+/// Deterministic pseudo-random numbers, a failing seed reproduces the input
+pub struct XorShift(pub u64);
+
+impl XorShift {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// Writes the source to a fresh file in the temp dir, removed when dropped
+pub struct TempFile(std::path::PathBuf);
+
+impl TempFile {
+    pub fn new(name: &str, src: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("esl-tests-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, src).unwrap();
+        Self(path)
+    }
+    pub fn path(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
@@ -0,0 +1,361 @@
+//! Literal subexpressions and `@const` bindings are evaluated at compile time
+
+mod common;
+
+use esl::{
+    diagnostics::{self, Diagnostic},
+    parser::{bigint::MAX_DIGITS, export::AstFile, nodes::NodeKind, printer},
+    semantic::{
+        consts::{self, Constants, Integer, Value},
+        errors::ConstError,
+        resolve::resolve,
+        typeck,
+        types::{IntType, Type},
+    },
+};
+
+use common::{parse, parse_valid};
+
+fn evaluate(src: &str) -> (AstFile, Constants) {
+    let file = parse_valid(src);
+    let resolution = resolve(&file);
+    let typing = typeck::check(&file, &resolution);
+    let constants = consts::evaluate(&file, src.as_bytes(), &resolution, &typing);
+    (file, constants)
+}
+
+// Errors of `{ expr }` with the text they point at
+fn errors(expr: &str) -> Vec<(ConstError, String)> {
+    let src = format!("{{ {expr} }}");
+    let (_, constants) = evaluate(&src);
+    constants
+        .errors
+        .into_iter()
+        .map(|(err, span)| (err, src[span.as_range()].to_string()))
+        .collect()
+}
+
+// Source after folding, without the trailing newline
+fn folded_block(src: &str) -> String {
+    let (mut file, constants) = evaluate(src);
+    assert!(constants.errors.is_empty(), "{:?}", constants.errors);
+    consts::fold(&mut file, &constants);
+    printer::to_source(&file, src.as_bytes())
+        .trim_end()
+        .to_string()
+}
+
+// `expr` after folding, as the value of a block
+fn folded(expr: &str) -> String {
+    let block = folded_block(&format!("{{ {expr} }}"));
+    block
+        .strip_prefix("{\n    ")
+        .and_then(|b| b.strip_suffix("\n}"))
+        .unwrap_or(&block)
+        .to_string()
+}
+
+#[test]
+fn literal_subexpressions_are_folded() {
+    assert_eq!(folded("1 + 2 * 3"), "7");
+    assert_eq!(folded("(1 + 2) * 3"), "9");
+    assert_eq!(folded("2 ** 10 - 1"), "1023");
+    assert_eq!(folded("7 / 2 + 7 % 2"), "4");
+    assert_eq!(folded("1 - 5"), "-4");
+    assert_eq!(folded("0.5 + 1.0"), "1.5");
+    assert_eq!(folded("\"a\" + \"b\""), "\"ab\"");
+    assert_eq!(folded("1 < 2 && !false"), "true");
+    assert_eq!(folded("1 << 4 | 3"), "19");
+}
+
+#[test]
+fn only_constant_parts_are_folded() {
+    assert_eq!(folded("f(1 + 2, x)"), "f(3, x)");
+    assert_eq!(folded("x + (2 * 3)"), "x + 6");
+    assert_eq!(
+        folded_block("{ var a = 1; a = 2 + 3; a }"),
+        "{\n    var a = 1;\n    a = 5;\n    a\n}"
+    );
+    // `x` is a runtime value even when it starts as a literal
+    assert_eq!(
+        folded_block("{ let x = 1; x + 1 }"),
+        "{\n    let x = 1;\n    x + 1\n}"
+    );
+}
+
+#[test]
+fn const_bindings_are_folded() {
+    let src = "{ @const let a = 2 * 3; @const let b = a + 1; b * x }";
+    assert_eq!(
+        folded_block(src),
+        "{\n    @const let a = 6;\n    @const let b = 7;\n    b * x\n}"
+    );
+    let (file, constants) = evaluate("{ @const let limit = 1 << 10; limit }");
+    let binding = file
+        .ast
+        .ids()
+        .find_map(|id| match &file.ast[id].kind {
+            NodeKind::ValueDeclaration(decl) => Some(decl.declaration),
+            _ => None,
+        })
+        .expect("a binding");
+    assert_eq!(
+        constants.value(binding),
+        Some(&Value::Int(Integer::NonNegative(1024)))
+    );
+}
+
+#[test]
+fn overflow_is_reported() {
+    assert_eq!(
+        errors("9223372036854775807 + 1"),
+        [(
+            ConstError::Overflow {
+                op: "+",
+                ty: Type::Int
+            },
+            "9223372036854775807 + 1".into()
+        )]
+    );
+    assert_eq!(
+        errors("x + 2 ** 63"),
+        [(
+            ConstError::Overflow {
+                op: "**",
+                ty: Type::Int
+            },
+            "2 ** 63".into()
+        )]
+    );
+    assert_eq!(
+        errors("-(-9223372036854775807 - 1)"),
+        [(
+            ConstError::Overflow {
+                op: "-",
+                ty: Type::Int
+            },
+            "-(-9223372036854775807 - 1)".into()
+        )]
+    );
+    // `-9223372036854775808` fits after the negation
+    assert!(errors("-9223372036854775808 + 0").is_empty());
+    // reported once, the outer operation has no value
+    assert_eq!(errors("(9223372036854775807 * 2) + 1").len(), 1);
+}
+
+#[test]
+fn division_by_zero_and_bad_operands() {
+    assert_eq!(
+        errors("1 / 0"),
+        [(ConstError::DivisionByZero, "1 / 0".into())]
+    );
+    assert_eq!(
+        errors("1 % (2 - 2)"),
+        [(ConstError::DivisionByZero, "1 % (2 - 2)".into())]
+    );
+    assert_eq!(
+        errors("1 << 64"),
+        [(
+            ConstError::ShiftOutOfRange {
+                amount: Integer::NonNegative(64),
+                bits: 64
+            },
+            "1 << 64".into()
+        )]
+    );
+    assert_eq!(
+        errors("2 ** -1"),
+        [(ConstError::NegativeExponent, "2 ** -1".into())]
+    );
+    // floats follow IEEE 754, infinity is not folded
+    assert!(errors("1.0 / 0.0").is_empty());
+    assert_eq!(folded("1.0 / 0.0"), "1.0 / 0.0");
+}
+
+#[test]
+fn const_initializers_must_be_constant() {
+    let src = "{ let x = 1; @const let a = 1 + x * 2; a }";
+    let (_, constants) = evaluate(src);
+    assert_eq!(constants.errors.len(), 1);
+    let (ConstError::NotConstant { unknown }, span) = &constants.errors[0] else {
+        panic!("{:?}", constants.errors);
+    };
+    assert_eq!(&src[span.as_range()], "1 + x * 2");
+    assert_eq!(&src[unknown.as_range()], "x");
+
+    // already reported, not a second time
+    assert_eq!(
+        errors("@const let a = 1 / 0; a"),
+        [(ConstError::DivisionByZero, "1 / 0".into())]
+    );
+    assert_eq!(errors("@const let f = (x) -> x; f").len(), 1);
+}
+
+#[test]
+fn diagnostics_point_at_the_unknown_part() {
+    let src = "{ let x = 1; @const let a = f(x) }";
+    let (_, constants) = evaluate(src);
+    let mut out = Vec::<Diagnostic>::new();
+    diagnostics::collect_consts(&constants, &mut out);
+    assert_eq!(out.len(), 1);
+    assert_eq!(out[0].code, "E0505");
+    assert_eq!(&src[out[0].primary.span.as_range()], "f(x)");
+    assert_eq!(&src[out[0].secondary[0].span.as_range()], "f");
+}
+
+fn sized(name: &str) -> Type {
+    Type::Sized(IntType::from_name(name).expect("a sized integer type"))
+}
+
+#[test]
+fn literals_are_checked_against_their_type() {
+    assert!(errors("let a: u8 = 255; let b: i8 = -128; let c: i8 = 127; a").is_empty());
+    assert_eq!(
+        errors("let a: u8 = 256; a"),
+        [(
+            ConstError::LiteralOutOfRange { ty: sized("u8") },
+            "256".into()
+        )]
+    );
+    assert_eq!(
+        errors("let a: i8 = -129; a"),
+        [(
+            ConstError::LiteralOutOfRange { ty: sized("i8") },
+            "-129".into()
+        )]
+    );
+    assert_eq!(
+        errors("let a: u16 = -1; a"),
+        [(
+            ConstError::LiteralOutOfRange { ty: sized("u16") },
+            "-1".into()
+        )]
+    );
+    // the type comes from the use when there is no hint
+    assert_eq!(
+        errors("let a: u8 = 1; a + 1000"),
+        [(
+            ConstError::LiteralOutOfRange { ty: sized("u8") },
+            "1000".into()
+        )]
+    );
+    assert_eq!(
+        errors("9223372036854775808"),
+        [(
+            ConstError::LiteralOutOfRange { ty: Type::Int },
+            "9223372036854775808".into()
+        )]
+    );
+    assert!(errors("-9223372036854775808").is_empty());
+}
+
+#[test]
+fn literals_of_any_size() {
+    let src = "let a: u128 = 0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF; \
+        let b: i128 = -170141183460469231731687303715884105728; a";
+    assert!(errors(src).is_empty());
+    let (_, constants) = evaluate(&format!("{{ {src} }}"));
+    let values = constants.values.values().collect::<Vec<_>>();
+    assert!(values.contains(&&Value::Int(Integer::NonNegative(u128::MAX))));
+    assert!(values.contains(&&Value::Int(Integer::Negative(i128::MIN))));
+
+    let huge = "340282366920938463463374607431768211456";
+    assert_eq!(
+        errors(&format!("let a: u128 = {huge}; a")),
+        [(
+            ConstError::LiteralOutOfRange { ty: sized("u128") },
+            huge.into()
+        )]
+    );
+    // kept exactly, even when it fits no type
+    assert_eq!(literal(&format!("{huge}0")), format!("{huge}0"));
+}
+
+// Decimal value of the only literal in `{ expr }`
+fn literal(expr: &str) -> String {
+    let file = parse_valid(&format!("{{ {expr} }}"));
+    let digits = file.ast.ids().find_map(|id| match &file.ast[id].kind {
+        NodeKind::IntegerLiteral(lit) => Some(lit.value.to_string()),
+        _ => None,
+    });
+    digits.unwrap_or_else(|| panic!("no literal in {expr}"))
+}
+
+#[test]
+fn long_literals() {
+    // digits are read a limb at a time, values cross the chunk borders
+    let longest = "9".repeat(MAX_DIGITS);
+    for digits in ["123456789", "1234567890", "12345678901234567890", &longest] {
+        assert_eq!(literal(digits), digits);
+    }
+    assert_eq!(literal(&format!("0b{}", "1".repeat(40))), ((1u64 << 40) - 1).to_string());
+    assert_eq!(literal("0xFFFFFFFFFFFFFFFFFFFF"), "1208925819614629174706175");
+
+    // longer ones are an error, reading them would take quadratic time
+    let src = format!("{{ {}9 }}", longest);
+    let file = parse(&src);
+    let errors = file
+        .ast
+        .ids()
+        .filter_map(|id| match &file.ast[id].kind {
+            NodeKind::Error(err) => Some(err.code()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(errors, ["E0118"]);
+}
+
+#[test]
+fn sized_arithmetic_wraps_only_bits() {
+    assert_eq!(
+        errors("let a: u8 = 200 + 100; a"),
+        [(
+            ConstError::Overflow {
+                op: "+",
+                ty: sized("u8")
+            },
+            "200 + 100".into()
+        )]
+    );
+    assert_eq!(
+        errors("let a: u32 = 1 - 2; a"),
+        [(
+            ConstError::Overflow {
+                op: "-",
+                ty: sized("u32")
+            },
+            "1 - 2".into()
+        )]
+    );
+    assert_eq!(
+        errors("let a: i8 = -(-128); a"),
+        [(
+            ConstError::Overflow {
+                op: "-",
+                ty: sized("i8")
+            },
+            "-(-128)".into()
+        )]
+    );
+    assert_eq!(
+        errors("let a: u32 = 1 << 32; a"),
+        [(
+            ConstError::ShiftOutOfRange {
+                amount: Integer::NonNegative(32),
+                bits: 32
+            },
+            "1 << 32".into()
+        )]
+    );
+    assert_eq!(
+        folded_block(
+            "{ let a: u8 = !0; let b: i8 = 1 << 7; let c: u128 = 2 ** 127 - 1 + 2 ** 127; }"
+        ),
+        "{\n    let a: u8 = 255;\n    let b: i8 = -128;\n    \
+            let c: u128 = 340282366920938463463374607431768211455;\n}"
+    );
+    assert_eq!(
+        folded_block("{ let a: i16 = -7 / 2 + -7 % 2 + (-1 >> 3); }"),
+        "{\n    let a: i16 = -5;\n}"
+    );
+}
@@ -0,0 +1,150 @@
+//! Golden files: every `tests/corpus/*.esl` has its tokens, tree and diagnostics checked in
+//! next to it as `.tokens`, `.ast` and `.diagnostics`.
+//! Run `BLESS=1 cargo test --test corpus` to rewrite them after an intended change
+
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use esl::{
+    diagnostics::{self, render::Renderer},
+    parser::{Parser, export},
+    source::SourceMap,
+    tokenizer::{
+        Tokenizer,
+        token::{Token, TokenKind},
+    },
+};
+
+const CORPUS: &str = "tests/corpus";
+const EXPECTATIONS: &[&str] = &["tokens", "ast", "diagnostics"];
+
+fn dump_tokens(tokens: &[Token], src: &[u8]) -> String {
+    let mut out = String::new();
+    let mut indent = 0;
+    for token in tokens {
+        if let TokenKind::ClosingBrace { .. } = token.kind {
+            indent -= 1;
+        }
+        let text = String::from_utf8_lossy(&src[token.range.as_range()]);
+        let range = token.range;
+        let pad = "  ".repeat(indent);
+        let _ = writeln!(
+            out,
+            "{pad}{}..{} {:?} {text:?}",
+            range.start, range.end, token.kind
+        );
+        if token.kind.close().is_some() {
+            indent += 1;
+        }
+    }
+    out
+}
+
+// Dumps of the file, in the order of EXPECTATIONS
+fn run(path: &Path) -> [String; 3] {
+    let mut sources = SourceMap::new();
+    // the same name on every machine
+    let name = format!("{CORPUS}/{}", path.file_name().unwrap().to_string_lossy());
+    let file = sources.add_file(name, std::fs::read(path).unwrap());
+    let src = &sources.get(file).src;
+
+    let mut tokenizer = Tokenizer::new(file, src);
+    tokenizer.tokenize();
+    let mut parser = Parser::new(file, src, &tokenizer.tokens);
+    parser.p_file();
+
+    let tokens = dump_tokens(&tokenizer.tokens, src);
+    let diags = diagnostics::collect(&tokenizer.tokens, &parser.ast);
+    let ast = export::AstFile {
+        names: tokenizer.interner,
+        ast: parser.ast,
+    };
+
+    let mut rendered = Vec::new();
+    Renderer::new(false)
+        .render_all(&mut rendered, &sources, &diags)
+        .unwrap();
+    [
+        tokens,
+        export::to_sexpr(&ast, src),
+        String::from_utf8(rendered).unwrap(),
+    ]
+}
+
+// First differing line, enough to find the change
+fn first_difference(expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    for line in 1.. {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => continue,
+            (None, None) => break,
+            (e, a) => {
+                return format!(
+                    "line {line}\n    expected: {}\n    actual:   {}",
+                    e.unwrap_or("<end of file>"),
+                    a.unwrap_or("<end of file>")
+                );
+            }
+        }
+    }
+    "line endings differ".to_string()
+}
+
+fn corpus_files() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(CORPUS);
+    let mut files = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+#[test]
+fn corpus() {
+    let bless = std::env::var_os("BLESS").is_some_and(|v| v != "0");
+    let files = corpus_files();
+    let mut failures = Vec::new();
+    let mut checked = 0;
+
+    for path in &files {
+        let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
+            continue;
+        };
+        if ext != "esl" {
+            if EXPECTATIONS.contains(&ext) && !path.with_extension("esl").exists() {
+                failures.push(format!("{}: no .esl file for it", path.display()));
+            }
+            continue;
+        }
+
+        checked += 1;
+        for (ext, actual) in EXPECTATIONS.iter().zip(run(path)) {
+            let expected_path = path.with_extension(ext);
+            if bless {
+                std::fs::write(&expected_path, &actual).unwrap();
+                continue;
+            }
+            match std::fs::read_to_string(&expected_path) {
+                Ok(expected) if expected == actual => (),
+                Ok(expected) => failures.push(format!(
+                    "{}: differs at {}",
+                    expected_path.display(),
+                    first_difference(&expected, &actual)
+                )),
+                Err(_) => failures.push(format!("{}: missing", expected_path.display())),
+            }
+        }
+    }
+
+    assert!(checked > 0, "no .esl files in {CORPUS}");
+    assert!(
+        failures.is_empty(),
+        "{} corpus mismatches, rerun with BLESS=1 if the changes are intended:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,114 @@
+//! Tree, DOT and HTML renderings of the parsed tree
+
+mod common;
+
+use std::process::{Command, Stdio};
+
+use esl::parser::{ast::Ast, debugger::Debugger};
+
+use common::{TempFile, parse};
+
+const SOURCE: &str = "{ let a = f(x) * 0x; let b = \"ü\" + y; }";
+
+
+fn render(
+    debugger: &Debugger,
+    write: fn(&Debugger, &mut Vec<u8>, &Ast, &[u8]) -> std::io::Result<()>,
+) -> String {
+    let mut out = Vec::new();
+    write(debugger, &mut out, &parse(SOURCE).ast, SOURCE.as_bytes()).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+fn tree(debugger: &Debugger) -> String {
+    render(debugger, |d, out, ast, src| d.write_tree(out, ast, src))
+}
+
+#[test]
+fn max_depth_cuts_the_tree() {
+    let full = tree(&Debugger::new());
+    let cut = tree(&Debugger::new().with_max_depth(1));
+    assert!(full.contains("Identifier"), "{full}");
+    assert!(!cut.contains("Identifier"), "{cut}");
+    assert_eq!(
+        cut.lines().filter(|line| line.ends_with("└── ...")).count(),
+        2,
+        "{cut}"
+    );
+}
+
+#[test]
+fn spans_are_optional() {
+    assert!(!tree(&Debugger::new()).contains(" @ "));
+    assert!(tree(&Debugger::new().with_spans(true)).contains(" @ 0..40 -> {"));
+}
+
+#[test]
+fn error_free_subtrees_are_hidden() {
+    let errors = tree(&Debugger::new().hide_error_free(true));
+    assert!(errors.contains("Error(InvalidNumber) -> 0x"), "{errors}");
+    assert!(!errors.contains("-> let b"), "{errors}");
+    assert!(!errors.contains("Identifier"), "{errors}");
+}
+
+#[test]
+fn dot_has_a_node_per_line_and_marks_errors() {
+    let dot = render(&Debugger::new(), |d, out, ast, src| {
+        d.write_dot(out, ast, src)
+    });
+    assert!(
+        dot.starts_with("digraph ast {\n") && dot.ends_with("}\n"),
+        "{dot}"
+    );
+    let nodes = dot.lines().filter(|line| line.contains("[label=")).count();
+    let edges = dot.lines().filter(|line| line.contains(" -> n")).count();
+    assert_eq!(nodes, parse(SOURCE).ast.len());
+    // every node but the root has a parent
+    assert_eq!(edges, nodes - 1);
+    assert!(
+        dot.contains(
+            "[label=\"Error E0109\\nNumber literal has no digits (0x1F)\\n0x\", color=red"
+        ),
+        "{dot}"
+    );
+    assert!(dot.contains("\\\"ü\\\" + y"), "{dot}");
+}
+
+#[test]
+fn html_ranges_are_in_utf16_units() {
+    let html = render(&Debugger::new(), |d, out, ast, src| {
+        d.write_html(out, ast, src)
+    });
+    assert!(
+        html.contains("<pre id=\"src\">{ let a = f(x) * 0x; let b = &quot;ü&quot; + y; }</pre>")
+    );
+    // `y` is at byte 36, but `ü` takes a single UTF-16 unit
+    assert!(
+        html.contains("data-start=\"35\" data-end=\"36\"><b>Identifier</b> <code>y</code>"),
+        "{html}"
+    );
+    assert!(html.contains("<details open><summary class=\"node\""));
+    assert!(html.contains("class=\"node error\""));
+}
+
+#[test]
+fn closed_stdout_is_reported_without_panic() {
+    // much more output than a pipe buffer holds
+    let src = format!("{{ {} }}", "let a = f(x, [1, 2]) * 3; ".repeat(5_000));
+    let file = TempFile::new("debug.esl", &src);
+    for format in ["tree", "dot", "html"] {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_esl"))
+            .args(["debug", "--format", format, file.path()])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        // like `esl debug file.esl | head -0`
+        drop(child.stdout.take());
+        let output = child.wait_with_output().unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(2), "{format}: {stderr}");
+        assert!(stderr.contains("can not write output"), "{format}: {stderr}");
+        assert!(!stderr.contains("panicked"), "{format}: {stderr}");
+    }
+}
@@ -0,0 +1,217 @@
+//! `esl check --format json|sarif`: the JSON schema is pinned, SARIF is checked by structure
+
+mod common;
+
+use std::process::Command;
+
+use esl::{
+    diagnostics::{
+        Diagnostic,
+        export::{JSON_SCHEMA_VERSION, Json, to_json, to_sarif},
+    },
+    source::{FileId, SourceMap, Span},
+};
+
+use common::TempFile;
+
+fn sources() -> SourceMap {
+    let mut sources = SourceMap::new();
+    sources.add_file("a.esl", b"{ let a = 1; }".to_vec());
+    sources.add_file("dir/b.esl", "{\n  \"é\" + let_x;\n}".as_bytes().to_vec());
+    sources
+}
+
+fn diagnostics() -> Vec<Diagnostic> {
+    let (a, b) = (FileId(0), FileId(1));
+    vec![
+        Diagnostic::warning("E0202", "unused variable `a`", Span::new(a, 6..7))
+            .with_label("never used")
+            .with_help("prefix it with `_`"),
+        Diagnostic::error("E0201", "cannot find `let_x`", Span::new(b, 11..16))
+            .with_label("not found")
+            .with_secondary(Span::new(a, 6..7), "similar name")
+            .with_note("names are \"case sensitive\"")
+            .with_suggestion(Span::new(b, 11..16), "use `a`", "a"),
+    ]
+}
+
+/// Field of an object, panics with the path to the missing key
+fn get<'a>(json: &'a Json, key: &str) -> &'a Json {
+    let Json::Object(fields) = json else {
+        panic!("expected object with {key:?}, found {json}");
+    };
+    match fields.iter().find(|(k, _)| *k == key) {
+        Some((_, value)) => value,
+        None => panic!("no {key:?} in {json}"),
+    }
+}
+
+fn items(json: &Json) -> &[Json] {
+    match json {
+        Json::Array(items) => items,
+        json => panic!("expected array, found {json}"),
+    }
+}
+
+#[test]
+fn json_schema_is_stable() {
+    let sources = sources();
+    let json = to_json(&sources, &diagnostics()).to_string();
+    // any change here breaks consumers, bump JSON_SCHEMA_VERSION with it
+    assert_eq!(JSON_SCHEMA_VERSION, 1);
+    let expected = concat!(
+        r#"{"version":1,"diagnostics":["#,
+        r#"{"severity":"warning","code":"E0202","message":"unused variable `a`","file":"a.esl","#,
+        r#""span":{"start":{"offset":6,"line":1,"column":7,"column_utf16":7},"#,
+        r#""end":{"offset":7,"line":1,"column":8,"column_utf16":8}},"#,
+        r#""labels":[{"file":"a.esl","#,
+        r#""span":{"start":{"offset":6,"line":1,"column":7,"column_utf16":7},"#,
+        r#""end":{"offset":7,"line":1,"column":8,"column_utf16":8}},"#,
+        r#""message":"never used","primary":true}],"#,
+        r#""notes":[],"help":"prefix it with `_`","suggestions":[]},"#,
+        r#"{"severity":"error","code":"E0201","message":"cannot find `let_x`","file":"dir/b.esl","#,
+        r#""span":{"start":{"offset":11,"line":2,"column":10,"column_utf16":9},"#,
+        r#""end":{"offset":16,"line":2,"column":15,"column_utf16":14}},"#,
+        r#""labels":[{"file":"dir/b.esl","#,
+        r#""span":{"start":{"offset":11,"line":2,"column":10,"column_utf16":9},"#,
+        r#""end":{"offset":16,"line":2,"column":15,"column_utf16":14}},"#,
+        r#""message":"not found","primary":true},"#,
+        r#"{"file":"a.esl","#,
+        r#""span":{"start":{"offset":6,"line":1,"column":7,"column_utf16":7},"#,
+        r#""end":{"offset":7,"line":1,"column":8,"column_utf16":8}},"#,
+        r#""message":"similar name","primary":false}],"#,
+        r#""notes":["names are \"case sensitive\""],"help":null,"#,
+        r#""suggestions":[{"message":"use `a`","file":"dir/b.esl","#,
+        r#""span":{"start":{"offset":11,"line":2,"column":10,"column_utf16":9},"#,
+        r#""end":{"offset":16,"line":2,"column":15,"column_utf16":14}},"#,
+        r#""replacement":"a"}]}]}"#,
+    );
+    assert_eq!(json, expected);
+}
+
+#[test]
+fn json_without_diagnostics() {
+    let json = to_json(&sources(), &[]).to_string();
+    assert_eq!(json, r#"{"version":1,"diagnostics":[]}"#);
+}
+
+#[test]
+fn sarif_structure() {
+    let sources = sources();
+    let sarif = to_sarif(&sources, &diagnostics());
+    assert_eq!(get(&sarif, "version"), &Json::str("2.1.0"));
+
+    let runs = items(get(&sarif, "runs"));
+    assert_eq!(runs.len(), 1);
+    let run = &runs[0];
+    assert_eq!(get(run, "columnKind"), &Json::str("utf16CodeUnits"));
+
+    let driver = get(get(run, "tool"), "driver");
+    assert_eq!(get(driver, "name"), &Json::str("esl"));
+    // one rule per code, sorted
+    let rules = items(get(driver, "rules"))
+        .iter()
+        .map(|rule| get(rule, "id").clone())
+        .collect::<Vec<_>>();
+    assert_eq!(rules, [Json::str("E0201"), Json::str("E0202")]);
+
+    let results = items(get(run, "results"));
+    assert_eq!(results.len(), 2);
+    assert_eq!(get(&results[0], "ruleId"), &Json::str("E0202"));
+    assert_eq!(get(&results[0], "level"), &Json::str("warning"));
+    assert_eq!(
+        get(get(&results[0], "message"), "text"),
+        &Json::str("unused variable `a`\nhelp: prefix it with `_`")
+    );
+
+    let error = &results[1];
+    assert_eq!(get(error, "level"), &Json::str("error"));
+    let locations = items(get(error, "locations"));
+    assert_eq!(locations.len(), 1);
+    let physical = get(&locations[0], "physicalLocation");
+    assert_eq!(
+        get(get(physical, "artifactLocation"), "uri"),
+        &Json::str("dir/b.esl")
+    );
+    // columns are in UTF-16 units, `é` counts once
+    let region = get(physical, "region");
+    assert_eq!(get(region, "startLine"), &Json::num(2));
+    assert_eq!(get(region, "startColumn"), &Json::num(9));
+    assert_eq!(get(region, "endLine"), &Json::num(2));
+    assert_eq!(get(region, "endColumn"), &Json::num(14));
+
+    let related = items(get(error, "relatedLocations"));
+    assert_eq!(get(&related[0], "id"), &Json::num(0));
+    let related = get(&related[0], "physicalLocation");
+    assert_eq!(
+        get(get(related, "artifactLocation"), "uri"),
+        &Json::str("a.esl")
+    );
+    assert_eq!(get(get(related, "region"), "startColumn"), &Json::num(7));
+
+    let fixes = items(get(error, "fixes"));
+    let change = &items(get(&fixes[0], "artifactChanges"))[0];
+    let replacement = &items(get(change, "replacements"))[0];
+    assert_eq!(
+        get(get(replacement, "insertedContent"), "text"),
+        &Json::str("a")
+    );
+    assert_eq!(
+        get(get(replacement, "deletedRegion"), "startColumn"),
+        &Json::num(9)
+    );
+}
+
+/*************************************************
+ *                      CLI                      *
+ *************************************************/
+
+fn esl_check(args: &[&str]) -> (Option<i32>, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_esl"))
+        .arg("check")
+        .args(args)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    (output.status.code(), stdout)
+}
+
+#[test]
+fn cli_json_output() {
+    let file = TempFile::new("json.esl", "{ let a = ; }");
+    let (code, stdout) = esl_check(&["--format", "json", file.path()]);
+    assert_eq!(code, Some(1));
+    assert!(stdout.starts_with(r#"{"version":1,"diagnostics":[{"severity":"error","code":"E"#));
+    assert!(stdout.contains(&format!(
+        r#""file":"{}""#,
+        file.path().replace('\\', "\\\\")
+    )));
+    assert!(stdout.contains(r#""start":{"offset":9,"line":1,"column":10,"column_utf16":10}"#));
+    assert!(stdout.ends_with("]}\n"));
+}
+
+#[test]
+fn cli_sarif_output() {
+    let file = TempFile::new("sarif.esl", "{ let a = ; }");
+    let (code, stdout) = esl_check(&["--format", "sarif", file.path()]);
+    assert_eq!(code, Some(1));
+    assert!(stdout.contains(r#""version":"2.1.0""#));
+    assert!(stdout.contains(r#""region":{"startLine":1,"startColumn":10,"#));
+}
+
+#[test]
+fn cli_clean_file_succeeds() {
+    let file = TempFile::new("clean.esl", "{ let x = 1; x; }");
+    let (code, stdout) = esl_check(&["--format", "json", file.path()]);
+    assert_eq!(code, Some(0));
+    assert_eq!(stdout, "{\"version\":1,\"diagnostics\":[]}\n");
+}
+
+#[test]
+fn cli_usage_and_io_errors() {
+    assert_eq!(esl_check(&["--format", "xml", "a.esl"]).0, Some(2));
+    assert_eq!(esl_check(&[]).0, Some(2));
+    let (code, stdout) = esl_check(&["--format", "json", "/nonexistent/esl/file.esl"]);
+    assert_eq!(code, Some(2));
+    assert!(stdout.is_empty());
+}
@@ -0,0 +1,46 @@
+//! Offline run of the fuzz target invariants over deterministic pseudo-random inputs.
+//! Use `cargo +nightly fuzz run parse` in `fuzz/` for the real thing
+
+mod common;
+
+use common::XorShift;
+
+const FRAGMENTS: &[&[u8]] = &[
+    b"{", b"}", b"(", b")", b"[", b"]", b"let ", b"var ", b"x", b"_", b"_a", b"1", b"0x", b"0b",
+    b"0b102", b"0xFF", b"99999999999999999999", b"1.5", b"\"s\"", b"\"", b"\\", b"/*", b"*/",
+    b"//", b"//!", b"///", b"->", b"=>", b"+", b"-", b"*", b"**", b"..", b"..=", b"::", b"|>",
+    b"?", b"!", b";", b",", b":", b"=", b"+=", b"<<=", b"@", b"@a", b"ref ", b"true", b"\xc3\xa9",
+    b"\xff", b" ", b"\n", b"\t", b"f(_, 2)", b"#", b"$",
+];
+
+#[test]
+fn random_fragments_do_not_panic() {
+    let mut rng = XorShift(0x5eed);
+    for _ in 0..3000 {
+        let len = rng.next() % 64;
+        let mut input = Vec::new();
+        for _ in 0..len {
+            input.extend_from_slice(FRAGMENTS[(rng.next() % FRAGMENTS.len() as u64) as usize]);
+        }
+        esl::fuzzing::check(&input);
+    }
+}
+
+#[test]
+fn random_bytes_do_not_panic() {
+    let mut rng = XorShift(0xb17e5);
+    for _ in 0..3000 {
+        let len = rng.next() % 128;
+        let input = (0..len).map(|_| rng.next() as u8).collect::<Vec<_>>();
+        esl::fuzzing::check(&input);
+    }
+}
+
+#[test]
+fn deep_nesting_does_not_overflow() {
+    for open in ["(", "[", "{", "{ a -> ", "{ f(", "{ x = (", "{ !-"] {
+        esl::fuzzing::check(open.repeat(2_000).as_bytes());
+    }
+    esl::fuzzing::check(format!("{{ {} }}", "a -> ".repeat(2_000)).as_bytes());
+    esl::fuzzing::check(format!("{{ {} }}", "/*".repeat(2_000)).as_bytes());
+}
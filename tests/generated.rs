@@ -0,0 +1,152 @@
+//! Properties of randomly generated valid programs, a failing seed reproduces the program
+
+mod common;
+
+use std::collections::BTreeSet;
+
+use esl::{
+    diagnostics,
+    fuzzing::{self, generate::Generator},
+    parser::{ast::Ast, nodes::NodeKind},
+};
+
+use common::parse_with_tokens;
+
+const SEEDS: u64 = 500;
+
+fn parse(src: &str) -> (Ast, usize) {
+    let (tokens, file) = parse_with_tokens(src);
+    let diags = diagnostics::collect(&tokens, &file.ast);
+    (file.ast, diags.len())
+}
+
+#[test]
+fn same_seed_same_program() {
+    for seed in [0, 1, u64::MAX] {
+        assert_eq!(
+            Generator::new(seed).program(),
+            Generator::new(seed).program()
+        );
+    }
+    assert_ne!(Generator::new(1).program(), Generator::new(2).program());
+}
+
+#[test]
+fn generated_programs_have_no_errors() {
+    for seed in 0..SEEDS {
+        let src = Generator::new(seed).program();
+        let (ast, diags) = parse(&src);
+        assert_eq!(diags, 0, "seed {seed} has diagnostics:\n{src}");
+        for id in ast.ids() {
+            assert!(
+                !matches!(ast[id].kind, NodeKind::Error(_)),
+                "seed {seed} has an error node {:?}:\n{src}",
+                ast[id].range
+            );
+        }
+    }
+}
+
+#[test]
+fn children_are_inside_of_parents() {
+    for seed in 0..SEEDS {
+        let src = Generator::new(seed).program();
+        fuzzing::check(src.as_bytes());
+
+        let (ast, _) = parse(&src);
+        for id in ast.ids() {
+            for child in ast.children(id) {
+                assert!(
+                    ast[id].range.contains(ast[child].range),
+                    "seed {seed}: {:?} of {} is outside of {:?} of {}:\n{src}",
+                    ast[child].range,
+                    ast[child].kind.name(),
+                    ast[id].range,
+                    ast[id].kind.name(),
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn every_kind_and_operation_is_generated() {
+    let mut kinds = BTreeSet::new();
+    let mut operations = BTreeSet::new();
+    let mut types = BTreeSet::new();
+    for seed in 0..SEEDS {
+        let (ast, _) = parse(&Generator::new(seed).program());
+        for id in ast.ids() {
+            kinds.insert(ast[id].kind.name());
+            if let NodeKind::Operation(op) = &ast[id].kind {
+                // calls have their arguments after the name
+                let name = format!("{op:?}");
+                operations.insert(name.split(' ').next().unwrap_or_default().to_string());
+            }
+            if let NodeKind::Type(ty) = &ast[id].kind {
+                let name = format!("{ty:?}");
+                types.insert(name.split(' ').next().unwrap_or_default().to_string());
+            }
+        }
+    }
+
+    let all_kinds = [
+        "IntegerLiteral",
+        "FloatingLiteral",
+        "StringLiteral",
+        "BooleanLiteral",
+        "Identifier",
+        "DontCare",
+        "Operation",
+        "Expression",
+        "ValueDeclaration",
+        "Block",
+        "Assignment",
+        "Lambda",
+        "Type",
+    ];
+    let all_operations = [
+        "Add",
+        "Sub",
+        "Neg",
+        "Mul",
+        "Div",
+        "Mod",
+        "Pow",
+        "Gt",
+        "Ge",
+        "Lt",
+        "Le",
+        "Ne",
+        "Eq",
+        "Or",
+        "And",
+        "Xor",
+        "Shl",
+        "Shr",
+        "Not",
+        "LogOr",
+        "LogAnd",
+        "Dot",
+        "Path",
+        "Range",
+        "RangeInc",
+        "Pipe",
+        "Arrow",
+        "Try",
+        "Ref",
+        "FuncCall",
+        "PartialCall",
+        "ValueCtor",
+        "TypeCtor",
+    ];
+    for kind in all_kinds {
+        assert!(kinds.contains(kind), "{kind} is never generated");
+    }
+    for op in all_operations {
+        assert!(operations.contains(op), "{op} is never generated");
+    }
+    for ty in ["Named", "Function", "Tuple", "Optional", "Ref"] {
+        assert!(types.contains(ty), "{ty} type is never generated");
+    }
+}
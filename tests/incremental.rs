@@ -0,0 +1,164 @@
+//! Incremental reparsing gives the same tokens, tree and diagnostics as parsing from scratch
+
+mod common;
+
+use esl::{
+    diagnostics,
+    document::{Document, Reparsed},
+    parser::{
+        Parser,
+        ast::{Ast, NodeId},
+    },
+    source::{FileId, Span},
+    tokenizer::Tokenizer,
+};
+
+use common::XorShift;
+
+const SOURCE: &str = "{
+    //! docs
+    let a: int = f(x, y + 1) * (b - c) / 3;
+    var s = (p, q) -> {
+        let t = p + q * 2;
+        g(t, { k }, \"text\")
+    };
+    s += h(a)?.field;
+    let l = x -> y -> { x + y };
+}
+";
+
+const FRAGMENTS: &[&str] = &[
+    "{", "}", "(", ")", "[", "]", "let ", "var ", "x", "_", "1", "0x", "99999999999999999999",
+    "\"s\"", "\"", "\"\\q\"", "\\", "/*", "*/", "//", "//!", "->", "+", "*", ";", ",", ":", "=",
+    "+=", "?", "whiel ", "\n", " ", "f(_, 2)", "#",
+];
+
+// Debug output of the reachable tree, ids differ between the two trees so they are hidden
+fn dump(ast: &Ast, id: NodeId, out: &mut String) {
+    let kind = format!("{:?}", ast[id].kind);
+    let mut rest = kind.as_str();
+    while let Some(at) = rest.find("NodeId(") {
+        out.push_str(&rest[..at]);
+        let digits = rest[at + 7..].find(')').unwrap_or(0);
+        rest = &rest[at + 8 + digits..];
+    }
+    out.push_str(rest);
+    out.push_str(&format!(" {:?} [", ast[id].range));
+    for child in ast.children(id) {
+        assert_eq!(ast.parent(child), Some(id));
+        dump(ast, child, out);
+    }
+    out.push(']');
+}
+
+fn dump_roots(ast: &Ast) -> String {
+    let mut out = String::new();
+    for root in &ast.roots {
+        dump(ast, *root, &mut out);
+    }
+    out
+}
+
+fn assert_same_as_full(doc: &Document) {
+    // the same interner, so symbols of the same names are the same
+    let mut tokenizer = Tokenizer::with_interner(doc.file, &doc.src, doc.interner.clone());
+    tokenizer.tokenize();
+    let mut parser = Parser::new(doc.file, &doc.src, &tokenizer.tokens);
+    parser.p_file();
+
+    let src = String::from_utf8_lossy(&doc.src);
+    assert_eq!(doc.tokens, tokenizer.tokens, "tokens differ for {src:?}");
+    assert_eq!(dump_roots(&doc.ast), dump_roots(&parser.ast), "trees differ for {src:?}");
+    assert_eq!(
+        diagnostics::collect(&doc.tokens, &doc.ast),
+        diagnostics::collect(&tokenizer.tokens, &parser.ast),
+        "diagnostics differ for {src:?}"
+    );
+}
+
+fn offset_of(doc: &Document, text: &str) -> usize {
+    String::from_utf8_lossy(&doc.src).find(text).unwrap()
+}
+
+#[test]
+fn edit_reparses_only_the_innermost_block() {
+    let mut doc = Document::new(FileId(0), SOURCE.as_bytes().to_vec());
+    let at = offset_of(&doc, "p + q");
+    let reparsed = doc.edit(at..at + 1, b"pp");
+
+    let start = offset_of(&doc, "{\n        let t");
+    let end = offset_of(&doc, "\n    };") + "\n    }".len();
+    assert_eq!(reparsed, Reparsed::Block(Span::new(FileId(0), start..end)));
+    assert_same_as_full(&doc);
+}
+
+#[test]
+fn block_ids_are_kept() {
+    let mut doc = Document::new(FileId(0), SOURCE.as_bytes().to_vec());
+    let root = doc.ast.roots[0];
+    let at = offset_of(&doc, "s += ");
+    doc.edit(at..at, b"let z = 0;\n    ");
+
+    assert_eq!(doc.ast.roots, vec![root]);
+    assert_same_as_full(&doc);
+}
+
+#[test]
+fn replaced_nodes_are_reused() {
+    let mut doc = Document::new(FileId(0), SOURCE.as_bytes().to_vec());
+    let initial = doc.ast.len();
+    for i in 0..500 {
+        let at = offset_of(&doc, "p + q");
+        // the block grows and shrinks back
+        let text: &[u8] = if i % 2 == 0 { b"p + q + r * (s - t)" } else { b"p + q" };
+        let end = offset_of(&doc, " * 2;");
+        doc.edit(at..end, text);
+        assert!(doc.ast.len() < initial * 2, "{} nodes after {i} edits", doc.ast.len());
+    }
+    assert_same_as_full(&doc);
+}
+
+#[test]
+fn structural_edits_match_full_reparse() {
+    let edits: &[(&str, &str)] = &[
+        // closes the block earlier
+        ("p + q", "} p + q"),
+        // opens a new group
+        ("p + q", "(p + q"),
+        // comments out the rest of the line
+        ("{ k }", "{ k // }"),
+        // unterminated string eats the rest of the file
+        ("\"text\"", "\"text"),
+        ("/ 3;", "/* 3;"),
+        ("x + y", "{{{{{ x + y"),
+        ("//! docs", "//! more docs"),
+        ("{ k }", ""),
+    ];
+    for (from, to) in edits {
+        let mut doc = Document::new(FileId(0), SOURCE.as_bytes().to_vec());
+        let at = offset_of(&doc, from);
+        doc.edit(at..at + from.len(), to.as_bytes());
+        assert_same_as_full(&doc);
+    }
+}
+
+#[test]
+fn random_edits_match_full_reparse() {
+    let mut rng = XorShift(0xed17);
+    let mut blocks = 0;
+    for _ in 0..20 {
+        let mut doc = Document::new(FileId(0), SOURCE.as_bytes().to_vec());
+        for _ in 0..50 {
+            let len = doc.src.len() as u64;
+            let start = (rng.next() % (len + 1)) as usize;
+            let end = (start + (rng.next() % 4) as usize).min(doc.src.len());
+            let fragment = FRAGMENTS[(rng.next() % FRAGMENTS.len() as u64) as usize];
+            if let Reparsed::Block(_) = doc.edit(start..end, fragment.as_bytes()) {
+                blocks += 1;
+            }
+            assert_same_as_full(&doc);
+        }
+    }
+    // most edits land inside of some block
+    assert!(blocks > 200, "only {blocks} edits were incremental");
+}
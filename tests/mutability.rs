@@ -0,0 +1,122 @@
+//! Assignments to `let` bindings and lambda arguments are rejected
+
+mod common;
+
+use esl::{
+    diagnostics::Diagnostic,
+    semantic::{errors::MutabilityError, mutability, resolve::resolve},
+    source::Span,
+};
+
+use common::parse_valid;
+
+fn check(src: &str) -> Vec<(MutabilityError, Span)> {
+    let file = parse_valid(src);
+    mutability::check(&file, &resolve(&file))
+}
+
+#[test]
+fn var_can_be_assigned() {
+    assert!(check("{ var a = 1; a = 2; a += 3; a.b = 4; (a) **= 2; }").is_empty());
+}
+
+#[test]
+fn let_cannot_be_assigned() {
+    let src = "{ let a = 1; a = 2; a <<= 3; a.b.c = 4; }";
+    let errors = check(src);
+    let spans = errors
+        .iter()
+        .map(|(_, span)| &src[span.as_range()])
+        .collect::<Vec<_>>();
+    assert_eq!(spans, ["a = 2", "a <<= 3", "a.b.c = 4"]);
+    for (err, _) in &errors {
+        let MutabilityError::AssignToLet {
+            name,
+            declared_at,
+            keyword,
+        } = err
+        else {
+            panic!("{err:?}");
+        };
+        assert_eq!(&**name, "a");
+        assert_eq!(declared_at.as_range(), 6..7);
+        assert_eq!(&src[keyword.as_range()], "let");
+    }
+}
+
+#[test]
+fn fix_changes_let_to_var() {
+    let src = "{ let count = 0; count += 1; }";
+    let errors = check(src);
+    let [(err, span)] = errors.as_slice() else {
+        panic!("{errors:?}");
+    };
+    let diag = Diagnostic::from_mutability_error(err, *span);
+    assert_eq!(diag.code, "E0301");
+    assert_eq!(&src[diag.primary.span.as_range()], "count += 1");
+    assert_eq!(&src[diag.secondary[0].span.as_range()], "count");
+
+    let fix = &diag.suggestions[0];
+    let mut fixed = src.to_string();
+    fixed.replace_range(fix.span.as_range(), &fix.replacement);
+    assert_eq!(fixed, "{ var count = 0; count += 1; }");
+    assert!(check(&fixed).is_empty());
+}
+
+#[test]
+fn arguments_cannot_be_assigned() {
+    let src = "{ let f = (a, b = 1) -> { a = 2; b -= 1; }; let g = x -> { var x = x; x = 1; }; }";
+    let errors = check(src);
+    let names = errors
+        .iter()
+        .map(|(err, _)| match err {
+            MutabilityError::AssignToArgument { name, .. } => name.to_string(),
+            err => panic!("{err:?}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["a", "b"]);
+
+    let diag = Diagnostic::from_mutability_error(&errors[0].0, errors[0].1);
+    assert_eq!(diag.code, "E0302");
+    assert!(diag.suggestions.is_empty());
+}
+
+#[test]
+fn postfix_targets_change_their_root() {
+    let src = "{ let i = 0; let a = 1; a[i] = 1; a?.x = 2; a.b[i]?.c += 3; a(i).d -= 4; }";
+    let errors = check(src);
+    let spans = errors
+        .iter()
+        .map(|(_, span)| &src[span.as_range()])
+        .collect::<Vec<_>>();
+    assert_eq!(
+        spans,
+        ["a[i] = 1", "a?.x = 2", "a.b[i]?.c += 3", "a(i).d -= 4"]
+    );
+    for (err, _) in &errors {
+        assert!(matches!(err, MutabilityError::AssignToLet { name, .. } if &**name == "a"));
+    }
+
+    let src = "{ let i = 0; var a = 1; a[i] = 1; a?.x = 2; a.b[i]?.c += 3; (a)[i] *= 4; }";
+    assert!(check(src).is_empty());
+}
+
+#[test]
+fn unresolved_and_computed_targets_are_skipped() {
+    assert!(check("{ let a = 1; b = 2; f(a).x = 3; _ = 4; }").is_empty());
+}
+
+#[test]
+fn const_bindings_cannot_be_assigned() {
+    let src = "{ @const let a = 1; a = 2; }";
+    let errors = check(src);
+    let [(err, span)] = errors.as_slice() else {
+        panic!("{errors:?}");
+    };
+    assert!(matches!(err, MutabilityError::AssignToConst { name, .. } if &**name == "a"));
+    let diag = Diagnostic::from_mutability_error(err, *span);
+    assert_eq!(diag.code, "E0303");
+    assert_eq!(&src[diag.secondary[0].span.as_range()], "a");
+    // `@const var` is not allowed, so `var` is not suggested
+    assert!(diag.suggestions.is_empty());
+}
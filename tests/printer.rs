@@ -0,0 +1,213 @@
+//! Printed source parses back into the same tree, up to spans and redundant parentheses
+
+mod common;
+
+use std::path::Path;
+
+use esl::{
+    fuzzing::generate::Generator,
+    parser::{
+        ast::NodeId,
+        export::AstFile,
+        expressions::Operation,
+        nodes::NodeKind,
+        printer::{node_to_source, to_source},
+    },
+};
+
+use common::parse;
+
+// Tree without spans, expressions as nested operations, so grouping of the source is lost
+fn shape(file: &AstFile, src: &str, id: NodeId) -> String {
+    let node = &file.ast[id];
+    let children = |ids: Vec<NodeId>| {
+        ids.into_iter()
+            .map(|child| format!(" {}", shape(file, src, child)))
+            .collect::<String>()
+    };
+    match &node.kind {
+        NodeKind::IntegerLiteral(lit) => lit.value.to_string(),
+        NodeKind::FloatingLiteral(lit) => format!("{:?}", lit.value),
+        NodeKind::StringLiteral(lit) => format!("{:?}", lit.text(src.as_bytes())),
+        NodeKind::BooleanLiteral(lit) => lit.value.to_string(),
+        NodeKind::Identifier(ident) => file.names.resolve(ident.name).to_string(),
+        NodeKind::DontCare(_) => "_".to_string(),
+        NodeKind::Expression(expr) => {
+            let mut stack = Vec::new();
+            for item in &expr.rpn {
+                let NodeKind::Operation(op) = &file.ast[*item].kind else {
+                    stack.push(shape(file, src, *item));
+                    continue;
+                };
+                let name = format!("{op:?}");
+                let name = name.split(' ').next().unwrap_or_default();
+                let operands = match op {
+                    Operation::Neg | Operation::Not | Operation::Ref | Operation::Try => 1,
+                    Operation::FuncCall { .. }
+                    | Operation::PartialCall { .. }
+                    | Operation::ValueCtor { .. }
+                    | Operation::TypeCtor { .. } => 1,
+                    _ => 2,
+                };
+                let operands = stack.split_off(stack.len() - operands).join(" ");
+                stack.push(format!(
+                    "({name} {operands}{})",
+                    children(file.ast.children(*item))
+                ));
+            }
+            assert_eq!(stack.len(), 1, "broken expression {:?}", node.range);
+            stack.pop().unwrap()
+        }
+        NodeKind::ValueDeclaration(decl) => {
+            let keyword = match (decl.constant, decl.mutable) {
+                (true, _) => "@const let",
+                (false, true) => "var",
+                (false, false) => "let",
+            };
+            format!("({keyword}{})", children(file.ast.children(id)))
+        }
+        NodeKind::Block(block) => {
+            let docs = block
+                .docs
+                .iter()
+                .map(|doc| format!(" {}", &src[doc.as_range()]))
+                .collect::<String>();
+            let returns = if block.returns_last { " returns" } else { "" };
+            format!("(Block{returns}{docs}{})", children(file.ast.children(id)))
+        }
+        NodeKind::Assignment(assignment) => {
+            let op = assignment
+                .operation
+                .as_ref()
+                .map_or("=".to_string(), |op| format!("{op:?}="));
+            format!("({op}{})", children(file.ast.children(id)))
+        }
+        NodeKind::Lambda(_) => format!("(Lambda{})", children(file.ast.children(id))),
+        NodeKind::Type(ty) => {
+            let name = format!("{ty:?}");
+            let name = name.split(' ').next().unwrap_or_default();
+            format!("({name}{})", children(file.ast.children(id)))
+        }
+        kind => panic!("unexpected {} at {:?}", kind.name(), node.range),
+    }
+}
+
+fn file_shape(file: &AstFile, src: &str) -> String {
+    let docs = file.ast.docs.iter().map(|doc| format!("{}\n", &src[doc.as_range()]));
+    let roots = file.ast.roots.iter().map(|root| shape(file, src, *root) + "\n");
+    docs.chain(roots).collect()
+}
+
+// Round trip, returns the printed text
+fn check_round_trip(src: &str) -> String {
+    let file = parse(src);
+    let printed = to_source(&file, src.as_bytes());
+    let reparsed = parse(&printed);
+    assert_eq!(
+        file_shape(&file, src),
+        file_shape(&reparsed, &printed),
+        "printed as:\n{printed}"
+    );
+    assert_eq!(to_source(&reparsed, printed.as_bytes()), printed);
+    printed
+}
+
+fn print_expr(expr: &str) -> String {
+    let src = format!("{{ {expr} }}");
+    let file = parse(&src);
+    let NodeKind::Block(block) = &file.ast[file.ast.roots[0]].kind else {
+        panic!("no block in {src}");
+    };
+    check_round_trip(&src);
+    node_to_source(&file, src.as_bytes(), block.items[0])
+}
+
+#[test]
+fn corpus_round_trips() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let mut checked = 0;
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "esl") {
+            continue;
+        }
+        let src = std::fs::read_to_string(&path).unwrap();
+        let file = parse(&src);
+        // errors are copied as is, the tree around them may change
+        if file
+            .ast
+            .ids()
+            .any(|id| matches!(file.ast[id].kind, NodeKind::Error(_)))
+        {
+            continue;
+        }
+        check_round_trip(&src);
+        checked += 1;
+    }
+    assert!(checked >= 5, "only {checked} corpus files without errors");
+}
+
+#[test]
+fn generated_programs_round_trip() {
+    for seed in 0..500 {
+        let src = Generator::new(seed).program();
+        let file = parse(&src);
+        let printed = to_source(&file, src.as_bytes());
+        let reparsed = parse(&printed);
+        assert_eq!(
+            file_shape(&file, &src),
+            file_shape(&reparsed, &printed),
+            "seed {seed}:\n{src}\nprinted as:\n{printed}"
+        );
+    }
+}
+
+#[test]
+fn parentheses_only_where_needed() {
+    let cases = [
+        ("(a + b) * c", "(a + b) * c"),
+        ("(a * b) + c", "a * b + c"),
+        ("a - (b - c)", "a - (b - c)"),
+        ("(a - b) - c", "a - b - c"),
+        ("(a ** b) ** c", "(a ** b) ** c"),
+        ("a ** (b ** c)", "a ** b ** c"),
+        ("-(a?)", "-(a?)"),
+        ("(-a)?", "-a?"),
+        ("-(-a)", "--a"),
+        ("ref (a.b)", "ref (a.b)"),
+        ("(a.b)(c)", "(a.b)(c)"),
+        ("a.(b(c))", "a.b(c)"),
+        ("((((x))))", "x"),
+        ("(1).x", "1 . x"),
+        ("f((x -> x), (y))", "f(x -> x, y)"),
+        ("(x -> x)(1)", "(x -> x)(1)"),
+        ("(x -> x) + 1", "(x -> x) + 1"),
+        ("1 + (x -> x)", "1 + x -> x"),
+        ("(x -> { x }) + 1", "x -> {\n    x\n} + 1"),
+        ("(a: int = 1) -> a", "(a: int = 1) -> a"),
+        ("() -> {}", "() -> {}"),
+        ("(a: (int)) -> a", "(a: int) -> a"),
+        ("(a: (int,)) -> a", "(a: (int,)) -> a"),
+        ("(a: ((int) -> int)?) -> a", "(a: ((int) -> int)?) -> a"),
+        ("(a: (int) -> (int?)) -> a", "(a: (int) -> int?) -> a"),
+        ("(a: (ref int)?) -> a", "(a: (ref int)?) -> a"),
+        ("(a: ref (int?)) -> a", "(a: ref int?) -> a"),
+        (
+            "(a: Map[(int,string), ()]) -> a",
+            "(a: Map[(int, string), ()]) -> a",
+        ),
+        ("Vec[_]{}", "Vec[_]{}"),
+    ];
+    for (src, expected) in cases {
+        assert_eq!(print_expr(src), expected, "{src}");
+    }
+}
+
+#[test]
+fn statements_and_literals() {
+    let src = "{ //! docs\nlet a: List[int]=0xFF; var _ = 1.0; a <<= \"q\\\"\\n\"; b = true; a }\n";
+    assert_eq!(
+        check_round_trip(src),
+        "{\n    //! docs\n    let a: List[int] = 255;\n    var _ = 1.0;\n    a <<= \"q\\\"\\n\";\n    b = true;\n    a\n}\n"
+    );
+}
@@ -0,0 +1,162 @@
+//! Identifier uses linked to their declarations, and binding diagnostics
+
+mod common;
+
+use esl::{
+    diagnostics::{self, Severity},
+    parser::nodes::NodeKind,
+    semantic::{
+        errors::ResolveError,
+        resolve::{Resolution, resolve},
+    },
+};
+
+use common::parse_valid;
+
+// Uses as `name@offset -> offset of the declaration`, in the source order
+fn links(src: &str) -> Vec<String> {
+    let file = parse_valid(src);
+    let resolution = resolve(&file);
+    let mut links = resolution
+        .definitions
+        .iter()
+        .map(|(use_id, def)| {
+            let NodeKind::Identifier(ident) = &file.ast[*use_id].kind else {
+                panic!("{use_id:?} is not an identifier");
+            };
+            let name = file.names.resolve(ident.name);
+            (
+                file.ast[*use_id].range.start,
+                file.ast[*def].range.start,
+                name,
+            )
+        })
+        .collect::<Vec<_>>();
+    links.sort();
+    links
+        .into_iter()
+        .map(|(at, def, name)| format!("{name}@{at} -> {def}"))
+        .collect()
+}
+
+fn errors(src: &str) -> Resolution {
+    resolve(&parse_valid(src))
+}
+
+#[test]
+fn uses_are_linked_to_the_closest_declaration() {
+    //         0         1         2         3         4         5
+    //         0123456789012345678901234567890123456789012345678901234567
+    let src = "{ let a = 1; let f = (b, c = b) -> { a + b + c }; f(a) }";
+    assert_eq!(
+        links(src),
+        [
+            "b@29 -> 22",
+            "a@37 -> 6",
+            "b@41 -> 22",
+            "c@45 -> 25",
+            "f@50 -> 17",
+            "a@52 -> 6",
+        ]
+    );
+}
+
+#[test]
+fn declaration_is_visible_after_its_value() {
+    //         0         1         2         3
+    //         0123456789012345678901234567890123456789
+    let src = "{ let a = 1; let f = () -> { let a = a + 1; a }; }";
+    assert_eq!(links(src), ["a@37 -> 6", "a@44 -> 33"]);
+}
+
+#[test]
+fn lambda_can_call_itself() {
+    let src = "{ let f = x -> f(x); }";
+    assert_eq!(links(src), ["f@15 -> 6", "x@17 -> 10"]);
+}
+
+#[test]
+fn types_and_members_are_not_resolved() {
+    let src = "{ let p: Point = Point{ 1 }; let q: List[int] = p.x::y; io::print(p.len) }";
+    let resolution = errors(src);
+    assert!(resolution.errors.is_empty(), "{:?}", resolution.errors);
+    assert_eq!(resolution.definitions.len(), 2);
+}
+
+#[test]
+fn indexed_assignment_target_is_a_value() {
+    //         0         1         2         3
+    //         0123456789012345678901234567890123
+    let src = "{ var a = 1; a[int] = 2; List[int]; }";
+    assert_eq!(links(src), ["a@13 -> 6"]);
+}
+
+#[test]
+fn unresolved_names_get_suggestions() {
+    let resolution = errors("{ let count = 1; let items = 2; coun + item + zzz + x }");
+    let names = resolution
+        .errors
+        .iter()
+        .map(|(err, _)| match err {
+            ResolveError::Unresolved { name, similar } => {
+                format!("{name}:{}", similar.as_deref().unwrap_or("-"))
+            }
+            err => panic!("unexpected {err:?}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["coun:count", "item:items", "zzz:-", "x:-"]);
+
+    let diag = diagnostics::Diagnostic::from_resolve_error(
+        &resolution.errors[0].0,
+        resolution.errors[0].1,
+    );
+    assert_eq!(diag.code, "E0201");
+    assert_eq!(diag.suggestions[0].replacement, "count");
+    assert_eq!(diag.suggestions[0].span, resolution.errors[0].1);
+}
+
+#[test]
+fn use_before_declaration() {
+    //         0         1         2
+    //         012345678901234567890123456789
+    let src = "{ let f = () -> g; let g = 1; }";
+    let resolution = errors(src);
+    let [(err, span)] = resolution.errors.as_slice() else {
+        panic!("{:?}", resolution.errors);
+    };
+    assert_eq!(span.start, 16);
+    let ResolveError::UsedBeforeDeclaration { name, declared_at } = err else {
+        panic!("{err:?}");
+    };
+    assert_eq!(&**name, "g");
+    assert_eq!(declared_at.start, 23);
+}
+
+#[test]
+fn shadowing_is_a_warning() {
+    //         0         1         2         3
+    //         0123456789012345678901234567890123456789
+    let src = "{ let x = 1; let f = x -> x; var _ = 2; let _ = 3; }";
+    let resolution = errors(src);
+    let [(err, span)] = resolution.errors.as_slice() else {
+        panic!("{:?}", resolution.errors);
+    };
+    assert_eq!(span.start, 21);
+    assert!(matches!(err, ResolveError::Shadowed { previous, .. } if previous.start == 6));
+
+    let diag = diagnostics::Diagnostic::from_resolve_error(err, *span);
+    assert_eq!(diag.severity, Severity::Warning);
+    assert_eq!(diag.code, "W0201");
+    assert_eq!(diag.secondary[0].span.start, 6);
+}
+
+#[test]
+fn scopes_end_with_their_block() {
+    let resolution = errors("{ let f = () -> { let a = 1; }; (x -> x); a + x }");
+    let unresolved = resolution
+        .errors
+        .iter()
+        .filter(|(err, _)| matches!(err, ResolveError::Unresolved { .. }))
+        .count();
+    assert_eq!(unresolved, 2, "{:?}", resolution.errors);
+}
@@ -0,0 +1,99 @@
+//! Line and column lookups of the source map, and their reverse for editor positions
+
+use esl::source::{FileId, LineCol, SourceMap, Span};
+
+fn pos(line: usize, col_utf8: usize, col_utf16: usize) -> LineCol {
+    LineCol {
+        line,
+        col_utf8,
+        col_utf16,
+    }
+}
+
+#[test]
+fn multi_byte_chars() {
+    let mut sources = SourceMap::new();
+    let id = sources.add_file("a.esl", "é = \"ж\";\nx".as_bytes().to_vec());
+    let file = sources.get(id);
+
+    // `é` is 2 bytes and 1 UTF-16 unit
+    assert_eq!(file.line_col(2), pos(0, 2, 1));
+    // `ж` is 2 bytes too, the closing quote comes after it
+    assert_eq!(file.line_col(8), pos(0, 8, 6));
+    assert_eq!(file.line_col(11), pos(1, 0, 0));
+
+    for offset in [0, 2, 3, 5, 6, 8, 9, 10, 11, 12] {
+        let at = file.line_col(offset);
+        assert_eq!(file.offset_utf16(at.line, at.col_utf16), offset);
+    }
+}
+
+#[test]
+fn astral_chars_take_two_utf16_units() {
+    let mut sources = SourceMap::new();
+    let id = sources.add_file("a.esl", "\"😀\" + x".as_bytes().to_vec());
+    let file = sources.get(id);
+
+    assert_eq!(file.line_col(5), pos(0, 5, 3));
+    assert_eq!(file.line_col(9), pos(0, 9, 7));
+    assert_eq!(file.offset_utf16(0, 3), 5);
+    assert_eq!(file.offset_utf16(0, 7), 9);
+    // the middle of the surrogate pair goes to the next char
+    assert_eq!(file.offset_utf16(0, 2), 5);
+}
+
+#[test]
+fn crlf_line_breaks() {
+    let mut sources = SourceMap::new();
+    let id = sources.add_file("a.esl", b"ab\r\ncd\r\n".to_vec());
+    let file = sources.get(id);
+
+    assert_eq!(file.line_count(), 3);
+    assert_eq!(file.line_range(0), 0..2);
+    assert_eq!(file.line_range(1), 4..6);
+    assert_eq!(file.line_col(5), pos(1, 1, 1));
+    // past the end of line stops before the `\r`
+    assert_eq!(file.offset_utf16(0, 10), 2);
+    assert_eq!(file.offset_utf16(1, 1), 5);
+}
+
+#[test]
+fn positions_past_the_end() {
+    let mut sources = SourceMap::new();
+    let id = sources.add_file("a.esl", b"ab\ncd".to_vec());
+    let file = sources.get(id);
+
+    assert_eq!(file.line_col(5), pos(1, 2, 2));
+    assert_eq!(file.line_col(100), pos(1, 2, 2));
+    assert_eq!(file.offset_utf16(1, 100), 5);
+    assert_eq!(file.offset_utf16(7, 0), 5);
+}
+
+#[test]
+fn invalid_utf8_does_not_shift_offsets() {
+    let mut sources = SourceMap::new();
+    let id = sources.add_file("a.esl", b"a\x80b\xC0c d".to_vec());
+    let file = sources.get(id);
+
+    // a lone continuation byte takes no units and a lone lead byte takes one
+    assert_eq!(file.line_col(4), pos(0, 4, 3));
+    assert_eq!(file.offset_utf16(0, 1), 2);
+    assert_eq!(file.offset_utf16(0, 3), 4);
+    assert_eq!(file.offset_utf16(0, 5), 6);
+}
+
+#[test]
+fn spans_of_a_second_file() {
+    let mut sources = SourceMap::new();
+    let first = sources.add_file("a.esl", b"let a = 1;".to_vec());
+    let second = sources.add_file("dir/b.esl", b"\n  let b = 2;".to_vec());
+    assert_eq!(first, FileId(0));
+    assert_eq!(second, FileId(1));
+    assert_eq!(sources.files().count(), 2);
+
+    let span = Span::new(second, 7..8);
+    assert_eq!(sources.source(span), b"b");
+    assert_eq!(sources.lookup(span), (pos(1, 6, 6), pos(1, 7, 7)));
+    assert_eq!(sources.describe(span), "dir/b.esl:2:7");
+    assert_eq!(sources.describe(Span::new(first, 4..5)), "a.esl:1:5");
+}
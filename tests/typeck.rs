@@ -0,0 +1,192 @@
+//! Types of bindings are inferred and checked against hints
+
+mod common;
+
+use esl::{
+    diagnostics::Diagnostic,
+    parser::{export::AstFile, nodes::NodeKind},
+    semantic::{errors::TypeError, resolve::resolve, typeck, typeck::Typing},
+    source::Span,
+};
+
+use common::parse_valid;
+
+fn check(src: &str) -> (AstFile, Typing) {
+    let file = parse_valid(src);
+    let typing = typeck::check(&file, &resolve(&file));
+    (file, typing)
+}
+
+fn errors(src: &str) -> Vec<(TypeError, Span)> {
+    check(src).1.errors
+}
+
+// Type of the first `let` or `var` binding called `name`
+fn binding(src: &str, name: &str) -> String {
+    let (file, typing) = check(src);
+    assert!(typing.errors.is_empty(), "{:?}", typing.errors);
+    let ast = &file.ast;
+    let id = ast
+        .ids()
+        .find(|id| match &ast[*id].kind {
+            NodeKind::ValueDeclaration(decl) => match &ast[decl.declaration].kind {
+                NodeKind::Identifier(ident) => file.names.resolve(ident.name) == name,
+                _ => false,
+            },
+            _ => false,
+        })
+        .and_then(|id| match &ast[id].kind {
+            NodeKind::ValueDeclaration(decl) => Some(decl.declaration),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("no binding {name}"));
+    typing.type_of(id).expect("binding has a type").to_string()
+}
+
+#[test]
+fn literals_and_hints() {
+    let src = "{ let a = 1; let b: float = 1.5; let c = \"c\"; let d = true; var e: int = a; }";
+    assert_eq!(binding(src, "a"), "int");
+    assert_eq!(binding(src, "b"), "float");
+    assert_eq!(binding(src, "c"), "string");
+    assert_eq!(binding(src, "d"), "bool");
+    assert_eq!(binding(src, "e"), "int");
+}
+
+#[test]
+fn lambda_types_are_inferred() {
+    let src = "{ let add = (a, b) -> a + b; let x = add(1, 2); let neg = (f) -> f(1.5) && true; }";
+    assert_eq!(binding(src, "add"), "(int, int) -> int");
+    assert_eq!(binding(src, "x"), "int");
+    assert_eq!(binding(src, "neg"), "((float) -> bool) -> bool");
+    let src = "{ let twice = (f, x) -> f(f(x)); let s = twice(s -> s + \"!\", \"a\"); }";
+    assert_eq!(binding(src, "s"), "string");
+}
+
+#[test]
+fn let_lambdas_are_polymorphic() {
+    let src = "{ let id = x -> x; let a = id(1); let b = id(\"b\"); let c = id(id)(true); }";
+    assert_eq!(binding(src, "a"), "int");
+    assert_eq!(binding(src, "b"), "string");
+    assert_eq!(binding(src, "c"), "bool");
+    // a var can be reassigned, so its type stays the same
+    assert_eq!(errors("{ var id = x -> x; id(1); id(\"b\"); }").len(), 1);
+}
+
+#[test]
+fn mismatch_points_at_hint() {
+    let src = "{ let a: string = 1 + 2; }";
+    let errors = errors(src);
+    let [(err, span)] = errors.as_slice() else {
+        panic!("{errors:?}");
+    };
+    let TypeError::Mismatch {
+        expected,
+        found,
+        expected_at,
+    } = err
+    else {
+        panic!("{err:?}");
+    };
+    assert_eq!(
+        (expected.to_string(), found.to_string()),
+        ("string".into(), "int".into())
+    );
+    assert_eq!(&src[span.as_range()], "1 + 2");
+    assert_eq!(&src[expected_at.unwrap().as_range()], "string");
+
+    let diag = Diagnostic::from_type_error(err, *span);
+    assert_eq!(diag.code, "E0401");
+    assert_eq!(&src[diag.secondary[0].span.as_range()], "string");
+}
+
+#[test]
+fn argument_mismatch_points_at_argument() {
+    let src = "{ let add = (a, b) -> a + b; add(1.5, 2); }";
+    let errors = errors(src);
+    let [(TypeError::Mismatch { .. }, span)] = errors.as_slice() else {
+        panic!("{errors:?}");
+    };
+    assert_eq!(&src[span.as_range()], "2");
+}
+
+#[test]
+fn calls() {
+    let codes = |src| {
+        errors(src)
+            .iter()
+            .map(|(err, _)| err.code())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(codes("{ let f = x -> x; f(1, 2); }"), ["E0402"]);
+    assert_eq!(codes("{ 1(2); }"), ["E0403"]);
+    assert_eq!(codes("{ let w = x -> x(x); }"), ["E0405"]);
+    assert_eq!(
+        codes("{ let f = (g) -> g(1) && true; f(x -> x); }"),
+        ["E0401"]
+    );
+}
+
+#[test]
+fn operators_need_supported_operands() {
+    let errors = errors("{ let s = \"a\" - \"b\"; let t = 1.5 << 2; }");
+    let found = errors
+        .iter()
+        .map(|(err, _)| match err {
+            TypeError::UnsupportedOperand { op, found } => format!("{op} {found}"),
+            err => panic!("{err:?}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(found, ["- string", "<< float"]);
+    assert!(
+        self::errors("{ let s = \"a\" + \"b\"; let c = 1.5 < 2.5; let b = true & false; }")
+            .is_empty()
+    );
+}
+
+#[test]
+fn hints_of_every_type_form() {
+    let src = "{ let f = (a: (int, string), b: ref int?, c: ((int) -> _)?, d: ()) -> d; }";
+    assert_eq!(
+        binding(src, "f"),
+        "((int, string), ref int?, ((int) -> _)?, ()) -> ()"
+    );
+
+    let src = "{ let l: List[int] = 3; let r: ref int = ref \"s\"; }";
+    let messages = errors(src)
+        .iter()
+        .map(|(err, _)| err.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "Mismatched types, expected `List[int]`, found `int`",
+            "Mismatched types, expected `ref int`, found `ref string`",
+        ]
+    );
+}
+
+#[test]
+fn integer_literals_take_sized_types() {
+    let src =
+        "{ let a: u8 = 1; let b = a + 2; let c = 3; let d = -4 * c; let e: i128 = 1 << 100; }";
+    assert_eq!(binding(src, "a"), "u8");
+    assert_eq!(binding(src, "b"), "u8");
+    assert_eq!(binding(src, "c"), "int");
+    assert_eq!(binding(src, "d"), "int");
+    assert_eq!(binding(src, "e"), "i128");
+
+    let src = "{ let a: u8 = 1; let b: int = a; let f: float = 1; let s: i32 = \"s\"; }";
+    let messages = errors(src)
+        .iter()
+        .map(|(err, _)| err.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "Mismatched types, expected `int`, found `u8`",
+            "Mismatched types, expected `float`, found `int`",
+            "Mismatched types, expected `i32`, found `string`",
+        ]
+    );
+}
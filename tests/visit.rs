@@ -0,0 +1,236 @@
+//! Default walks of the traversal traits, pruning by overridden hooks and rewriting with Fold
+
+mod common;
+
+use std::collections::BTreeSet;
+
+use esl::{
+    parser::{
+        ast::{Ast, NodeId},
+        export::AstFile,
+        expressionables::Lambda,
+        expressions::Operation,
+        nodes::NodeKind,
+        primitives::{BooleanLiteral, Identifier},
+        visit::{Fold, Visitor, VisitorMut, walk_node, walk_node_mut},
+    },
+    source::interner::Symbol,
+};
+
+use common::parse;
+
+/// Has every node kind, `let e = ;` gives the error node
+const EVERY_KIND: &str = "{
+    let a: List[int] = -1 + 2.5;
+    var s = \"text\";
+    s = f(true, _);
+    let l = (x) -> { x * 2 };
+    let e = ;
+}";
+
+const ALL_KINDS: &[&str] = &[
+    "Assignment",
+    "Block",
+    "BooleanLiteral",
+    "DontCare",
+    "Error",
+    "Expression",
+    "FloatingLiteral",
+    "Identifier",
+    "IntegerLiteral",
+    "Lambda",
+    "Operation",
+    "StringLiteral",
+    "Type",
+    "ValueDeclaration",
+];
+
+fn kinds(ast: &Ast, ids: &[NodeId]) -> BTreeSet<&'static str> {
+    ids.iter().map(|id| ast[*id].kind.name()).collect()
+}
+
+/// `(Kind payload children...)` on one line
+fn render(file: &AstFile, id: NodeId) -> String {
+    let ast = &file.ast;
+    let mut out = format!("({}", ast[id].kind.name());
+    match &ast[id].kind {
+        NodeKind::Identifier(ident) => out += &format!(" {}", file.names.resolve(ident.name)),
+        NodeKind::IntegerLiteral(lit) => out += &format!(" {}", lit.value),
+        NodeKind::BooleanLiteral(lit) => out += &format!(" {}", lit.value),
+        NodeKind::Operation(op) => out += &format!(" {op:?}"),
+        _ => (),
+    }
+    ast[id].visit_children(|c| out += &format!(" {}", render(file, c)));
+    out + ")"
+}
+
+fn render_all(file: &AstFile) -> Vec<String> {
+    file.ast.roots.iter().map(|root| render(file, *root)).collect()
+}
+
+fn names(file: &AstFile, ids: &[NodeId]) -> Vec<String> {
+    ids.iter()
+        .filter_map(|id| match &file.ast[*id].kind {
+            NodeKind::Identifier(ident) => Some(file.names.resolve(ident.name).to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Collect {
+    visited: Vec<NodeId>,
+    skip_lambdas: bool,
+}
+
+impl<'a> Visitor<'a> for Collect {
+    fn visit_node(&mut self, ast: &'a Ast, id: NodeId) {
+        self.visited.push(id);
+        walk_node(self, ast, id);
+    }
+    fn visit_lambda(&mut self, ast: &'a Ast, _id: NodeId, lambda: &'a Lambda) {
+        if !self.skip_lambdas {
+            lambda.visit_children(|c| self.visit_node(ast, c));
+        }
+    }
+}
+
+#[test]
+fn visitor_reaches_every_node_kind() {
+    let file = parse(EVERY_KIND);
+    let mut collect = Collect::default();
+    for root in &file.ast.roots {
+        collect.visit_node(&file.ast, *root);
+    }
+    assert_eq!(
+        kinds(&file.ast, &collect.visited),
+        ALL_KINDS.iter().copied().collect()
+    );
+    // each node once
+    let unique = collect.visited.iter().collect::<BTreeSet<_>>();
+    assert_eq!(unique.len(), collect.visited.len());
+    assert_eq!(unique.len(), file.ast.len());
+}
+
+#[test]
+fn visitor_hook_without_walk_skips_the_subtree() {
+    let file = parse(EVERY_KIND);
+    let mut collect = Collect {
+        skip_lambdas: true,
+        ..Default::default()
+    };
+    for root in &file.ast.roots {
+        collect.visit_node(&file.ast, *root);
+    }
+    // the lambda itself is visited, its argument and body are not
+    assert!(kinds(&file.ast, &collect.visited).contains("Lambda"));
+    assert_eq!(
+        names(&file, &collect.visited),
+        ["a", "List", "int", "s", "s", "f", "l", "e"]
+    );
+}
+
+/// Renames identifiers outside of lambdas
+struct Rename {
+    from: Symbol,
+    to: Symbol,
+    visited: Vec<NodeId>,
+}
+
+impl VisitorMut for Rename {
+    fn visit_node(&mut self, ast: &mut Ast, id: NodeId) {
+        self.visited.push(id);
+        walk_node_mut(self, ast, id);
+    }
+    fn visit_identifier(&mut self, _ast: &mut Ast, _id: NodeId, ident: &mut Identifier) {
+        if ident.name == self.from {
+            ident.name = self.to;
+        }
+    }
+    fn visit_lambda(&mut self, _ast: &mut Ast, _id: NodeId, _lambda: &mut Lambda) {}
+}
+
+#[test]
+fn visitor_mut_reaches_every_node_kind_and_keeps_the_tree() {
+    struct Count(Vec<NodeId>);
+    impl VisitorMut for Count {
+        fn visit_node(&mut self, ast: &mut Ast, id: NodeId) {
+            self.0.push(id);
+            walk_node_mut(self, ast, id);
+        }
+    }
+
+    let mut file = parse(EVERY_KIND);
+    let before = render_all(&file);
+    let mut count = Count(Vec::new());
+    for root in file.ast.roots.clone() {
+        count.visit_node(&mut file.ast, root);
+    }
+    assert_eq!(
+        kinds(&file.ast, &count.0),
+        ALL_KINDS.iter().copied().collect()
+    );
+    assert_eq!(count.0.len(), file.ast.len());
+    // payloads are put back after their hooks
+    assert_eq!(render_all(&file), before);
+}
+
+#[test]
+fn visitor_mut_changes_payloads_and_skips_pruned_subtrees() {
+    let mut file = parse("{ x = x + 1; let f = (x) -> { x }; }");
+    let mut rename = Rename {
+        from: file.names.get("x").unwrap(),
+        to: file.names.intern("y"),
+        visited: Vec::new(),
+    };
+    for root in file.ast.roots.clone() {
+        rename.visit_node(&mut file.ast, root);
+    }
+    let all = file.ast.ids().collect::<Vec<_>>();
+    assert_eq!(names(&file, &all), ["y", "y", "f", "x", "x"]);
+    assert!(rename.visited.len() < file.ast.len());
+}
+
+/// `yes` becomes `true` and `+` becomes `-`, the lambda body is left alone
+struct Rewrite {
+    yes: Symbol,
+}
+
+impl Fold for Rewrite {
+    fn fold_identifier(&mut self, _ast: &mut Ast, _id: NodeId, ident: Identifier) -> NodeKind {
+        if ident.name == self.yes {
+            NodeKind::BooleanLiteral(BooleanLiteral { value: true })
+        } else {
+            NodeKind::Identifier(ident)
+        }
+    }
+    fn fold_operation(&mut self, _ast: &mut Ast, _id: NodeId, op: Operation) -> NodeKind {
+        match op {
+            Operation::Add => NodeKind::Operation(Operation::Sub),
+            op => NodeKind::Operation(op),
+        }
+    }
+    fn fold_lambda(&mut self, _ast: &mut Ast, _id: NodeId, lambda: Lambda) -> NodeKind {
+        NodeKind::Lambda(lambda)
+    }
+}
+
+#[test]
+fn fold_rewrites_the_tree() {
+    let mut file = parse("{ let a = yes + b; let f = () -> { yes + 1 }; }");
+    let mut rewrite = Rewrite {
+        yes: file.names.get("yes").unwrap(),
+    };
+    for root in file.ast.roots.clone() {
+        rewrite.fold_node(&mut file.ast, root);
+    }
+    assert_eq!(
+        render_all(&file),
+        ["(Block \
+            (ValueDeclaration (Identifier a) \
+                (Expression (BooleanLiteral true) (Identifier b) (Operation Sub))) \
+            (ValueDeclaration (Identifier f) \
+                (Expression (Lambda (Block \
+                    (Expression (Identifier yes) (IntegerLiteral 1) (Operation Add)))))))"]
+    );
+}